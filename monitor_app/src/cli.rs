@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 pub struct Cli {
@@ -9,9 +10,318 @@ pub struct Cli {
 #[derive(Subcommand)]
 pub enum Commands {
     AddServer,      
-    RunServer,      
+    //bez nazvu sa opyta, prechody idu cez rovnake prikazy ako v TUI
+    RunServer {
+        name: Option<String>,
+    },
+    StopServer {
+        name: Option<String>,
+    },
+    RestartServer {
+        name: Option<String>,
+    },
+    //zopakuje prechod, ktory skoncil ako FAILED
+    RetryServer {
+        name: Option<String>,
+    },
+    //FAILED -> stav pred prechodom
+    ResetServer {
+        name: Option<String>,
+    },
+    //novy server ("/") -> OFF
+    ActivateServer {
+        name: Option<String>,
+    },
     UpdateServer,   
     RemoveServer,  
     ListServer,
-    Gui,
-}
\ No newline at end of file
+    //stav jedneho servera, meranie a posledne prechody
+    Status {
+        name: String,
+    },
+    SetSource,
+    SetInterval,
+    AddCpu,
+    RemoveCpu,
+    ListCpu,
+    //kedy servery dosiahnu 90% / 100% max_ram podla trendu
+    Forecast {
+        //dlzka historie pre trend v hodinach
+        #[arg(long, default_value_t = 6)]
+        hours: i64,
+    },
+    //tag web1 prod eu, bez tagov vypise existujuce
+    Tag {
+        name: String,
+        tags: Vec<String>,
+        #[arg(long)]
+        remove: bool,
+    },
+    //depend app mongo: app potrebuje mongo; bez nazvu vypise vsetky zavislosti
+    Depend {
+        name: Option<String>,
+        upstream: Vec<String>,
+        #[arg(long)]
+        remove: bool,
+    },
+    //prikazy na spustenie / zastavenie servera, bez nazvu vypise vsetky
+    //napr. lifecycle web --unit nginx alebo lifecycle app --start "./run.sh" --stop "pkill app"
+    Lifecycle {
+        name: Option<String>,
+        //systemd sluzba, ma prednost pred prikazmi
+        #[arg(long)]
+        unit: Option<String>,
+        #[arg(long)]
+        start: Option<String>,
+        #[arg(long)]
+        stop: Option<String>,
+        #[arg(long)]
+        restart: Option<String>,
+        //limit na prikazy aj cakanie na sondu
+        #[arg(long)]
+        timeout: Option<i32>,
+        //--wait-probe false: start necaka na zdravu sluzbu, true to vrati
+        #[arg(long)]
+        wait_probe: Option<bool>,
+        #[arg(long)]
+        remove: bool,
+    },
+    //report dostupnosti voci SLO, pripadne pridanie / zmazanie ciela
+    Slo {
+        #[command(subcommand)]
+        action: Option<SloAction>,
+    },
+    //pravidla alertov, bez akcie vypise zoznam
+    Rule {
+        #[command(subcommand)]
+        action: Option<RuleAction>,
+    },
+    //aktivne alerty, s --history vyriesene
+    Alerts {
+        #[arg(long)]
+        history: bool,
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+    //potvrdenie alertu, ze sa riesi
+    Ack {
+        id: i32,
+        #[arg(long, default_value = "")]
+        note: String,
+        //predvolene $USER
+        #[arg(long)]
+        by: Option<String>,
+    },
+    //eskalacne politiky, bez akcie vypise zoznam
+    Escalation {
+        #[command(subcommand)]
+        action: Option<EscalationAction>,
+    },
+    //kanaly notifikacii (webhook, smtp, exec), bez akcie vypise zoznam
+    Channel {
+        #[command(subcommand)]
+        action: Option<ChannelAction>,
+    },
+    //okna udrzby, bez akcie vypise zoznam
+    Maint {
+        #[command(subcommand)]
+        action: Option<MaintAction>,
+    },
+    //stisenie alertov, bez akcie vypise platne
+    Silence {
+        #[command(subcommand)]
+        action: Option<SilenceAction>,
+    },
+    //sablony notifikacii, bez nazvu vypise vsetky
+    Template {
+        //napr. template_subject
+        name: Option<String>,
+        //nova sablona zo suboru
+        #[arg(long)]
+        file: Option<PathBuf>,
+        //vrati predvolenu sablonu
+        #[arg(long)]
+        reset: bool,
+    },
+    //napr. config tls_warn_days 30
+    Config {
+        key: Option<String>,
+        value: Option<String>,
+    },
+    Gui {
+        //rovnaky seed = rovnaky priebeh simulovanych metrik
+        #[arg(long)]
+        seed: Option<u64>,
+        //subor so scenarom incidentov (.toml / .json)
+        #[arg(long)]
+        scenario: Option<PathBuf>,
+        //iba cita historiu, ktoru zapisuje daemon (automaticky, ak daemon bezi)
+        #[arg(long)]
+        viewer: bool,
+        //pidfile daemona, podla neho sa zisti, ci uz bezi
+        #[arg(long, default_value = crate::daemon::DEFAULT_PIDFILE)]
+        pidfile: PathBuf,
+        //adresa, na ktorej sa prijimaju reporty agentov (napr. 0.0.0.0:7070)
+        #[arg(long)]
+        agent_listen: Option<String>,
+    },
+    Daemon {
+        #[arg(long, default_value = crate::daemon::DEFAULT_PIDFILE)]
+        pidfile: PathBuf,
+        #[arg(long)]
+        log_file: Option<PathBuf>,
+        #[arg(long)]
+        seed: Option<u64>,
+        #[arg(long)]
+        scenario: Option<PathBuf>,
+        #[arg(long, default_value_t = 8)]
+        max_concurrency: usize,
+        #[arg(long)]
+        agent_listen: Option<String>,
+    },
+    Agent {
+        //adresa collectora (host:port)
+        #[arg(long)]
+        collector: String,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        token: String,
+        #[arg(long, default_value_t = 1000)]
+        interval_ms: u64,
+        //lokalny port sluzby pre meranie odozvy
+        #[arg(long)]
+        port: Option<u16>,
+        //pocet merani odozvy v jednom reporte
+        #[arg(long, default_value_t = 5)]
+        samples: usize,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SloAction {
+    //slo add tag:prod 99.9 --days 30
+    Add {
+        //"server:<nazov>", "tag:<tag>" alebo samotny nazov servera
+        scope: String,
+        objective: f32,
+        #[arg(long, default_value_t = 30)]
+        days: i32,
+    },
+    Remove {
+        id: i32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum RuleAction {
+    //rule add cpu-high all "cpu_usage > 90 for 5m"
+    Add {
+        name: String,
+        //"all", "server:<nazov>" alebo "tag:<tag>"
+        scope: String,
+        expr: String,
+        //nazvy kanalov oddelene ciarkou, prazdne = vsetky
+        #[arg(long, default_value = "")]
+        channels: String,
+        //critical, warning alebo info
+        #[arg(long, default_value = "warning")]
+        severity: String,
+    },
+    Remove {
+        id: i32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ChannelAction {
+    //channel add ops webhook http://hooks.local/alert
+    //channel add mail smtp localhost:25 --to ops@firma.sk
+    //channel add pager exec "/usr/local/bin/page.sh"
+    Add {
+        name: String,
+        //webhook, smtp alebo exec
+        kind: String,
+        //URL, "host:port" SMTP servera alebo prikaz pre sh -c
+        target: String,
+        #[arg(long)]
+        from: Option<String>,
+        //adresati oddeleni ciarkou
+        #[arg(long)]
+        to: Option<String>,
+    },
+    Remove {
+        id: i32,
+    },
+    //posle skusobnu notifikaciu
+    Test {
+        name: String,
+    },
+    //posledne pokusy o dorucenie
+    Log {
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum MaintAction {
+    //maint add tag:db "2026-11-01 02:00" 2h --recur weekly --comment "zaplaty"
+    Add {
+        //"all", "server:<nazov>", "tag:<tag>" alebo samotny nazov servera
+        scope: String,
+        //"YYYY-MM-DD HH:MM" v UTC alebo "now"
+        start: String,
+        //napr. 30m, 2h, 1h30m
+        duration: String,
+        //daily alebo weekly
+        #[arg(long, default_value = "")]
+        recur: String,
+        #[arg(long, default_value = "")]
+        comment: String,
+    },
+    Remove {
+        id: i32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SilenceAction {
+    //silence add "rule=cpu-high,server=web*" 2h --comment "migracia"
+    Add {
+        //rule=, server=, tag=, hodnota moze koncit '*'
+        matcher: String,
+        duration: String,
+        //predvolene $USER
+        #[arg(long)]
+        author: Option<String>,
+        #[arg(long, default_value = "")]
+        comment: String,
+    },
+    //ukonci stisenie pred vyprsanim
+    Remove {
+        id: i32,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum EscalationAction {
+    //escalation add prod tag:prod --every 15 --tiers "mail;pager,sms" --repeat 3
+    Add {
+        name: String,
+        //"all", "server:<nazov>" alebo "tag:<tag>"
+        scope: String,
+        //minuty bez potvrdenia medzi urovnami
+        #[arg(long, default_value_t = 15)]
+        every: i32,
+        //urovne oddelene ';', kanaly v urovni ','
+        #[arg(long)]
+        tiers: String,
+        //kolkokrat sa posledna uroven zopakuje
+        #[arg(long, default_value_t = 0)]
+        repeat: i32,
+    },
+    Remove {
+        id: i32,
+    },
+}
@@ -0,0 +1,155 @@
+//zber metrik na pozadi bez GUI
+
+use Monitor_Lib::agent::AgentListener;
+use Monitor_Lib::db::establish_connection;
+use Monitor_Lib::engine::Simulator;
+use Monitor_Lib::lifecycle;
+use Monitor_Lib::pipeline::Pipeline;
+use Monitor_Lib::scenario::Scenario;
+use Monitor_Lib::scheduler::{Sampler, Scheduler, SchedulerConfig};
+use chrono::Local;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::broadcast::error::RecvError;
+
+//bez --pidfile, aby GUI vedelo zistit bezuci daemon
+pub const DEFAULT_PIDFILE: &str = "monitor.pid";
+
+//dlhsie sa na dorucenie notifikacii pri ukonceni neciaka
+const SHUTDOWN_WAIT: Duration = Duration::from_secs(30);
+
+pub struct DaemonOptions {
+    pub pidfile: PathBuf,
+    pub log_file: Option<PathBuf>,
+    pub scenario: Option<PathBuf>,
+    pub max_concurrency: usize,
+    pub agent_listen: Option<String>,
+}
+
+//log do suboru alebo na stdout, po SIGHUP sa subor znova otvori (logrotate)
+struct DaemonLog {
+    path: Option<PathBuf>,
+    file: Option<File>,
+}
+
+impl DaemonLog {
+    fn open(path: Option<PathBuf>) -> std::io::Result<Self> {
+        let mut log = DaemonLog { path, file: None };
+        log.reopen()?;
+        Ok(log)
+    }
+
+    fn reopen(&mut self) -> std::io::Result<()> {
+        if let Some(p) = &self.path {
+            self.file = Some(OpenOptions::new().create(true).append(true).open(p)?);
+        }
+        Ok(())
+    }
+
+    fn line(&mut self, msg: &str) {
+        let line = format!("[{}] {}", Local::now().format("%Y-%m-%d %H:%M:%S"), msg);
+        match self.file.as_mut() {
+            Some(f) => { let _ = writeln!(f, "{}", line); }
+            None => println!("{}", line),
+        }
+    }
+}
+
+//PID z pidfile, iba ak proces zije a je to tento program (PID mohol dostat iny proces)
+pub fn running_pid(path: &Path) -> Option<u32> {
+    let pid: u32 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    if pid == std::process::id() {
+        return None;
+    }
+    let comm = fs::read_to_string(format!("/proc/{}/comm", pid)).ok()?;
+    let own = fs::read_to_string("/proc/self/comm").ok()?;
+    (comm.trim() == own.trim()).then_some(pid)
+}
+
+fn write_pidfile(path: &Path) -> Result<(), String> {
+    if let Some(pid) = running_pid(path) {
+        return Err(format!("Daemon už beží (PID {})", pid));
+    }
+    fs::write(path, format!("{}\n", std::process::id())).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn load_scenario(path: Option<&Path>) -> Result<Option<Scenario>, String> {
+    path.map(Scenario::load).transpose()
+}
+
+pub async fn run(opts: DaemonOptions, sim: Simulator) -> Result<(), Box<dyn std::error::Error>> {
+    let mut log = DaemonLog::open(opts.log_file.clone())?;
+    write_pidfile(&opts.pidfile)?;
+
+    let sampler = Arc::new(Sampler::new(sim));
+    sampler.simulator().lock().unwrap().set_scenario(load_scenario(opts.scenario.as_deref())?);
+
+    let agents = match &opts.agent_listen {
+        Some(addr) => {
+            let listener = AgentListener::bind(addr).await?;
+            sampler.set_agent_cache(listener.cache());
+            log.line(&format!("Agenti: počúvam na {}", listener.local_addr()));
+            Some(listener)
+        }
+        None => None,
+    };
+
+    let config = SchedulerConfig { max_concurrency: opts.max_concurrency, ..SchedulerConfig::default() };
+    let scheduler = Scheduler::spawn(config, sampler.clone());
+    let mut samples = scheduler.subscribe();
+    let mut pipeline = Pipeline::new()?;
+    //prechody po termine sa kontroluju aj bez otvoreneho TUI
+    let mut conn = establish_connection()?;
+    let mut expire = tokio::time::interval(Duration::from_secs(5));
+
+    let mut sigterm = signal(SignalKind::terminate())?;
+    let mut sigint = signal(SignalKind::interrupt())?;
+    let mut sighup = signal(SignalKind::hangup())?;
+
+    log.line(&format!("Daemon spustený (PID {})", std::process::id()));
+
+    loop {
+        tokio::select! {
+            received = samples.recv() => match received {
+                Ok(sample) => match pipeline.process(&sample) {
+                    Ok(lines) => lines.iter().for_each(|l| log.line(l)),
+                    Err(e) => log.line(&format!("ERROR: zápis vzorky {}: {}", sample.server.name, e)),
+                },
+                Err(RecvError::Lagged(n)) => log.line(&format!("ERROR: zahodených {} vzoriek", n)),
+                Err(RecvError::Closed) => break,
+            },
+            _ = expire.tick() => match lifecycle::expire_transitions(&mut conn) {
+                Ok(lines) => lines.iter().for_each(|l| log.line(l)),
+                Err(e) => log.line(&format!("ERROR: kontrola prechodov: {}", e)),
+            },
+            _ = sigterm.recv() => { log.line("SIGTERM, ukončujem"); break; }
+            _ = sigint.recv() => { log.line("SIGINT, ukončujem"); break; }
+            _ = sighup.recv() => {
+                if let Err(e) = log.reopen() {
+                    eprintln!("Chyba logu: {}", e);
+                }
+                match load_scenario(opts.scenario.as_deref()) {
+                    Ok(sc) => {
+                        sampler.simulator().lock().unwrap().set_scenario(sc);
+                        log.line("SIGHUP, konfigurácia znova načítaná");
+                    }
+                    Err(e) => log.line(&format!("ERROR: scenár: {}", e)),
+                }
+            }
+        }
+    }
+
+    scheduler.stop();
+    drop(agents);
+    match pipeline.shutdown(SHUTDOWN_WAIT).await {
+        0 => {}
+        n => log.line(&format!("ERROR: {} notifikácií sa nestihlo doručiť", n)),
+    }
+    fs::remove_file(&opts.pidfile).ok();
+    log.line("Daemon ukončený");
+    Ok(())
+}
@@ -1,11 +1,28 @@
 mod cli;
+mod daemon;
 mod ui;
 
 use clap::Parser;
-use cli::{Cli, Commands};
-use Monitor_Lib::db::{establish_connection, get_all_servers, update_status, add_server, remove_server};
-use Monitor_Lib::engine::simulate_server_metrics;
-use Monitor_Lib::models::Server;
+use cli::{ChannelAction, Cli, Commands, EscalationAction, MaintAction, RuleAction, SilenceAction, SloAction};
+use Monitor_Lib::db::{establish_connection, get_all_servers, add_server, remove_server, get_cpu_profiles, save_cpu_profile, remove_cpu_profile, update_source, update_interval, get_latest_history, save_prometheus_source, set_agent_token, update_probe, setting_or, get_setting, set_setting, remove_setting, get_settings, get_events, add_tag, remove_tag, get_tags, add_slo_target, remove_slo_target, get_alert_rules, add_alert_rule, remove_alert_rule, get_active_alerts, get_channels, add_channel, remove_channel, get_deliveries, get_maintenance_windows, add_maintenance_window, remove_maintenance_window, get_silences, add_silence, expire_silence, get_alert, ack_alert, get_escalation_policies, add_escalation_policy, remove_escalation_policy, get_flap_states, add_dependency, remove_dependency, get_dependencies, get_lifecycle_commands, get_lifecycle_command, get_recent_server_events, get_transition, get_transitions, save_lifecycle_command, remove_lifecycle_command};
+use Monitor_Lib::engine::Simulator;
+use Monitor_Lib::scenario::Scenario;
+use Monitor_Lib::agent::{run_agent, AgentListener, AgentOptions};
+use Monitor_Lib::scheduler::{Sampler, Scheduler, SchedulerConfig};
+use Monitor_Lib::models::{CpuProfile, LifecycleCommand, Metrics, PrometheusSource, Scope, Server};
+use Monitor_Lib::probe::{ProbeKind, ProbeStatus};
+use Monitor_Lib::tls::TlsInfo;
+use Monitor_Lib::anomaly::{AnomalyConfig, AnomalyDetector, AnomalyMetric};
+use Monitor_Lib::forecast::{fmt_eta, server_forecast, RamForecast};
+use Monitor_Lib::slo::{self, SloReport};
+use Monitor_Lib::rules::Condition;
+use Monitor_Lib::alerts::{alert_views, AlertView, Severity};
+use Monitor_Lib::flap::{FlapConfig, FlapDetector};
+use Monitor_Lib::deps::DependencyGraph;
+use Monitor_Lib::lifecycle::{self, Action};
+use Monitor_Lib::notify::{deliver, smtp_config, Digest, DigestItem, GroupBy, Notification, Templates, TEMPLATES};
+use Monitor_Lib::template::Template;
+use Monitor_Lib::maintenance::{parse_duration, window_end, Matcher, Recur, Suppressions};
 use diesel::prelude::*;
 use ratatui::{backend::CrosstermBackend, widgets::TableState, Terminal};
 use ratatui::crossterm::{
@@ -13,7 +30,7 @@ use ratatui::crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use std::{collections::HashMap, io::{self, Write}, time::{Duration, Instant}};
+use std::{collections::HashMap, io::{self, Write}, path::Path, sync::Arc, time::{Duration, Instant}};
 use chrono::{DateTime, Utc};
 use rand::Rng;
 use tokio::sync::mpsc;
@@ -22,6 +39,7 @@ use tokio::sync::mpsc;
 pub enum ActiveBlock {
     Servers,
     Info,
+    Alerts,
 }
 
 #[derive(PartialEq, Clone, Debug)]
@@ -37,6 +55,21 @@ pub enum InfoMode {
     UpdateServerCpu,
     ConfirmWarning,
     DeleteConfirm,
+    AckNote,
+}
+
+//jeden riadok tabulky serverov
+pub struct ServerRow {
+    pub server: Server,
+    pub metrics: Metrics,
+    pub health: Option<ProbeStatus>,
+    pub tls: Option<TlsInfo>,
+    pub anomalies: Vec<AnomalyMetric>,
+    //koniec prebiehajucej udrzby a komentar
+    pub maintenance: Option<(chrono::NaiveDateTime, String)>,
+    pub flapping: bool,
+    //dovod, preco prechod skoncil ako FAILED
+    pub failure: Option<String>,
 }
 
 pub struct AppState {
@@ -49,19 +82,63 @@ pub struct AppState {
     pub new_ram: String,
     pub new_cpu: String,
     pub update_id: Option<i32>,
+    pub tls_warn_days: i64,
+    pub tls_crit_days: i64,
+    //stlpec odozvy ukazuje p95 a stratu namiesto priemeru
+    pub latency_detail: bool,
+    //predpoved RAM pre vybrany server (id, vysledok)
+    pub forecast: Option<(i32, Option<RamForecast>)>,
+    //spodny panel ukazuje SLO namiesto logov
+    pub show_slo: bool,
+    pub slo: Vec<SloReport>,
+    //panel alertov (aktivne alebo historia)
+    pub alerts: Vec<AlertView>,
+    pub alert_selected: usize,
+    pub alert_history: bool,
+    //alert, ktory sa prave potvrdzuje, a poznamka
+    pub ack_id: Option<i32>,
+    pub ack_note: String,
+    //spodny panel ukazuje zavislosti vybraneho servera
+    pub show_deps: bool,
+    pub deps: DependencyGraph,
 }
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut conn = establish_connection();
-    diesel::sql_query("PRAGMA journal_mode = WAL;").execute(&mut conn).ok();
-
     // Spracovanie Commandov
     let cli = Cli::parse();
 
+    //agent bezi na vzdialenom stroji, databazu nepotrebuje
+    if let Commands::Agent { collector, name, token, interval_ms, port, samples } = cli.command {
+        let opts = AgentOptions { collector, name, token, interval: Duration::from_millis(interval_ms.max(100)), port, samples };
+        run_agent(opts, |msg| println!("[{}] {}", Utc::now().format("%H:%M:%S"), msg)).await;
+        return Ok(());
+    }
+
+    let mut conn = establish_connection()?;
+
     match cli.command {
-        Commands::Gui => {
-            run_ratatui_loop(&mut conn).await?;
+        Commands::Agent { .. } => {}
+        Commands::Gui { seed, scenario, viewer, pidfile, agent_listen } => {
+            //bez toho by GUI a daemon zapisovali tie iste vzorky dvakrat
+            let viewer = viewer || daemon::running_pid(&pidfile).is_some();
+            let mut sim = make_simulator(seed);
+            if let Some(path) = scenario {
+                sim.set_scenario(Some(Scenario::load(&path)?));
+            }
+            let sampler = Arc::new(Sampler::new(sim));
+            let agents = match agent_listen {
+                Some(addr) => Some(AgentListener::bind(&addr).await?),
+                None => None,
+            };
+            if let Some(a) = &agents {
+                sampler.set_agent_cache(a.cache());
+            }
+            run_ratatui_loop(&mut conn, sampler, viewer, &pidfile).await?;
+        }
+        Commands::Daemon { pidfile, log_file, seed, scenario, max_concurrency, agent_listen } => {
+            let opts = daemon::DaemonOptions { pidfile, log_file, scenario, max_concurrency, agent_listen };
+            daemon::run(opts, make_simulator(seed)).await?;
         }
         Commands::AddServer => {
             println!("PRIDANIE SERVERA");
@@ -71,8 +148,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             let mut ram_s = String::new(); io::stdin().read_line(&mut ram_s)?;
             print!("Port: "); io::stdout().flush()?;
             let mut port_s = String::new(); io::stdin().read_line(&mut port_s)?;
-            print!("CPU Model: "); io::stdout().flush()?;
+            let known_cpus: Vec<String> = get_cpu_profiles(&mut conn)?.into_iter().map(|p| p.name).collect();
+            print!("CPU Model [{}]: ", known_cpus.join(", ")); io::stdout().flush()?;
             let mut cpu = String::new(); io::stdin().read_line(&mut cpu)?;
+            if !known_cpus.iter().any(|c| c.eq_ignore_ascii_case(cpu.trim())) {
+                println!("Upozornenie: CPU '{}' nie je v katalógu, použije sa generický profil.", cpu.trim());
+            }
 
             let ram: f32 = ram_s.trim().parse().unwrap_or(0.0);
             let port: i32 = port_s.trim().parse().unwrap_or(0);
@@ -80,21 +161,82 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             add_server(&mut conn, name.trim(), ram, port, cpu.trim());
             println!("Server '{}' bol pridaný.", name.trim());
         }
-        Commands::RunServer => {
-            print!("Zadaj NÁZOV servera na zapnutie: ");
-            io::stdout().flush()?;
-            let mut target_name = String::new();
-            io::stdin().read_line(&mut target_name)?;
-            let target_name = target_name.trim();
-
-            use Monitor_Lib::schema::servers::dsl::*;
-            let found_server = servers.filter(name.eq(target_name)).first::<Server>(&mut conn).optional()?;
-
-            if let Some(s) = found_server {
-                update_status(&mut conn, s.id, "ON");
-                println!("Server '{}' (ID: {}) bol zapnutý.", target_name, s.id);
-            } else {
-                println!("Chyba: Server s názvom '{}' neexistuje.", target_name);
+        Commands::RunServer { name: target } => run_transition(&mut conn, target, Action::Start, "zapnutie").await?,
+        Commands::StopServer { name: target } => run_transition(&mut conn, target, Action::Stop, "vypnutie").await?,
+        Commands::RestartServer { name: target } => run_transition(&mut conn, target, Action::Restart, "reštart").await?,
+        Commands::RetryServer { name: target } => {
+            let Some(s) = find_server(&mut conn, target, "opakovanie")? else {
+                return Ok(());
+            };
+            if s.status != lifecycle::FAILED {
+                println!("Chyba: Server {} nie je v stave FAILED.", s.name);
+                return Ok(());
+            }
+            println!("{}: opakujem prechod", s.name);
+            println!("{}", lifecycle::retry(s).await.message);
+        }
+        Commands::ResetServer { name: target } => {
+            let Some(s) = find_server(&mut conn, target, "reset")? else {
+                return Ok(());
+            };
+            match lifecycle::reset(&mut conn, &s)? {
+                Ok(msg) => println!("{}", msg),
+                Err(e) => println!("Chyba: {}", e),
+            }
+        }
+        Commands::ActivateServer { name: target } => {
+            let Some(s) = find_server(&mut conn, target, "aktiváciu")? else {
+                return Ok(());
+            };
+            let out = lifecycle::activate(s, |pct| println!("Aktivácia {}%", pct)).await;
+            println!("{}", out.message);
+        }
+        Commands::Status { name: target } => {
+            let Some(s) = find_server(&mut conn, Some(target), "")? else {
+                return Ok(());
+            };
+            let now = Utc::now().naive_utc();
+            //od kedy je server v aktualnom stave
+            let since = Monitor_Lib::db::last_event_before(&mut conn, s.id, "status", now + chrono::Duration::seconds(1))?
+                .filter(|e| e.message == s.status)
+                .map(|e| format!(" (od {}, {})", e.timestamp.format("%Y-%m-%d %H:%M:%S"), fmt_eta(now - e.timestamp)))
+                .unwrap_or_default();
+            println!("Server:    {} (ID {})", s.name, s.id);
+            println!("Stav:      {}{}", s.status, since);
+            if let Some(t) = get_transition(&mut conn, s.id)? {
+                match &t.reason {
+                    Some(why) => println!("Chyba:     {} (retry-server / reset-server)", why),
+                    None => println!("Prechod:   {} od {}, termín {}", t.action, t.started_at.format("%H:%M:%S"), t.deadline.format("%H:%M:%S")),
+                }
+            }
+            println!("Zdroj:     {}, {}:{} (sonda {})", s.source, s.host, s.port, s.probe);
+            match get_latest_history(&mut conn, s.id)? {
+                Some(h) => {
+                    let ms = if h.response_ms < 0 { "DOWN".to_string() } else { format!("{}ms", h.response_ms) };
+                    println!("Meranie:   {} | CPU {:.1}% | RAM {:.1}/{:.1}G ({})", ms, h.cpu_usage, h.ram_usage, s.max_ram, h.timestamp.format("%H:%M:%S"));
+                }
+                None => println!("Meranie:   -"),
+            }
+            let control = match get_lifecycle_command(&mut conn, s.id)? {
+                Some(c) if !c.unit.is_empty() => format!("systemd {}", c.unit),
+                Some(c) if !lifecycle::steps(&c, Action::Start).is_empty() || !lifecycle::steps(&c, Action::Stop).is_empty() => "shell príkazy".to_string(),
+                _ => "simulované".to_string(),
+            };
+            println!("Ovládanie: {}", control);
+            if let Some((w, end)) = Suppressions::load(&mut conn)?.maintenance(&s) {
+                println!("Údržba:    #{} do {} {}", w.id, end.format("%H:%M"), w.comment);
+            }
+            if let Some(f) = get_flap_states(&mut conn)?.into_iter().find(|f| f.server_id == s.id && f.flapping) {
+                println!("Flapping:  {:.0}% zmien od {}", f.change_pct, f.since.format("%H:%M:%S"));
+            }
+            let active = get_active_alerts(&mut conn)?.into_iter().filter(|a| a.server_id == s.id && a.state == "firing").count();
+            println!("Alerty:    {} aktívnych", active);
+            let events = get_recent_server_events(&mut conn, s.id, "lifecycle.", 5)?;
+            if !events.is_empty() {
+                println!("Posledné prechody:");
+                for e in events {
+                    println!("  {} {:<16} {}", e.timestamp.format("%Y-%m-%d %H:%M:%S"), e.kind, e.message.replace('\n', "\n    "));
+                }
             }
         }
         Commands::RemoveServer => {
@@ -155,11 +297,16 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 io::stdin().read_line(&mut n_ram)?;
                 let final_ram = n_ram.trim().parse::<f32>().unwrap_or(s.max_ram);
 
+                let known_cpus: Vec<String> = get_cpu_profiles(&mut conn)?.into_iter().map(|p| p.name).collect();
+                println!("Dostupné CPU: {}", known_cpus.join(", "));
                 print!("Nový CPU model [{}]: ", s.cpu_model);
                 io::stdout().flush()?;
                 let mut n_cpu = String::new();
                 io::stdin().read_line(&mut n_cpu)?;
                 let final_cpu = if n_cpu.trim().is_empty() { s.cpu_model } else { n_cpu.trim().to_string() };
+                if !known_cpus.iter().any(|c| c.eq_ignore_ascii_case(&final_cpu)) {
+                    println!("Upozornenie: CPU '{}' nie je v katalógu, použije sa generický profil.", final_cpu);
+                }
 
                 diesel::update(servers.filter(id.eq(s.id)))
                     .set((
@@ -175,12 +322,643 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("Chyba: Server s názvom '{}' neexistuje.", target_name);
             }
         }
+        Commands::SetSource => {
+            print!("Zadaj NÁZOV servera: ");
+            io::stdout().flush()?;
+            let mut target_name = String::new();
+            io::stdin().read_line(&mut target_name)?;
+            let target_name = target_name.trim();
+
+            use Monitor_Lib::schema::servers::dsl::*;
+            let found_server = servers.filter(name.eq(target_name)).first::<Server>(&mut conn).optional()?;
+
+            if let Some(s) = found_server {
+                print!("Zdroj metrík [sim/local/prometheus/agent/probe] ({}): ", s.source);
+                io::stdout().flush()?;
+                let mut n_source = String::new();
+                io::stdin().read_line(&mut n_source)?;
+                let n_source = n_source.trim().to_lowercase();
+
+                if !matches!(n_source.as_str(), "sim" | "local" | "prometheus" | "agent" | "probe") {
+                    println!("Chyba: Neznámy zdroj '{}'.", n_source);
+                } else {
+                    if n_source == "prometheus" {
+                        print!("URL /metrics (http://host:port/metrics): ");
+                        io::stdout().flush()?;
+                        let mut n_url = String::new();
+                        io::stdin().read_line(&mut n_url)?;
+
+                        print!("CPU séria [process_cpu_seconds_total]: ");
+                        io::stdout().flush()?;
+                        let mut n_cpu = String::new();
+                        io::stdin().read_line(&mut n_cpu)?;
+
+                        print!("RAM séria [process_resident_memory_bytes]: ");
+                        io::stdout().flush()?;
+                        let mut n_ram = String::new();
+                        io::stdin().read_line(&mut n_ram)?;
+
+                        let or_default = |v: &str, d: &str| if v.trim().is_empty() { d.to_string() } else { v.trim().to_string() };
+                        save_prometheus_source(&mut conn, &PrometheusSource {
+                            server_id: s.id,
+                            url: n_url.trim().to_string(),
+                            cpu_series: or_default(&n_cpu, "process_cpu_seconds_total"),
+                            ram_series: or_default(&n_ram, "process_resident_memory_bytes"),
+                        })?;
+                    }
+                    if n_source == "probe" {
+                        print!("Host [{}]: ", s.host);
+                        io::stdout().flush()?;
+                        let mut n_host = String::new();
+                        io::stdin().read_line(&mut n_host)?;
+                        let final_host = if n_host.trim().is_empty() { s.host.clone() } else { n_host.trim().to_string() };
+
+                        print!("Protokol [auto/tcp/http/mongodb/redis/postgres/banner] ({}): ", s.probe);
+                        io::stdout().flush()?;
+                        let mut n_probe = String::new();
+                        io::stdin().read_line(&mut n_probe)?;
+                        let final_probe = if n_probe.trim().is_empty() { s.probe.clone() } else { n_probe.trim().to_lowercase() };
+
+                        if ProbeKind::parse(&final_probe, s.port).is_none() {
+                            println!("Chyba: Neznámy protokol '{}'.", final_probe);
+                            return Ok(());
+                        }
+                        update_probe(&mut conn, s.id, &final_host, &final_probe)?;
+                    }
+                    if n_source == "agent" {
+                        let new_token: String = rand::rng().sample_iter(rand::distr::Alphanumeric).take(32).map(char::from).collect();
+                        set_agent_token(&mut conn, s.id, &new_token)?;
+                        println!("Token agenta: {}", new_token);
+                        println!("Spustenie: Monitor-App agent --collector <host:port> --name '{}' --token {}", s.name, new_token);
+                    }
+                    update_source(&mut conn, s.id, &n_source)?;
+                    println!("Server '{}' teraz používa zdroj '{}'.", target_name, n_source);
+                }
+            } else {
+                println!("Chyba: Server s názvom '{}' neexistuje.", target_name);
+            }
+        }
+        Commands::SetInterval => {
+            print!("Zadaj NÁZOV servera: ");
+            io::stdout().flush()?;
+            let mut target_name = String::new();
+            io::stdin().read_line(&mut target_name)?;
+            let target_name = target_name.trim();
+
+            use Monitor_Lib::schema::servers::dsl::*;
+            let found_server = servers.filter(name.eq(target_name)).first::<Server>(&mut conn).optional()?;
+
+            if let Some(s) = found_server {
+                print!("Interval vzorkovania v ms [{}]: ", s.interval_ms);
+                io::stdout().flush()?;
+                let mut n_interval = String::new();
+                io::stdin().read_line(&mut n_interval)?;
+                let final_interval = n_interval.trim().parse::<i32>().unwrap_or(s.interval_ms).max(50);
+
+                print!("Timeout merania v ms [{}]: ", s.timeout_ms);
+                io::stdout().flush()?;
+                let mut n_timeout = String::new();
+                io::stdin().read_line(&mut n_timeout)?;
+                let final_timeout = n_timeout.trim().parse::<i32>().unwrap_or(s.timeout_ms).max(1);
+
+                update_interval(&mut conn, s.id, final_interval, final_timeout)?;
+                println!("Server '{}': interval {}ms, timeout {}ms.", target_name, final_interval, final_timeout);
+            } else {
+                println!("Chyba: Server s názvom '{}' neexistuje.", target_name);
+            }
+        }
+        Commands::Template { name, file, reset } => {
+            let Some(name) = name else {
+                let group_by = get_setting(&mut conn, "notify_group_by")?.and_then(|g| GroupBy::parse(&g)).unwrap_or_default();
+                println!("Zoskupenie: {} (okno {}s)", group_by.as_str(), setting_or(&mut conn, "notify_group_window", 30u64));
+                for (key, default) in TEMPLATES {
+                    let custom = get_setting(&mut conn, key)?;
+                    println!("\n{} ({}):", key, if custom.is_some() { "vlastná" } else { "predvolená" });
+                    println!("{}", custom.as_deref().unwrap_or(default).trim_end());
+                }
+                return Ok(());
+            };
+            if !TEMPLATES.iter().any(|(k, _)| *k == name) {
+                println!("Neznáma šablóna '{}', použi {}.", name, TEMPLATES.map(|(k, _)| k).join(", "));
+                return Ok(());
+            }
+            if reset {
+                remove_setting(&mut conn, &name)?;
+                println!("Šablóna {} vrátená na predvolenú.", name);
+            } else if let Some(path) = file {
+                let src = std::fs::read_to_string(&path)?;
+                if let Err(e) = Template::parse(&src) {
+                    println!("Chybná šablóna: {}", e);
+                    return Ok(());
+                }
+                set_setting(&mut conn, &name, &src)?;
+                println!("Šablóna {} uložená.", name);
+            }
+            //nahlad so skusobnymi alertmi
+            let templates = Templates::load(&mut conn);
+            let message = if name.starts_with("template_group") {
+                let items = vec![
+                    DigestItem { notification: Notification { state: "firing".into(), ..Notification::test() }, count: 3 },
+                    DigestItem { notification: Notification { state: "resolved".into(), server: "monitor2".into(), server_id: 1, ..Notification::test() }, count: 1 },
+                ];
+                templates.digest(&Digest { group: "server".into(), key: "monitor".into(), alerts: items })
+            } else {
+                templates.single(&Notification::test())
+            };
+            println!("Predmet: {}\n{}", message.subject, message.text);
+        }
+        Commands::Config { key, value } => {
+            match (key, value) {
+                (Some(k), Some(v)) => {
+                    set_setting(&mut conn, &k, &v)?;
+                    println!("{} = {}", k, v);
+                }
+                (Some(k), None) => match get_setting(&mut conn, &k)? {
+                    Some(v) => println!("{} = {}", k, v),
+                    None => println!("{} nie je nastavené", k),
+                },
+                _ => {
+                    for (k, v) in get_settings(&mut conn)? {
+                        println!("{} = {}", k, v);
+                    }
+                }
+            }
+        }
+        Commands::AddCpu => {
+            println!("PRIDANIE CPU PROFILU");
+            print!("Názov modelu: "); io::stdout().flush()?;
+            let mut cpu_name = String::new(); io::stdin().read_line(&mut cpu_name)?;
+            print!("Počet jadier: "); io::stdout().flush()?;
+            let mut cores_s = String::new(); io::stdin().read_line(&mut cores_s)?;
+            print!("Efektivita (0.1 - 1.0, menej = výkonnejší): "); io::stdout().flush()?;
+            let mut eff_s = String::new(); io::stdin().read_line(&mut eff_s)?;
+            print!("Základná záťaž (%): "); io::stdout().flush()?;
+            let mut load_s = String::new(); io::stdin().read_line(&mut load_s)?;
+
+            let profile = CpuProfile {
+                name: cpu_name.trim().to_lowercase(),
+                cores: cores_s.trim().parse().unwrap_or(4),
+                efficiency: eff_s.trim().parse().unwrap_or(1.0),
+                base_load: load_s.trim().parse().unwrap_or(35.0),
+            };
+            save_cpu_profile(&mut conn, &profile)?;
+            println!("CPU profil '{}' bol uložený.", profile.name);
+        }
+        Commands::RemoveCpu => {
+            print!("Zadaj NÁZOV CPU profilu na odstránenie: ");
+            io::stdout().flush()?;
+            let mut target_name = String::new();
+            io::stdin().read_line(&mut target_name)?;
+            let target_name = target_name.trim();
+
+            if remove_cpu_profile(&mut conn, target_name)? > 0 {
+                println!("CPU profil '{}' bol odstránený.", target_name);
+            } else {
+                println!("Chyba: CPU profil '{}' neexistuje.", target_name);
+            }
+        }
+        Commands::Tag { name: target, tags, remove } => {
+            let Some(s) = get_all_servers(&mut conn)?.into_iter().find(|s| s.name == target) else {
+                println!("Server '{}' neexistuje.", target);
+                return Ok(());
+            };
+            for t in &tags {
+                if remove { remove_tag(&mut conn, s.id, t)?; } else { add_tag(&mut conn, s.id, t)?; }
+            }
+            let current = get_tags(&mut conn)?.remove(&s.id).unwrap_or_default();
+            println!("{}: {}", s.name, if current.is_empty() { "-".to_string() } else { current.join(", ") });
+        }
+        Commands::Depend { name: target, upstream, remove } => {
+            let servers = get_all_servers(&mut conn)?;
+            let names: HashMap<i32, &str> = servers.iter().map(|s| (s.id, s.name.as_str())).collect();
+            let Some(target) = target else {
+                for (s_id, up) in get_dependencies(&mut conn)? {
+                    println!("{} -> {}", names.get(&s_id).unwrap_or(&"?"), names.get(&up).unwrap_or(&"?"));
+                }
+                return Ok(());
+            };
+            let Some(s) = servers.iter().find(|s| s.name == target) else {
+                println!("Server '{}' neexistuje.", target);
+                return Ok(());
+            };
+            for up_name in &upstream {
+                let Some(up) = servers.iter().find(|u| u.name == *up_name) else {
+                    println!("Server '{}' neexistuje.", up_name);
+                    continue;
+                };
+                if remove {
+                    remove_dependency(&mut conn, s.id, up.id)?;
+                } else if DependencyGraph::load(&mut conn)?.would_cycle(s.id, up.id) {
+                    println!("{} -> {} by vytvorilo cyklus, nepridané.", s.name, up.name);
+                } else {
+                    add_dependency(&mut conn, s.id, up.id)?;
+                }
+            }
+            let graph = DependencyGraph::load(&mut conn)?;
+            let list = |ids: &[i32]| if ids.is_empty() { "-".to_string() } else { ids.iter().map(|i| *names.get(i).unwrap_or(&"?")).collect::<Vec<_>>().join(", ") };
+            println!("{} závisí od: {}", s.name, list(graph.depends_on(s.id)));
+            println!("od {} závisia: {}", s.name, list(graph.dependents(s.id)));
+        }
+        Commands::Lifecycle { name: target, unit, start, stop, restart, timeout, wait_probe, remove } => {
+            let servers = get_all_servers(&mut conn)?;
+            let print = |c: &LifecycleCommand, name: &str| {
+                println!("{}:", name);
+                if !c.unit.is_empty() {
+                    println!("  systemd:  {}", c.unit);
+                }
+                for (label, cmd) in [("start", &c.start_cmd), ("stop", &c.stop_cmd), ("restart", &c.restart_cmd)] {
+                    if !cmd.is_empty() {
+                        println!("  {:<9} {}", format!("{}:", label), cmd);
+                    }
+                }
+                println!("  timeout:  {}s, sonda: {}", c.timeout_secs, if c.wait_probe { "áno" } else { "nie" });
+            };
+            let Some(target) = target else {
+                for c in get_lifecycle_commands(&mut conn)? {
+                    let name = servers.iter().find(|s| s.id == c.server_id).map(|s| s.name.as_str()).unwrap_or("?");
+                    print(&c, name);
+                }
+                return Ok(());
+            };
+            let Some(s) = servers.iter().find(|s| s.name == target) else {
+                println!("Server '{}' neexistuje.", target);
+                return Ok(());
+            };
+            if remove {
+                remove_lifecycle_command(&mut conn, s.id)?;
+                println!("Príkazy servera {} zmazané, štart a stop sa iba simulujú.", s.name);
+                return Ok(());
+            }
+            let mut c = get_lifecycle_command(&mut conn, s.id)?.unwrap_or(LifecycleCommand {
+                server_id: s.id,
+                unit: String::new(),
+                start_cmd: String::new(),
+                stop_cmd: String::new(),
+                restart_cmd: String::new(),
+                timeout_secs: 60,
+                wait_probe: true,
+            });
+            let changed = unit.is_some() || start.is_some() || stop.is_some() || restart.is_some() || timeout.is_some() || wait_probe.is_some();
+            if let Some(v) = unit { c.unit = v.trim().to_string(); }
+            if let Some(v) = start { c.start_cmd = v; }
+            if let Some(v) = stop { c.stop_cmd = v; }
+            if let Some(v) = restart { c.restart_cmd = v; }
+            if let Some(v) = timeout { c.timeout_secs = v.max(1); }
+            if let Some(v) = wait_probe { c.wait_probe = v; }
+            if changed {
+                save_lifecycle_command(&mut conn, &c)?;
+            }
+            print(&c, &s.name);
+        }
+        Commands::Slo { action } => match action {
+            Some(SloAction::Add { scope, objective, days }) => {
+                let Some(sc) = Scope::parse(&scope).filter(|sc| *sc != Scope::All) else {
+                    println!("Neplatný rozsah '{}', použi server:<názov> alebo tag:<tag>.", scope);
+                    return Ok(());
+                };
+                if !(0.0..100.0).contains(&objective) {
+                    println!("Cieľ musí byť v percentách, napr. 99.9");
+                    return Ok(());
+                }
+                add_slo_target(&mut conn, &sc.to_string(), objective, days.max(1))?;
+                println!("SLO pridané: {} {}% / {} dní", sc, objective, days.max(1));
+            }
+            Some(SloAction::Remove { id }) => {
+                if remove_slo_target(&mut conn, id)? > 0 { println!("SLO {} zmazané.", id); } else { println!("SLO {} neexistuje.", id); }
+            }
+            None => {
+                let pct = |v: Option<f64>, prec: usize| v.map(|v| format!("{:.*}%", prec, v)).unwrap_or_else(|| "-".into());
+                let rate = |v: Option<f64>| v.map(|v| format!("{:.2}x", v)).unwrap_or_else(|| "-".into());
+                println!("{:-<100}", "");
+                println!("{:<4} | {:<20} | {:<8} | {:<5} | {:<10} | {:<10} | {:<8} | {:<8}", "ID", "ROZSAH", "CIEĽ", "DNÍ", "DOSTUPNÉ", "ROZPOČET", "BURN", "BURN 1h");
+                println!("{:-<100}", "");
+                for r in slo::evaluate_all(&mut conn)? {
+                    println!("{:<4} | {:<20} | {:<8} | {:<5} | {:<10} | {:<10} | {:<8} | {:<8}{}",
+                        r.target.id, r.target.scope, format!("{}%", r.target.objective), r.target.window_days,
+                        pct(r.availability(), 3), pct(r.budget_remaining(), 1), rate(r.burn_rate()), rate(r.recent_burn_rate()),
+                        if r.breached() { "  PORUŠENÉ" } else { "" });
+                }
+            }
+        },
+        Commands::Rule { action } => match action {
+            Some(RuleAction::Add { name: rule_name, scope, expr, channels, severity }) => {
+                let Some(sc) = Scope::parse(&scope) else {
+                    println!("Neplatný rozsah '{}', použi all, server:<názov> alebo tag:<tag>.", scope);
+                    return Ok(());
+                };
+                if let Err(e) = Condition::parse(&expr) {
+                    println!("Chybné pravidlo: {}", e);
+                    return Ok(());
+                }
+                let Some(sev) = Severity::parse(&severity) else {
+                    println!("Neplatná závažnosť '{}', použi critical, warning alebo info.", severity);
+                    return Ok(());
+                };
+                let known = get_channels(&mut conn)?;
+                let names: Vec<&str> = channels.split(',').map(str::trim).filter(|c| !c.is_empty()).collect();
+                if let Some(missing) = names.iter().find(|n| !known.iter().any(|c| c.name == **n)) {
+                    println!("Kanál '{}' neexistuje.", missing);
+                    return Ok(());
+                }
+                add_alert_rule(&mut conn, &rule_name, &sc.to_string(), expr.trim(), &names.join(","), sev.as_str())?;
+                println!("Pravidlo pridané: {} [{}] {} ({})", rule_name, sc, expr.trim(), sev.as_str());
+            }
+            Some(RuleAction::Remove { id }) => {
+                if remove_alert_rule(&mut conn, id)? > 0 { println!("Pravidlo {} zmazané.", id); } else { println!("Pravidlo {} neexistuje.", id); }
+            }
+            None => {
+                let active = get_active_alerts(&mut conn)?;
+                println!("{:-<120}", "");
+                println!("{:<4} | {:<16} | {:<16} | {:<35} | {:<8} | {:<7} | {:<16}", "ID", "NÁZOV", "ROZSAH", "PODMIENKA", "ZÁVAŽN.", "AKTÍVNE", "KANÁLY");
+                println!("{:-<120}", "");
+                for r in get_alert_rules(&mut conn)? {
+                    let firing = active.iter().filter(|a| a.rule_id == r.id && a.state == "firing").count();
+                    let channels = if r.channels.is_empty() { "všetky" } else { r.channels.as_str() };
+                    println!("{:<4} | {:<16} | {:<16} | {:<35} | {:<8} | {:<7} | {:<16}", r.id, r.name, r.scope, r.expr, r.severity, firing, channels);
+                }
+            }
+        },
+        Commands::Alerts { history, limit } => {
+            let views = alert_views(&mut conn, history.then_some(limit))?;
+            println!("{:-<120}", "");
+            println!("{:<5} | {:<8} | {:<9} | {:<8} | {:<16} | {:<16} | {:<9} | {:<4} | {:<30}", "ID", "ZÁVAŽN.", "STAV", "VEK", "SERVER", "PRAVIDLO", "HODNOTA", "ESK.", "POZNÁMKA");
+            println!("{:-<120}", "");
+            for v in views {
+                println!("{:<5} | {:<8} | {:<9} | {:<8} | {:<16} | {:<16} | {:<9.2} | {:<4} | {:<30}",
+                    v.alert.id, v.severity.as_str(), v.alert.state, fmt_eta(v.age()), v.server, v.rule, v.alert.value, v.alert.esc_level, note_text(&v));
+            }
+        }
+        Commands::Ack { id, note, by } => {
+            let by = by.or_else(|| std::env::var("USER").ok()).unwrap_or_else(|| "?".into());
+            match get_alert(&mut conn, id)? {
+                None => println!("Alert {} neexistuje.", id),
+                Some(a) if a.state == "resolved" => println!("Alert {} je už vyriešený.", id),
+                Some(_) => {
+                    ack_alert(&mut conn, id, &by, note.trim())?;
+                    println!("Alert {} potvrdený ({}).", id, by);
+                }
+            }
+        }
+        Commands::Escalation { action } => match action {
+            Some(EscalationAction::Add { name: policy_name, scope, every, tiers, repeat }) => {
+                let Some(sc) = Scope::parse(&scope) else {
+                    println!("Neplatný rozsah '{}', použi all, server:<názov> alebo tag:<tag>.", scope);
+                    return Ok(());
+                };
+                let known = get_channels(&mut conn)?;
+                let levels: Vec<Vec<&str>> = tiers.split(';').map(|t| t.split(',').map(str::trim).filter(|c| !c.is_empty()).collect::<Vec<_>>()).filter(|t| !t.is_empty()).collect();
+                if levels.is_empty() {
+                    println!("Zadaj aspoň jednu úroveň kanálov, napr. --tiers \"mail;pager,sms\"");
+                    return Ok(());
+                }
+                if let Some(missing) = levels.iter().flatten().find(|n| !known.iter().any(|c| c.name == **n)) {
+                    println!("Kanál '{}' neexistuje.", missing);
+                    return Ok(());
+                }
+                let tiers = levels.iter().map(|t| t.join(",")).collect::<Vec<_>>().join(";");
+                match add_escalation_policy(&mut conn, &policy_name, &sc.to_string(), every.max(1), &tiers, repeat.max(0)) {
+                    Ok(_) => println!("Eskalácia pridaná: {} [{}] každých {} min: {}", policy_name, sc, every.max(1), tiers),
+                    Err(diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)) => println!("Eskalácia '{}' už existuje.", policy_name),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Some(EscalationAction::Remove { id }) => {
+                if remove_escalation_policy(&mut conn, id)? > 0 { println!("Eskalácia {} zmazaná.", id); } else { println!("Eskalácia {} neexistuje.", id); }
+            }
+            None => {
+                println!("{:-<100}", "");
+                println!("{:<4} | {:<14} | {:<16} | {:<6} | {:<8} | {:<40}", "ID", "NÁZOV", "ROZSAH", "MIN", "OPAKOV.", "ÚROVNE");
+                println!("{:-<100}", "");
+                for p in get_escalation_policies(&mut conn)? {
+                    println!("{:<4} | {:<14} | {:<16} | {:<6} | {:<8} | {:<40}", p.id, p.name, p.scope, p.every_mins, p.max_repeats, p.tier_list().join(" -> "));
+                }
+            }
+        },
+        Commands::Channel { action } => match action {
+            Some(ChannelAction::Add { name: channel_name, kind, target, from, to }) => {
+                let mut config = "{}".to_string();
+                match kind.as_str() {
+                    "webhook" if let Err(e) = Monitor_Lib::http::split_url(&target) => {
+                        println!("Neplatná URL webhooku: {}", e);
+                        return Ok(());
+                    }
+                    "smtp" => {
+                        let Some(to) = to else {
+                            println!("SMTP kanál potrebuje adresáta: --to adresa[,adresa]");
+                            return Ok(());
+                        };
+                        config = smtp_config(from.as_deref(), &to);
+                    }
+                    "webhook" | "exec" => {}
+                    other => {
+                        println!("Neznámy typ kanála '{}', použi webhook, smtp alebo exec.", other);
+                        return Ok(());
+                    }
+                }
+                match add_channel(&mut conn, &channel_name, &kind, &target, &config) {
+                    Ok(_) => println!("Kanál pridaný: {} ({}) -> {}", channel_name, kind, target),
+                    Err(diesel::result::Error::DatabaseError(diesel::result::DatabaseErrorKind::UniqueViolation, _)) => println!("Kanál '{}' už existuje.", channel_name),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            Some(ChannelAction::Remove { id }) => {
+                if remove_channel(&mut conn, id)? > 0 { println!("Kanál {} zmazaný.", id); } else { println!("Kanál {} neexistuje.", id); }
+            }
+            Some(ChannelAction::Test { name: channel_name }) => {
+                let Some(channel) = get_channels(&mut conn)?.into_iter().find(|c| c.name == channel_name) else {
+                    println!("Kanál '{}' neexistuje.", channel_name);
+                    return Ok(());
+                };
+                let message = Templates::load(&mut conn).single(&Notification::test());
+                match deliver(&channel, &message).await {
+                    Ok(()) => println!("Skúšobná notifikácia doručená cez '{}'.", channel.name),
+                    Err(e) => println!("Doručenie cez '{}' zlyhalo: {}", channel.name, e),
+                }
+            }
+            Some(ChannelAction::Log { limit }) => {
+                let names: HashMap<i32, String> = get_channels(&mut conn)?.into_iter().map(|c| (c.id, c.name)).collect();
+                println!("{:-<100}", "");
+                println!("{:<19} | {:<6} | {:<14} | {:<9} | {:<5} | {:<30}", "ČAS", "ALERT", "KANÁL", "STAV", "POKUS", "VÝSLEDOK");
+                println!("{:-<100}", "");
+                for d in get_deliveries(&mut conn, limit)? {
+                    let channel = names.get(&d.channel_id).map(String::as_str).unwrap_or("?");
+                    let result = if d.ok { "OK".to_string() } else { d.error.unwrap_or_default() };
+                    println!("{:<19} | {:<6} | {:<14} | {:<9} | {:<5} | {:<30}", d.timestamp.format("%Y-%m-%d %H:%M:%S"), d.alert_id, channel, d.state, d.attempt, result);
+                }
+            }
+            None => {
+                println!("{:-<90}", "");
+                println!("{:<4} | {:<14} | {:<8} | {:<40} | {:<14}", "ID", "NÁZOV", "TYP", "CIEĽ", "KONFIG");
+                println!("{:-<90}", "");
+                for c in get_channels(&mut conn)? {
+                    println!("{:<4} | {:<14} | {:<8} | {:<40} | {:<14}", c.id, c.name, c.kind, c.target, c.config);
+                }
+            }
+        },
+        Commands::Maint { action } => match action {
+            Some(MaintAction::Add { scope, start, duration, recur, comment }) => {
+                let Some(sc) = Scope::parse(&scope) else {
+                    println!("Neplatný rozsah '{}', použi all, server:<názov> alebo tag:<tag>.", scope);
+                    return Ok(());
+                };
+                let starts_at = if start == "now" { Some(Utc::now().naive_utc()) }
+                                else { chrono::NaiveDateTime::parse_from_str(&start, "%Y-%m-%d %H:%M").ok() };
+                let Some(starts_at) = starts_at else {
+                    println!("Neplatný začiatok '{}', použi \"YYYY-MM-DD HH:MM\" (UTC) alebo now.", start);
+                    return Ok(());
+                };
+                let Some(length) = parse_duration(&duration) else {
+                    println!("Neplatné trvanie '{}', napr. 30m, 2h, 1h30m.", duration);
+                    return Ok(());
+                };
+                let Some(rc) = Recur::parse(&recur) else {
+                    println!("Neplatné opakovanie '{}', použi daily alebo weekly.", recur);
+                    return Ok(());
+                };
+                let Ok(mins) = i32::try_from(length.num_minutes().max(1)) else {
+                    println!("Trvanie '{}' je príliš dlhé.", duration);
+                    return Ok(());
+                };
+                add_maintenance_window(&mut conn, &sc.to_string(), starts_at, mins, rc.as_str(), &comment)?;
+                println!("Údržba pridaná: {} od {} na {}{}", sc, starts_at.format("%Y-%m-%d %H:%M"), fmt_eta(length),
+                    if rc == Recur::Once { String::new() } else { format!(", opakuje sa {}", rc.as_str()) });
+            }
+            Some(MaintAction::Remove { id }) => {
+                if remove_maintenance_window(&mut conn, id)? > 0 { println!("Údržba {} zmazaná.", id); } else { println!("Údržba {} neexistuje.", id); }
+            }
+            None => {
+                let now = Utc::now().naive_utc();
+                println!("{:-<100}", "");
+                println!("{:<4} | {:<16} | {:<16} | {:<8} | {:<8} | {:<9} | {:<20}", "ID", "ROZSAH", "ZAČIATOK (UTC)", "TRVANIE", "OPAKOV.", "TERAZ", "KOMENTÁR");
+                println!("{:-<100}", "");
+                for w in get_maintenance_windows(&mut conn)? {
+                    let active = window_end(&w, now).map(|end| format!("do {}", end.format("%H:%M"))).unwrap_or_else(|| "-".into());
+                    println!("{:<4} | {:<16} | {:<16} | {:<8} | {:<8} | {:<9} | {:<20}", w.id, w.scope, w.starts_at.format("%Y-%m-%d %H:%M"),
+                        fmt_eta(chrono::Duration::minutes(w.duration_mins as i64)), if w.recur.is_empty() { "-" } else { w.recur.as_str() }, active, w.comment);
+                }
+            }
+        },
+        Commands::Silence { action } => match action {
+            Some(SilenceAction::Add { matcher, duration, author, comment }) => {
+                if let Err(e) = Matcher::parse(&matcher) {
+                    println!("Chybný matcher: {}", e);
+                    return Ok(());
+                }
+                let Some(length) = parse_duration(&duration) else {
+                    println!("Neplatné trvanie '{}', napr. 30m, 2h, 1h30m.", duration);
+                    return Ok(());
+                };
+                let Some(expires_at) = Utc::now().naive_utc().checked_add_signed(length) else {
+                    println!("Trvanie '{}' je príliš dlhé.", duration);
+                    return Ok(());
+                };
+                let author = author.or_else(|| std::env::var("USER").ok()).unwrap_or_else(|| "?".into());
+                let si = add_silence(&mut conn, matcher.trim(), &author, &comment, expires_at)?;
+                println!("Stíšené #{}: {} do {} UTC", si.id, si.matcher, si.expires_at.format("%Y-%m-%d %H:%M"));
+            }
+            Some(SilenceAction::Remove { id }) => {
+                if expire_silence(&mut conn, id)? > 0 { println!("Stíšenie {} ukončené.", id); } else { println!("Stíšenie {} neexistuje alebo už vypršalo.", id); }
+            }
+            None => {
+                let now = Utc::now().naive_utc();
+                println!("{:-<100}", "");
+                println!("{:<4} | {:<30} | {:<10} | {:<8} | {:<30}", "ID", "MATCHER", "AUTOR", "OSTÁVA", "KOMENTÁR");
+                println!("{:-<100}", "");
+                for si in get_silences(&mut conn)? {
+                    println!("{:<4} | {:<30} | {:<10} | {:<8} | {:<30}", si.id, si.matcher, si.author, fmt_eta(si.expires_at - now), si.comment);
+                }
+            }
+        },
+        Commands::Forecast { hours } => {
+            let mut rows = Vec::new();
+            for s in get_all_servers(&mut conn)? {
+                let f = server_forecast(&mut conn, &s, hours)?;
+                rows.push((s, f));
+            }
+            //najskor tie, ktorym dojde RAM najskor
+            rows.sort_by_key(|(_, f)| f.and_then(|f| f.until_full).map(|d| d.num_seconds()).unwrap_or(i64::MAX));
+
+            let eta = |d: Option<chrono::Duration>| d.map(fmt_eta).unwrap_or_else(|| "-".into());
+            println!("{:-<80}", "");
+            println!("{:<20} | {:<13} | {:<10} | {:<10} | {:<10}", "NÁZOV", "RAM", "TREND GB/h", "DO 90%", "DO 100%");
+            println!("{:-<80}", "");
+            for (s, f) in rows {
+                match f {
+                    Some(f) => println!("{:<20} | {:<13} | {:<+10.3} | {:<10} | {:<10}", s.name, format!("{:.1}/{:.1}G", f.current_gb, s.max_ram), f.slope_gb_per_hour, eta(f.until_90), eta(f.until_full)),
+                    None => println!("{:<20} | {:<13} | {:<10} | {:<10} | {:<10}", s.name, format!("-/{:.1}G", s.max_ram), "málo dát", "-", "-"),
+                }
+            }
+        }
+        Commands::ListCpu => {
+            println!("{:-<60}", "");
+            println!("{:<15} | {:<6} | {:<10} | {:<10}", "MODEL", "JADRÁ", "EFEKTIVITA", "ZÁŤAŽ %");
+            println!("{:-<60}", "");
+            for p in get_cpu_profiles(&mut conn)? {
+                println!("{:<15} | {:<6} | {:<10.2} | {:<10.1}", p.name, p.cores, p.efficiency, p.base_load);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+//kto alert potvrdil a s akou poznamkou, pripadne jeho pricina
+pub fn note_text(v: &AlertView) -> String {
+    let ack = match (&v.alert.ack_by, v.alert.ack_note.as_deref()) {
+        (Some(by), Some(note)) if !note.is_empty() => Some(format!("{}: {}", by, note)),
+        (Some(by), _) => Some(by.clone()),
+        _ => None,
+    };
+    let cause = v.alert.caused_by.as_ref().map(|c| format!("spôsobené: {}", c));
+    match (ack, cause) {
+        (Some(a), Some(c)) => format!("{} ({})", a, c),
+        (a, c) => a.or(c).unwrap_or_else(|| "-".into()),
+    }
+}
+
+//server podla mena z argumentu, bez neho sa opyta
+fn find_server(conn: &mut Monitor_Lib::db::SqliteConnection, target: Option<String>, purpose: &str) -> Result<Option<Server>, Box<dyn std::error::Error>> {
+    let target_name = match target {
+        Some(n) => n,
+        None => {
+            print!("Zadaj NÁZOV servera na {}: ", purpose);
+            io::stdout().flush()?;
+            let mut n = String::new();
+            io::stdin().read_line(&mut n)?;
+            n.trim().to_string()
+        }
+    };
+    use Monitor_Lib::schema::servers::dsl::*;
+    let found = servers.filter(name.eq(&target_name)).first::<Server>(conn).optional()?;
+    if found.is_none() {
+        println!("Chyba: Server s názvom '{}' neexistuje.", target_name);
     }
+    Ok(found)
+}
 
+//rovnaky prechod ako Enter v TUI, caka na vysledok
+async fn run_transition(conn: &mut Monitor_Lib::db::SqliteConnection, target: Option<String>, action: Action, purpose: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(s) = find_server(conn, target, purpose)? else {
+        return Ok(());
+    };
+    if let Err(e) = lifecycle::check(&s, action) {
+        println!("Chyba: {}", e);
+        return Ok(());
+    }
+    println!("{}: {}", s.name, action.pending_status());
+    let out = lifecycle::transition(s, action).await;
+    println!("{}", out.message);
     Ok(())
 }
 
-async fn run_ratatui_loop(conn: &mut Monitor_Lib::db::SqliteConnection) -> Result<(), Box<dyn std::error::Error>> {
+fn make_simulator(seed: Option<u64>) -> Simulator {
+    match seed {
+        Some(s) => Simulator::seeded(s),
+        None => Simulator::default(),
+    }
+}
+
+async fn run_ratatui_loop(conn: &mut Monitor_Lib::db::SqliteConnection, sampler: Arc<Sampler>, viewer: bool, pidfile: &Path) -> Result<(), Box<dyn std::error::Error>> {
     enable_raw_mode()?;
     execute!(io::stdout(), EnterAlternateScreen)?;
     let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
@@ -189,7 +967,7 @@ async fn run_ratatui_loop(conn: &mut Monitor_Lib::db::SqliteConnection) -> Resul
     state.select(Some(0));
 
     let mut app_state = AppState {
-        logs: vec![format!("[{}] Monitoring beží", Utc::now().format("%H:%M:%S"))],
+        logs: vec![format!("[{}] Monitoring beží{}", Utc::now().format("%H:%M:%S"), if viewer { " (viewer)" } else { "" })],
         start_times: HashMap::new(),
         active_block: ActiveBlock::Servers,
         info_mode: InfoMode::View,
@@ -198,249 +976,401 @@ async fn run_ratatui_loop(conn: &mut Monitor_Lib::db::SqliteConnection) -> Resul
         new_ram: String::new(),
         new_cpu: String::new(),
         update_id: None,
+        tls_warn_days: setting_or(conn, "tls_warn_days", 30),
+        tls_crit_days: setting_or(conn, "tls_crit_days", 7),
+        latency_detail: false,
+        forecast: None,
+        show_slo: false,
+        slo: Vec::new(),
+        alerts: Vec::new(),
+        alert_selected: 0,
+        alert_history: false,
+        ack_id: None,
+        ack_note: String::new(),
+        show_deps: false,
+        deps: DependencyGraph::default(),
     };
 
-    //Prepnutie stavov pri starte
-    let startup_servers = get_all_servers(conn).unwrap();
-    for s in startup_servers {
-        if s.status == "STARTING" {
-            update_status(conn, s.id, "ON");
-            app_state.logs.push(format!("[{}] {}: Stav opravený na ON", Utc::now().format("%H:%M:%S"), s.name));
+    for s in get_all_servers(conn).unwrap() {
+        if s.status == "ON" {
             app_state.start_times.insert(s.id, Utc::now());
-        } else if s.status == "STOPPING" {
-            update_status(conn, s.id, "OFF");
-            app_state.logs.push(format!("[{}] {}: Stav opravený na OFF", Utc::now().format("%H:%M:%S"), s.name));
-        } else if s.status == "ON" { 
-            app_state.start_times.insert(s.id, Utc::now()); 
         }
     }
 
+    //vo viewer rezime vzorkuje daemon, GUI cita iba historiu
+    let scheduler = if viewer { None } else { Some(Scheduler::spawn(SchedulerConfig::default(), sampler)) };
     let (tx, mut rx) = mpsc::channel::<(i32, String, bool)>(100);
-    let valid_ports = vec![80, 443, 3000, 8080, 27017];
-    let valid_cpus = vec!["intel-i5", "intel-i7", "intel-i9", "ryzen-5", "ryzen-7", "ryzen-9"];
+    let valid_ports = [80, 443, 3000, 8080, 27017];
+    let mut detector = AnomalyDetector::new(AnomalyConfig::load(conn));
+    let mut flaps = FlapDetector::new(FlapConfig::load(conn));
+    let mut last_seen: HashMap<i32, DateTime<Utc>> = HashMap::new();
+    let mut forecast_at = Instant::now();
+    let mut slo_at: Option<Instant> = None;
+    let mut deps_at: Option<Instant> = None;
+    let mut suppressions = Suppressions::load(conn).unwrap_or_default();
+    let mut alerts_at: Option<Instant> = None;
+    let ack_author = std::env::var("USER").unwrap_or_else(|_| "tui".into());
+    let mut expire_at: Option<Instant> = None;
+    //daemon spusteny az po GUI zapisuje udalosti sam, GUI ich potom iba cita
+    let mut daemon_live = viewer;
+    let mut daemon_at: Option<Instant> = None;
 
     loop {
+        if scheduler.is_some() && daemon_at.is_none_or(|t| t.elapsed() >= Duration::from_secs(2)) {
+            daemon_live = daemon::running_pid(pidfile).is_some();
+            daemon_at = Some(Instant::now());
+        }
+        let recording = scheduler.is_some() && !daemon_live;
+
         while let Ok((id, log_msg, is_on)) = rx.try_recv() {
             app_state.logs.push(log_msg);
-            if is_on { app_state.start_times.insert(id, Utc::now()); }
+            if is_on { app_state.start_times.entry(id).or_insert_with(Utc::now); }
             else { app_state.start_times.remove(&id); }
         }
 
+        //prechody po termine (napr. po pade procesu) skoncia ako FAILED; ked bezi daemon, kontroluje ich on
+        if recording && expire_at.is_none_or(|t| t.elapsed() >= Duration::from_secs(5)) {
+            for line in lifecycle::expire_transitions(conn).unwrap_or_default() {
+                app_state.logs.push(format!("[{}] {}", Utc::now().format("%H:%M:%S"), line));
+            }
+            expire_at = Some(Instant::now());
+        }
+        let mut failures: HashMap<i32, String> = get_transitions(conn).unwrap_or_default().into_iter().filter_map(|t| Some((t.server_id, t.reason?))).collect();
+
         let servers_list = get_all_servers(conn).unwrap();
+        let cpu_profiles = get_cpu_profiles(conn).unwrap_or_default();
+        //viewer zvyrazni anomalie, ktore daemon zapisal za poslednu minutu
+        let mut recent_anomalies: HashMap<i32, Vec<AnomalyMetric>> = HashMap::new();
+        if !recording {
+            let since = (Utc::now() - chrono::Duration::seconds(60)).naive_utc();
+            for e in get_events(conn, since, Some("anomaly.")).unwrap_or_default() {
+                if let Some(m) = e.kind.strip_prefix("anomaly.").and_then(AnomalyMetric::from_key) {
+                    recent_anomalies.entry(e.server_id).or_default().push(m);
+                }
+            }
+        }
+
+        suppressions.refresh(conn).ok();
         let mut display_data = Vec::new();
+        let flapping: Vec<i32> = get_flap_states(conn).unwrap_or_default().into_iter().filter(|f| f.flapping).map(|f| f.server_id).collect();
         for s in servers_list {
-            let (ms, cpu, ram) = simulate_server_metrics(&s).await;
-            display_data.push((s, ms, cpu, ram));
+            let (metrics, health, tls, anomalies) = match &scheduler {
+                Some(sch) => match sch.latest(s.id) {
+                    Some(l) => {
+                        //kazda vzorka sa do detektorov posle iba raz; ked bezi daemon, zapisuje ich on
+                        if recording && s.status == "ON" && last_seen.insert(s.id, l.taken_at) != Some(l.taken_at) {
+                            for line in detector.check(conn, &s, &l.metrics).unwrap_or_default() {
+                                app_state.logs.push(format!("[{}] {}", Utc::now().format("%H:%M:%S"), line));
+                            }
+                            if let Ok(Some(change)) = flaps.check(conn, &s, &l.metrics, l.health.as_ref()) {
+                                app_state.logs.push(format!("[{}] {}", Utc::now().format("%H:%M:%S"), change.describe(&s.name)));
+                            }
+                        }
+                        let anomalies = if recording { detector.flagged(s.id) } else { recent_anomalies.remove(&s.id).unwrap_or_default() };
+                        (l.metrics, l.health, l.tls, anomalies)
+                    }
+                    None => Default::default(),
+                },
+                None => (
+                    get_latest_history(conn, s.id).ok().flatten().map(|h| h.metrics()).unwrap_or_default(),
+                    None,
+                    None,
+                    recent_anomalies.remove(&s.id).unwrap_or_default(),
+                ),
+            };
+            let maintenance = suppressions.maintenance(&s).map(|(w, end)| (end, w.comment.clone()));
+            let flapping = flapping.contains(&s.id) && s.status == "ON";
+            let failure = if s.status == lifecycle::FAILED { failures.remove(&s.id) } else { None };
+            display_data.push(ServerRow { server: s, metrics, health, tls, anomalies, maintenance, flapping, failure });
+        }
+
+        //trend RAM vybraneho servera, historia sa nacita najviac raz za 10s
+        let selected = state.selected().and_then(|i| display_data.get(i)).map(|r| r.server.clone());
+        if let Some(s) = selected
+            && (app_state.forecast.as_ref().map(|(id, _)| *id) != Some(s.id) || forecast_at.elapsed() >= Duration::from_secs(10)) {
+            app_state.forecast = Some((s.id, server_forecast(conn, &s, 6).ok().flatten()));
+            forecast_at = Instant::now();
+        }
+
+        //zavislosti sa menia iba cez CLI, staci ich nacitat raz za 10s
+        if app_state.show_deps && deps_at.is_none_or(|t| t.elapsed() >= Duration::from_secs(10)) {
+            app_state.deps = DependencyGraph::load(conn).unwrap_or_default();
+            deps_at = Some(Instant::now());
+        }
+
+        //SLO sa prepocitava iba ked je zobrazene, najviac raz za 30s
+        if app_state.show_slo && slo_at.is_none_or(|t| t.elapsed() >= Duration::from_secs(30)) {
+            app_state.slo = slo::evaluate_all(conn).unwrap_or_default();
+            slo_at = Some(Instant::now());
+        }
+
+        //alerty zapisuje daemon, potvrdenia aj ine TUI a CLI
+        if alerts_at.is_none_or(|t| t.elapsed() >= Duration::from_secs(2)) {
+            app_state.alerts = alert_views(conn, app_state.alert_history.then_some(100)).unwrap_or_default();
+            app_state.alert_selected = app_state.alert_selected.min(app_state.alerts.len().saturating_sub(1));
+            alerts_at = Some(Instant::now());
         }
 
         terminal.draw(|f| ui::draw_main_layout(f, &display_data, &mut state, &app_state))?;
 
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    if app_state.active_block == ActiveBlock::Info && !matches!(app_state.info_mode, InfoMode::View | InfoMode::ConfirmWarning | InfoMode::DeleteConfirm) {
-                        match key.code {
-                            KeyCode::Char(c) => {
-                                match app_state.info_mode {
-                                    InfoMode::AddServerName | InfoMode::UpdateServerName => app_state.new_name.push(c),
-                                    InfoMode::AddServerPort | InfoMode::UpdateServerPort => if c.is_digit(10) { app_state.new_port.push(c) },
-                                    InfoMode::AddServerRam | InfoMode::UpdateServerRam => if c.is_digit(10) || c == '.' { app_state.new_ram.push(c) },
-                                    InfoMode::AddServerCpu | InfoMode::UpdateServerCpu => app_state.new_cpu.push(c),
-                                    _ => {}
-                                }
-                                continue;
-                            }
-                            KeyCode::Backspace => {
-                                match app_state.info_mode {
-                                    InfoMode::AddServerName | InfoMode::UpdateServerName => { app_state.new_name.pop(); }
-                                    InfoMode::AddServerPort | InfoMode::UpdateServerPort => { app_state.new_port.pop(); }
-                                    InfoMode::AddServerRam | InfoMode::UpdateServerRam => { app_state.new_ram.pop(); }
-                                    InfoMode::AddServerCpu | InfoMode::UpdateServerCpu => { app_state.new_cpu.pop(); }
-                                    _ => {}
-                                }
-                                continue;
+        if event::poll(Duration::from_millis(100))? && let Event::Key(key) = event::read()? && key.kind == KeyEventKind::Press {
+            if app_state.info_mode == InfoMode::AckNote {
+                match key.code {
+                    KeyCode::Char(c) => app_state.ack_note.push(c),
+                    KeyCode::Backspace => { app_state.ack_note.pop(); }
+                    KeyCode::Enter => {
+                        if let Some(aid) = app_state.ack_id.take() {
+                            match ack_alert(conn, aid, &ack_author, app_state.ack_note.trim()) {
+                                Ok(n) if n > 0 => app_state.logs.push(format!("[{}] Alert #{} potvrdený", Utc::now().format("%H:%M:%S"), aid)),
+                                _ => app_state.logs.push(format!("[{}] ERROR: Alert #{} sa nepodarilo potvrdiť", Utc::now().format("%H:%M:%S"), aid)),
                             }
+                            alerts_at = None;
+                        }
+                        app_state.info_mode = InfoMode::View;
+                    }
+                    KeyCode::Esc => {
+                        app_state.ack_id = None;
+                        app_state.info_mode = InfoMode::View;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if app_state.active_block == ActiveBlock::Info && !matches!(app_state.info_mode, InfoMode::View | InfoMode::ConfirmWarning | InfoMode::DeleteConfirm) {
+                match key.code {
+                    KeyCode::Char(c) => {
+                        match app_state.info_mode {
+                            InfoMode::AddServerName | InfoMode::UpdateServerName => app_state.new_name.push(c),
+                            InfoMode::AddServerPort | InfoMode::UpdateServerPort if c.is_ascii_digit() => app_state.new_port.push(c),
+                            InfoMode::AddServerRam | InfoMode::UpdateServerRam if c.is_ascii_digit() || c == '.' => app_state.new_ram.push(c),
+                            InfoMode::AddServerCpu | InfoMode::UpdateServerCpu => app_state.new_cpu.push(c),
                             _ => {}
                         }
+                        continue;
                     }
+                    KeyCode::Backspace => {
+                        match app_state.info_mode {
+                            InfoMode::AddServerName | InfoMode::UpdateServerName => { app_state.new_name.pop(); }
+                            InfoMode::AddServerPort | InfoMode::UpdateServerPort => { app_state.new_port.pop(); }
+                            InfoMode::AddServerRam | InfoMode::UpdateServerRam => { app_state.new_ram.pop(); }
+                            InfoMode::AddServerCpu | InfoMode::UpdateServerCpu => { app_state.new_cpu.pop(); }
+                            _ => {}
+                        }
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
 
-                    match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Char('a') => {
-                            if app_state.active_block == ActiveBlock::Servers {
-                                if let Some(idx) = state.selected() {
-                                    if let Some((s, _, _, _)) = display_data.get(idx) {
-                                        if s.status == "/" {
-                                            let sid = s.id;
-                                            let sname = s.name.clone();
-                                            let tx_clone = tx.clone();
-                                            tokio::spawn(async move {
-                                                for i in (0..=100).step_by(25) {
-                                                    let _ = tx_clone.send((sid, format!("[{}] {}: Aktivácia {}%", Utc::now().format("%H:%M:%S"), sname, i), false)).await;
-                                                    tokio::time::sleep(Duration::from_millis(400)).await;
-                                                }
-                                                let mut bg_conn = establish_connection();
-                                                update_status(&mut bg_conn, sid, "OFF");
-                                                let _ = tx_clone.send((sid, format!("[{}] {}: Activation complete", Utc::now().format("%H:%M:%S"), sname), false)).await;
-                                            });
-                                        } else {
-                                            app_state.logs.push(format!("[{}] ERROR: Server {} je už aktivovaný!", Utc::now().format("%H:%M:%S"), s.name));
-                                        }
-                                    }
-                                }
-                            }
+            match key.code {
+                KeyCode::Char('q') => break,
+                KeyCode::Char('a') if app_state.active_block == ActiveBlock::Servers => {
+                    if let Some(idx) = state.selected() && let Some(ServerRow { server: s, .. }) = display_data.get(idx) {
+                        if s.status == "/" {
+                            let sid = s.id;
+                            let sname = s.name.clone();
+                            let server = s.clone();
+                            let tx_clone = tx.clone();
+                            tokio::spawn(async move {
+                                let out = lifecycle::activate(server, |pct| {
+                                    let _ = tx_clone.try_send((sid, format!("[{}] {}: Aktivácia {}%", Utc::now().format("%H:%M:%S"), sname, pct), false));
+                                }).await;
+                                let _ = tx_clone.send((sid, format!("[{}] {}", Utc::now().format("%H:%M:%S"), out.message), false)).await;
+                            });
+                        } else {
+                            app_state.logs.push(format!("[{}] ERROR: Server {} je už aktivovaný!", Utc::now().format("%H:%M:%S"), s.name));
                         }
-                        KeyCode::Char('r') => {
-                            if app_state.active_block == ActiveBlock::Servers {
-                                if let Some(idx) = state.selected() {
-                                    if let Some((s, _, _, _)) = display_data.get(idx) {
-                                        app_state.update_id = Some(s.id);
-                                        app_state.new_name = s.name.clone();
-                                        app_state.info_mode = InfoMode::DeleteConfirm;
-                                        app_state.active_block = ActiveBlock::Info;
-                                    }
-                                }
-                            }
+                    }
+                }
+                KeyCode::Char('r') if app_state.active_block == ActiveBlock::Servers => {
+                    if let Some(idx) = state.selected() && let Some(ServerRow { server: s, .. }) = display_data.get(idx) {
+                        app_state.update_id = Some(s.id);
+                        app_state.new_name = s.name.clone();
+                        app_state.info_mode = InfoMode::DeleteConfirm;
+                        app_state.active_block = ActiveBlock::Info;
+                    }
+                }
+                KeyCode::Char('l') if app_state.active_block == ActiveBlock::Servers => {
+                    app_state.latency_detail = !app_state.latency_detail;
+                }
+                KeyCode::Char('s') if app_state.active_block == ActiveBlock::Servers => {
+                    app_state.show_slo = !app_state.show_slo;
+                    app_state.show_deps = false;
+                    slo_at = None;
+                }
+                KeyCode::Char('d') if app_state.active_block == ActiveBlock::Servers => {
+                    app_state.show_deps = !app_state.show_deps;
+                    app_state.show_slo = false;
+                    deps_at = None;
+                }
+                KeyCode::Char('k') if app_state.active_block == ActiveBlock::Alerts => {
+                    match app_state.alerts.get(app_state.alert_selected) {
+                        Some(v) if v.alert.state != "resolved" => {
+                            app_state.ack_id = Some(v.alert.id);
+                            app_state.ack_note = v.alert.ack_note.clone().unwrap_or_default();
+                            app_state.info_mode = InfoMode::AckNote;
                         }
-                        KeyCode::Char('n') => {
-                            if app_state.active_block == ActiveBlock::Info {
-                                app_state.info_mode = InfoMode::AddServerName;
-                                app_state.update_id = None;
-                                app_state.new_name.clear(); app_state.new_port.clear();
-                                app_state.new_ram.clear(); app_state.new_cpu.clear();
-                            }
+                        Some(_) => app_state.logs.push(format!("[{}] ERROR: Alert je už vyriešený!", Utc::now().format("%H:%M:%S"))),
+                        None => {}
+                    }
+                }
+                KeyCode::Char('g') if app_state.active_block == ActiveBlock::Alerts => {
+                    if let Some(v) = app_state.alerts.get(app_state.alert_selected) && let Some(idx) = display_data.iter().position(|r| r.server.id == v.alert.server_id) {
+                        state.select(Some(idx));
+                        app_state.active_block = ActiveBlock::Servers;
+                    }
+                }
+                KeyCode::Char('h') if app_state.active_block == ActiveBlock::Alerts => {
+                    app_state.alert_history = !app_state.alert_history;
+                    app_state.alert_selected = 0;
+                    alerts_at = None;
+                }
+                KeyCode::Char('n') if app_state.active_block == ActiveBlock::Info => {
+                    app_state.info_mode = InfoMode::AddServerName;
+                    app_state.update_id = None;
+                    app_state.new_name.clear(); app_state.new_port.clear();
+                    app_state.new_ram.clear(); app_state.new_cpu.clear();
+                }
+                KeyCode::Char('x') if app_state.active_block == ActiveBlock::Servers => {
+                    if let Some(ServerRow { server: s, .. }) = state.selected().and_then(|i| display_data.get(i)) {
+                        let line = match lifecycle::reset(conn, s) {
+                            Ok(Ok(msg)) => msg,
+                            Ok(Err(e)) => format!("ERROR: {}", e),
+                            Err(e) => format!("ERROR: {}: {}", s.name, e),
+                        };
+                        app_state.logs.push(format!("[{}] {}", Utc::now().format("%H:%M:%S"), line));
+                    }
+                }
+                KeyCode::Char('u') if app_state.active_block == ActiveBlock::Info => {
+                    if let Some(idx) = state.selected() && let Some(ServerRow { server: s, .. }) = display_data.get(idx) {
+                        if s.status == "OFF" {
+                            app_state.info_mode = InfoMode::UpdateServerName;
+                            app_state.update_id = Some(s.id);
+                            app_state.new_name = s.name.clone();
+                            app_state.new_port = s.port.to_string();
+                            app_state.new_ram = s.max_ram.to_string();
+                            app_state.new_cpu = s.cpu_model.clone();
+                        } else {
+                            app_state.logs.push(format!("[{}] ERROR: Server musí byť OFF pre úpravu!", Utc::now().format("%H:%M:%S")));
                         }
-                        KeyCode::Char('u') => {
-                            if app_state.active_block == ActiveBlock::Info {
-                                if let Some(idx) = state.selected() {
-                                    if let Some((s, _, _, _)) = display_data.get(idx) {
-                                        if s.status == "OFF" {
-                                            app_state.info_mode = InfoMode::UpdateServerName;
-                                            app_state.update_id = Some(s.id);
-                                            app_state.new_name = s.name.clone();
-                                            app_state.new_port = s.port.to_string();
-                                            app_state.new_ram = s.max_ram.to_string();
-                                            app_state.new_cpu = s.cpu_model.clone();
-                                        } else {
-                                            app_state.logs.push(format!("[{}] ERROR: Server musí byť OFF pre úpravu!", Utc::now().format("%H:%M:%S")));
-                                        }
-                                    }
-                                }
-                            }
+                    }
+                }
+                KeyCode::Tab => {
+                    app_state.active_block = match app_state.active_block {
+                        ActiveBlock::Servers => ActiveBlock::Info,
+                        ActiveBlock::Info => { app_state.info_mode = InfoMode::View; ActiveBlock::Alerts },
+                        ActiveBlock::Alerts => ActiveBlock::Servers,
+                    };
+                }
+                KeyCode::Down | KeyCode::Up => {
+                    let len = display_data.len();
+                    if app_state.active_block == ActiveBlock::Info && !matches!(app_state.info_mode, InfoMode::View | InfoMode::DeleteConfirm | InfoMode::ConfirmWarning) {
+                        if key.code == KeyCode::Down {
+                            app_state.info_mode = match app_state.info_mode {
+                                InfoMode::AddServerName | InfoMode::UpdateServerName => InfoMode::AddServerPort,
+                                InfoMode::AddServerPort | InfoMode::UpdateServerPort => InfoMode::AddServerRam,
+                                InfoMode::AddServerRam | InfoMode::UpdateServerRam => InfoMode::AddServerCpu,
+                                _ => app_state.info_mode.clone(),
+                            };
+                        } else {
+                            app_state.info_mode = match app_state.info_mode {
+                                InfoMode::AddServerPort | InfoMode::UpdateServerPort => InfoMode::AddServerName,
+                                InfoMode::AddServerRam | InfoMode::UpdateServerRam => InfoMode::AddServerPort,
+                                InfoMode::AddServerCpu | InfoMode::UpdateServerCpu => InfoMode::AddServerRam,
+                                _ => app_state.info_mode.clone(),
+                            };
                         }
-                        KeyCode::Tab => {
-                            app_state.active_block = match app_state.active_block {
-                                ActiveBlock::Servers => ActiveBlock::Info,
-                                ActiveBlock::Info => { app_state.info_mode = InfoMode::View; ActiveBlock::Servers },
+                    } else if app_state.active_block == ActiveBlock::Alerts {
+                        let n = app_state.alerts.len();
+                        if n > 0 {
+                            app_state.alert_selected = match key.code {
+                                KeyCode::Down => (app_state.alert_selected + 1) % n,
+                                _ => (app_state.alert_selected + n - 1) % n,
                             };
                         }
-                        KeyCode::Down | KeyCode::Up => {
-                            let len = display_data.len();
-                            if app_state.active_block == ActiveBlock::Info && !matches!(app_state.info_mode, InfoMode::View | InfoMode::DeleteConfirm | InfoMode::ConfirmWarning) {
-                                if key.code == KeyCode::Down {
-                                    app_state.info_mode = match app_state.info_mode {
-                                        InfoMode::AddServerName | InfoMode::UpdateServerName => InfoMode::AddServerPort,
-                                        InfoMode::AddServerPort | InfoMode::UpdateServerPort => InfoMode::AddServerRam,
-                                        InfoMode::AddServerRam | InfoMode::UpdateServerRam => InfoMode::AddServerCpu,
-                                        _ => app_state.info_mode.clone(),
-                                    };
-                                } else {
-                                    app_state.info_mode = match app_state.info_mode {
-                                        InfoMode::AddServerPort | InfoMode::UpdateServerPort => InfoMode::AddServerName,
-                                        InfoMode::AddServerRam | InfoMode::UpdateServerRam => InfoMode::AddServerPort,
-                                        InfoMode::AddServerCpu | InfoMode::UpdateServerCpu => InfoMode::AddServerRam,
-                                        _ => app_state.info_mode.clone(),
-                                    };
+                    } else if len > 0 {
+                        let i = match key.code {
+                            KeyCode::Down => state.selected().map(|i| (i + 1) % len).unwrap_or(0),
+                            _ => state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0),
+                        };
+                        state.select(Some(i));
+                    }
+                }
+                KeyCode::Enter => {
+                    if app_state.active_block == ActiveBlock::Info {
+                        match app_state.info_mode {
+                            InfoMode::DeleteConfirm => {
+                                if let Some(uid) = app_state.update_id {
+                                    remove_server(conn, uid).ok();
+                                    app_state.logs.push(format!("[{}] SERVER ODSTRÁNENÝ: {}", Utc::now().format("%H:%M:%S"), app_state.new_name));
+                                    app_state.info_mode = InfoMode::View;
+                                    app_state.active_block = ActiveBlock::Servers;
                                 }
-                            } else if len > 0 {
-                                let i = match key.code {
-                                    KeyCode::Down => state.selected().map(|i| (i + 1) % len).unwrap_or(0),
-                                    _ => state.selected().map(|i| if i == 0 { len - 1 } else { i - 1 }).unwrap_or(0),
-                                };
-                                state.select(Some(i));
                             }
-                        }
-                        KeyCode::Enter => {
-                            if app_state.active_block == ActiveBlock::Info {
-                                match app_state.info_mode {
-                                    InfoMode::DeleteConfirm => {
-                                        if let Some(uid) = app_state.update_id {
-                                            remove_server(conn, uid).ok();
-                                            app_state.logs.push(format!("[{}] SERVER ODSTRÁNENÝ: {}", Utc::now().format("%H:%M:%S"), app_state.new_name));
-                                            app_state.info_mode = InfoMode::View;
-                                            app_state.active_block = ActiveBlock::Servers;
-                                        }
-                                    }
-                                    InfoMode::AddServerName | InfoMode::UpdateServerName => app_state.info_mode = if app_state.update_id.is_some() { InfoMode::UpdateServerPort } else { InfoMode::AddServerPort },
-                                    InfoMode::AddServerPort | InfoMode::UpdateServerPort => app_state.info_mode = if app_state.update_id.is_some() { InfoMode::UpdateServerRam } else { InfoMode::AddServerRam },
-                                    InfoMode::AddServerRam | InfoMode::UpdateServerRam => app_state.info_mode = if app_state.update_id.is_some() { InfoMode::UpdateServerCpu } else { InfoMode::AddServerCpu },
-                                    InfoMode::AddServerCpu | InfoMode::UpdateServerCpu | InfoMode::ConfirmWarning => {
-                                        let p: i32 = app_state.new_port.parse().unwrap_or(0);
-                                        let r: f32 = app_state.new_ram.parse().unwrap_or(0.0);
-                                        let p_ok = valid_ports.contains(&p);
-                                        let c_ok = valid_cpus.contains(&app_state.new_cpu.as_str());
-
-                                        if (p_ok && c_ok) || app_state.info_mode == InfoMode::ConfirmWarning {
-                                            let detail_msg = format!("{} (Port: {}, RAM: {}G, CPU: {})", app_state.new_name, p, r, app_state.new_cpu);
-                                            
-                                            if let Some(uid) = app_state.update_id {
-                                                use Monitor_Lib::schema::servers::dsl::*;
-                                                diesel::update(servers.filter(id.eq(uid))).set((name.eq(&app_state.new_name), port.eq(p), max_ram.eq(r), cpu_model.eq(&app_state.new_cpu))).execute(conn).ok();
-                                                app_state.logs.push(format!("[{}] SERVER AKTUALIZOVANÝ: {}", Utc::now().format("%H:%M:%S"), detail_msg));
-                                            } else {
-                                                add_server(conn, &app_state.new_name, r, p, &app_state.new_cpu);
-                                                app_state.logs.push(format!("[{}] SERVER VYTVORENÝ: {}", Utc::now().format("%H:%M:%S"), detail_msg));
-                                            }
-                                            app_state.info_mode = InfoMode::View;
-                                        } else {
-                                            app_state.info_mode = InfoMode::ConfirmWarning;
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            } else if app_state.active_block == ActiveBlock::Servers {
-                                if let Some(idx) = state.selected() {
-                                    if let Some((s, _, _, _)) = display_data.get(idx) {
-                                        if s.status == "/" {
-                                            app_state.logs.push(format!("[{}] ERROR: Server nie je aktivovaný!", Utc::now().format("%H:%M:%S")));
-                                        } else if s.status == "ON" || s.status == "OFF" {
-                                            let sid = s.id;
-                                            let sname = s.name.clone();
-                                            let is_turning_on = s.status == "OFF";
-                                            
-                                            let start_msg = if is_turning_on { "Starting" } else { "Stopping" };
-                                            app_state.logs.push(format!("[{}] {}: {}", Utc::now().format("%H:%M:%S"), sname, start_msg));
-
-                                            let (temp, final_s, final_log) = if is_turning_on { ("Starting", "ON", "Started") } else { ("Stopping", "OFF", "Stopped") };
-                                            update_status(conn, sid, temp);
-                                            
-                                            let tx_clone = tx.clone();
-                                            tokio::spawn(async move {
-                                                let start_inst = Instant::now();
-                                                tokio::time::sleep(Duration::from_secs(3)).await;
-                                                let duration = start_inst.elapsed().as_secs();
-                                                
-                                                let mut bg_conn = establish_connection();
-                                                update_status(&mut bg_conn, sid, final_s);
-                                                
-                                                let log_line = format!("[{}] {}: {} (trvanie: {}s)", Utc::now().format("%H:%M:%S"), sname, final_log, duration);
-                                                let _ = tx_clone.send((sid, log_line, final_s == "ON")).await;
-                                            });
-                                        }
+                            InfoMode::AddServerName | InfoMode::UpdateServerName => app_state.info_mode = if app_state.update_id.is_some() { InfoMode::UpdateServerPort } else { InfoMode::AddServerPort },
+                            InfoMode::AddServerPort | InfoMode::UpdateServerPort => app_state.info_mode = if app_state.update_id.is_some() { InfoMode::UpdateServerRam } else { InfoMode::AddServerRam },
+                            InfoMode::AddServerRam | InfoMode::UpdateServerRam => app_state.info_mode = if app_state.update_id.is_some() { InfoMode::UpdateServerCpu } else { InfoMode::AddServerCpu },
+                            InfoMode::AddServerCpu | InfoMode::UpdateServerCpu | InfoMode::ConfirmWarning => {
+                                let p: i32 = app_state.new_port.parse().unwrap_or(0);
+                                let r: f32 = app_state.new_ram.parse().unwrap_or(0.0);
+                                let p_ok = valid_ports.contains(&p);
+                                let c_ok = cpu_profiles.iter().any(|c| c.name.eq_ignore_ascii_case(&app_state.new_cpu));
+
+                                if (p_ok && c_ok) || app_state.info_mode == InfoMode::ConfirmWarning {
+                                    let detail_msg = format!("{} (Port: {}, RAM: {}G, CPU: {})", app_state.new_name, p, r, app_state.new_cpu);
+                                    
+                                    if let Some(uid) = app_state.update_id {
+                                        use Monitor_Lib::schema::servers::dsl::*;
+                                        diesel::update(servers.filter(id.eq(uid))).set((name.eq(&app_state.new_name), port.eq(p), max_ram.eq(r), cpu_model.eq(&app_state.new_cpu))).execute(conn).ok();
+                                        app_state.logs.push(format!("[{}] SERVER AKTUALIZOVANÝ: {}", Utc::now().format("%H:%M:%S"), detail_msg));
+                                    } else {
+                                        add_server(conn, &app_state.new_name, r, p, &app_state.new_cpu);
+                                        app_state.logs.push(format!("[{}] SERVER VYTVORENÝ: {}", Utc::now().format("%H:%M:%S"), detail_msg));
                                     }
+                                    app_state.info_mode = InfoMode::View;
+                                } else {
+                                    app_state.info_mode = InfoMode::ConfirmWarning;
                                 }
                             }
+                            _ => {}
                         }
-                        KeyCode::Esc => { 
-                            if app_state.info_mode == InfoMode::ConfirmWarning {
-                                app_state.info_mode = if app_state.update_id.is_some() { InfoMode::UpdateServerCpu } else { InfoMode::AddServerCpu };
-                            } else {
-                                app_state.info_mode = InfoMode::View; 
-                            }
+                    } else if app_state.active_block == ActiveBlock::Servers && let Some(idx) = state.selected() && let Some(ServerRow { server: s, .. }) = display_data.get(idx) {
+                        if s.status == "/" {
+                            app_state.logs.push(format!("[{}] ERROR: Server nie je aktivovaný!", Utc::now().format("%H:%M:%S")));
+                        } else if s.status == lifecycle::FAILED {
+                            let sid = s.id;
+                            app_state.logs.push(format!("[{}] {}: opakujem prechod", Utc::now().format("%H:%M:%S"), s.name));
+                            let server = s.clone();
+                            let tx_clone = tx.clone();
+                            tokio::spawn(async move {
+                                let out = lifecycle::retry(server).await;
+                                let log_line = format!("[{}] {}", Utc::now().format("%H:%M:%S"), out.message);
+                                let _ = tx_clone.send((sid, log_line, out.status == "ON")).await;
+                            });
+                        } else if s.status == "ON" || s.status == "OFF" {
+                            let sid = s.id;
+                            let action = if s.status == "OFF" { Action::Start } else { Action::Stop };
+                            app_state.logs.push(format!("[{}] {}: {}", Utc::now().format("%H:%M:%S"), s.name, action.pending_status()));
+
+                            //prikazy zo servera (lifecycle), inak iba simulovane oneskorenie
+                            let server = s.clone();
+                            let tx_clone = tx.clone();
+                            tokio::spawn(async move {
+                                let out = lifecycle::transition(server, action).await;
+                                let log_line = format!("[{}] {}", Utc::now().format("%H:%M:%S"), out.message);
+                                let _ = tx_clone.send((sid, log_line, out.status == "ON")).await;
+                            });
                         }
-                        _ => {}
                     }
                 }
+                KeyCode::Esc => { 
+                    if app_state.info_mode == InfoMode::ConfirmWarning {
+                        app_state.info_mode = if app_state.update_id.is_some() { InfoMode::UpdateServerCpu } else { InfoMode::AddServerCpu };
+                    } else {
+                        app_state.info_mode = InfoMode::View; 
+                    }
+                }
+                _ => {}
             }
         }
     }
@@ -1,16 +1,22 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Row, Table, TableState},
+    text::{Line, Text},
+    widgets::{Block, Borders, Cell, List, ListItem, Paragraph, Row, Table, TableState},
     Frame,
 };
-use Monitor_Lib::models::Server;
+use Monitor_Lib::probe::ProbeStatus;
+use Monitor_Lib::tls::TlsLevel;
+use Monitor_Lib::anomaly::AnomalyMetric;
+use Monitor_Lib::forecast::fmt_eta;
+use Monitor_Lib::alerts::Severity;
+use Monitor_Lib::flap::is_up;
 use chrono::Utc;
-use crate::{ActiveBlock, InfoMode};
+use crate::{note_text, ActiveBlock, InfoMode, ServerRow};
 
 pub fn draw_main_layout(
     f: &mut Frame,
-    data: &[(Server, i32, f32, f32)],
+    data: &[ServerRow],
     state: &mut TableState,
     app_state: &crate::AppState,
 ) {
@@ -34,12 +40,13 @@ pub fn draw_main_layout(
 
     //NÁPOVEDA V TITULKOCH
     let server_title = if app_state.active_block == ActiveBlock::Servers {
-        " SERVERY | [ENTER] ON/OFF | [A] Activate | [R] Remove "
+        " SERVERY | [ENTER] ON/OFF | [A] Activate | [R] Remove | [L] p95/Loss | [S] SLO | [D] Závislosti "
     } else { " SERVERY " };
 
     let info_title = match app_state.info_mode {
         InfoMode::DeleteConfirm => " Zmazať? | [ENTER] Áno | [ESC] Nie ",
         InfoMode::ConfirmWarning => " CHYBA | [ENTER] Pokračovať | [ESC] Späť ",
+        InfoMode::AckNote => " POTVRDIŤ | [ENTER] Uložiť | [ESC] Zrušiť ",
         InfoMode::View if app_state.active_block == ActiveBlock::Info => " INFO | [N] Nový | [U] Upraviť ",
         InfoMode::View => " INFO ",
         _ if app_state.update_id.is_some() => " UPRAVIŤ | [ENTER] Ďalej | [ESC] Zrušiť",
@@ -47,27 +54,53 @@ pub fn draw_main_layout(
     };
 
     //TABUĽKA SERVEROV
-    let rows = data.iter().map(|(s, ms, cpu, ram)| {
-        let is_transitioning = s.status == "Starting" || s.status == "Stopping";
+    let rows = data.iter().map(|ServerRow { server: s, metrics: m, health, anomalies, maintenance, flapping, .. }| {
+        let (ms, cpu, ram) = (&m.response_ms, &m.cpu_usage, &m.ram_usage);
+        let is_transitioning = matches!(s.status.as_str(), "Starting" | "Stopping" | "Restarting");
         let style = match s.status.as_str() {
+            "ON" if matches!(health, Some(ProbeStatus::PortOpen(_))) => Style::default().fg(Color::Yellow),
+            "ON" if matches!(health, Some(ProbeStatus::Down(_))) => Style::default().fg(Color::LightRed),
             "ON" => Style::default().fg(Color::Green),
             "OFF" => Style::default().fg(Color::Red),
-            "Starting" | "Stopping" => Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC),
+            "Starting" | "Stopping" | "Restarting" => Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC),
             _ => Style::default().fg(Color::DarkGray),
         };
 
-        let d_ms = if s.status == "ON" && *ms != -1 && !is_transitioning { format!("{}ms", ms) } else { "0ms".into() };
+        let d_ms = if s.status == "ON" && matches!(health, Some(ProbeStatus::PortOpen(_))) { "OPEN".into() }
+                   else if s.status == "ON" && *ms == -1 && s.port != 0 { "DOWN".into() }
+                   else if s.status == "ON" && *ms != -1 && !is_transitioning && app_state.latency_detail { format!("{}ms {:.0}%", m.rtt_p95, m.loss_pct) }
+                   else if s.status == "ON" && *ms != -1 && !is_transitioning { format!("{}ms", ms) } else { "0ms".into() };
         let (d_ram, d_cpu) = if is_transitioning || s.status == "OFF" {
             ("0.0/0.0G".into(), "0.0%".into())
         } else {
             (format!("{:.1}/{:.1}G", ram, s.max_ram), format!("{:.1}%", cpu))
         };
+        let (d_disk, d_net, d_load, d_conn) = if is_transitioning || s.status == "OFF" {
+            ("-".into(), "-".into(), "-".into(), "-".into())
+        } else {
+            (format!("{:.0}%", m.disk_usage), format!("{}/{}", fmt_rate(m.net_rx), fmt_rate(m.net_tx)), format!("{:.2}", m.load_avg), m.connections.to_string())
+        };
 
-        Row::new(vec![s.id.to_string(), s.status.clone(), s.name.clone(), d_ms, d_ram, d_cpu]).style(style)
+        //hodnoty mimo naucenej normy
+        let flag = |metric: AnomalyMetric, text: String| {
+            if anomalies.contains(&metric) { Cell::from(text).style(Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD)) } else { Cell::from(text) }
+        };
+        let status = match maintenance {
+            Some(_) => Cell::from("MAINT").style(Style::default().fg(Color::Blue).add_modifier(Modifier::BOLD)),
+            None if s.status == "FAILED" => Cell::from("FAILED").style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)),
+            None if *flapping => Cell::from("FLAPPING").style(Style::default().fg(Color::LightMagenta).add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK)),
+            None => Cell::from(s.status.clone()),
+        };
+        Row::new(vec![
+            Cell::from(s.id.to_string()), status, Cell::from(s.name.clone()),
+            flag(AnomalyMetric::Latency, d_ms), flag(AnomalyMetric::Ram, d_ram), flag(AnomalyMetric::Cpu, d_cpu),
+            Cell::from(d_disk), Cell::from(d_net), Cell::from(d_load), Cell::from(d_conn),
+        ]).style(style)
     });
 
-    let table = Table::new(rows, [Constraint::Length(3), Constraint::Length(9), Constraint::Percentage(30), Constraint::Length(8), Constraint::Length(12), Constraint::Length(8)])
-        .header(Row::new(vec!["ID", "STAV", "NÁZOV", "ODOZVA", "RAM", "CPU %"]).style(Style::default().fg(Color::Yellow)))
+    let (ms_title, ms_width) = if app_state.latency_detail { ("P95 STRATA", 11) } else { ("ODOZVA", 8) };
+    let table = Table::new(rows, [Constraint::Length(3), Constraint::Length(9), Constraint::Percentage(20), Constraint::Length(ms_width), Constraint::Length(12), Constraint::Length(8), Constraint::Length(5), Constraint::Length(11), Constraint::Length(6), Constraint::Length(5)])
+        .header(Row::new(vec!["ID", "STAV", "NÁZOV", ms_title, "RAM", "CPU %", "DISK", "NET RX/TX", "LOAD", "CONN"]).style(Style::default().fg(Color::Yellow)))
         .block(Block::default().borders(Borders::ALL).title(server_title).border_style(server_style))
         .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED).fg(Color::Cyan));
 
@@ -76,18 +109,89 @@ pub fn draw_main_layout(
     //INFO PANEL
     match app_state.info_mode {
         InfoMode::View => {
-            let mut text = "\n Vyber server...".to_string();
-            if let Some(idx) = state.selected() {
-                if let Some((s, _, _, _)) = data.get(idx) {
-                    let rt = if let Some(st) = app_state.start_times.get(&s.id) {
-                        let d = Utc::now().signed_duration_since(*st);
-                        format!("{}m {}s", d.num_minutes(), d.num_seconds() % 60)
-                    } else { "Offline".into() };
-                    text = format!("\n Port:     {}\n CPU:      {}\n Status:   {}\n\n RUN TIME: {}", s.port, s.cpu_model, s.status, rt);
+            let mut text = Text::from("\n Vyber server...");
+            if let Some(idx) = state.selected() && let Some(ServerRow { server: s, metrics: m, health, tls, anomalies, maintenance, flapping, failure }) = data.get(idx) {
+                let rt = if let Some(st) = app_state.start_times.get(&s.id) {
+                    let d = Utc::now().signed_duration_since(*st);
+                    format!("{}m {}s", d.num_minutes(), d.num_seconds() % 60)
+                } else { "Offline".into() };
+                let svc = match health {
+                    Some(ProbeStatus::Healthy) => "odpovedá".to_string(),
+                    Some(ProbeStatus::PortOpen(why)) => format!("iba port ({})", why),
+                    Some(ProbeStatus::Down(why)) => format!("nedostupná ({})", why),
+                    None => "-".to_string(),
+                };
+                let lat = if m.rtt_max > 0 {
+                    format!("min {} / avg {} / max {} / p95 {} ms, strata {:.0}%", m.rtt_min, m.response_ms, m.rtt_max, m.rtt_p95, m.loss_pct)
+                } else { "-".to_string() };
+                text = Text::from(format!("\n Port:     {}\n CPU:      {}\n Status:   {}\n Zdroj:    {}\n Služba:   {}\n Odozva:   {}\n Disk IO:  {:.0} KB/s\n\n RUN TIME: {}", s.port, s.cpu_model, s.status, s.source, svc, lat, m.disk_io, rt));
+
+                //odhad podla trendu z historie
+                if let Some((fid, forecast)) = &app_state.forecast
+                    && *fid == s.id {
+                    let line = match forecast {
+                        Some(f) => match (f.until_90, f.until_full) {
+                            (_, Some(full)) if full.is_zero() => Line::styled(" Do plna:  RAM je plná", Style::default().fg(Color::Red)),
+                            (Some(p90), Some(full)) => {
+                                let st = if full.num_hours() < 24 { Style::default().fg(Color::Red) } else if p90.num_hours() < 24 { Style::default().fg(Color::Yellow) } else { Style::default() };
+                                Line::styled(format!(" Do plna:  {} (90% o {})", fmt_eta(full), fmt_eta(p90)), st)
+                            }
+                            _ => Line::from(format!(" Do plna:  stabilná ({:+.2} GB/h)", f.slope_gb_per_hour)),
+                        },
+                        None => Line::from(" Do plna:  málo dát"),
+                    };
+                    text.push_line(line);
+                }
+
+                if let Some(why) = failure {
+                    text.push_line(Line::styled(format!(" Chyba:    {}", why), Style::default().fg(Color::Red)));
+                    text.push_line(Line::styled(" [ENTER] Znova | [X] Reset", Style::default().fg(Color::Red)));
+                }
+
+                if *flapping {
+                    text.push_line(Line::styled(" Flapping: stav sa často mení, alerty sú potlačené", Style::default().fg(Color::LightMagenta)));
+                }
+
+                //alerty sa pocas udrzby neposielaju
+                if let Some((end, comment)) = maintenance {
+                    let note = if comment.is_empty() { String::new() } else { format!(" ({})", comment) };
+                    text.push_line(Line::styled(format!(" Údržba:   do {} UTC{}", end.format("%H:%M"), note), Style::default().fg(Color::Blue)));
+                }
+
+                if !anomalies.is_empty() {
+                    let names: Vec<&str> = anomalies.iter().map(|a| a.label()).collect();
+                    text.push_line(Line::styled(format!(" Anomália: {}", names.join(", ")), Style::default().fg(Color::Magenta)));
+                }
+
+                //certifikat, zlta/cervena pod prahmi z nastaveni
+                if let Some(t) = tls {
+                    let tls_style = match t.level(app_state.tls_warn_days, app_state.tls_crit_days) {
+                        TlsLevel::Ok => Style::default().fg(Color::Green),
+                        TlsLevel::Warning => Style::default().fg(Color::Yellow),
+                        TlsLevel::Critical => Style::default().fg(Color::Red),
+                    };
+                    text.push_line(Line::from(""));
+                    text.push_line(Line::styled(format!(" TLS:      {} dní ({})", t.days_left, t.not_after.format("%Y-%m-%d")), tls_style));
+                    text.push_line(Line::styled(format!(" Hostname: {}", if t.hostname_ok { "OK" } else { "NESEDÍ" }), tls_style));
+                    text.push_line(Line::from(format!(" Vydal:    {}", t.issuer)));
+                    if let Some(c) = t.intermediate.as_ref().filter(|c| c.not_after < t.not_after) {
+                        text.push_line(Line::styled(format!(" Reťazec:  {} dní ({})", c.days_left, c.subject), tls_style));
+                    }
                 }
             }
             f.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(info_title).border_style(info_style)), top_chunks[1]);
         }
+        InfoMode::AckNote => {
+            let mut text = Text::from("");
+            if let Some(v) = app_state.alerts.iter().find(|v| Some(v.alert.id) == app_state.ack_id) {
+                text.push_line(Line::from(format!(" Alert:    #{} {}", v.alert.id, v.rule)));
+                text.push_line(Line::from(format!(" Server:   {}", v.server)));
+                text.push_line(Line::from(format!(" Podmienka: {}", v.expr)));
+            }
+            text.push_line(Line::from(""));
+            text.push_line(Line::styled(format!(" Poznámka: {}_", app_state.ack_note), Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)));
+            f.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(info_title).border_style(Style::default().fg(Color::Yellow))), top_chunks[1]);
+        }
         InfoMode::DeleteConfirm => {
             let text = format!("\n Naozaj zmazať:\n {}?", app_state.new_name);
             f.render_widget(Paragraph::new(text).style(Style::default().fg(Color::Red)).block(Block::default().borders(Borders::ALL).title(info_title).border_style(Style::default().fg(Color::Red))), top_chunks[1]);
@@ -104,18 +208,118 @@ pub fn draw_main_layout(
         }
     }
 
-    //LOGS
-    let logs: Vec<ListItem> = app_state.logs.iter().rev()
-        .map(|l| {
-            let s = if l.contains("ERROR") { Style::default().fg(Color::Red) }
-                    else if l.contains("Starting") || l.contains("Stopping") { Style::default().fg(Color::Cyan) }
-                    else { Style::default() };
-            ListItem::new(l.as_str()).style(s)
-        }).collect();
-    f.render_widget(List::new(logs).block(Block::default().borders(Borders::ALL).title(" LOGS ")), chunks[1]);
+    //ALERTY namiesto logov, kym je panel aktivny
+    if app_state.active_block == ActiveBlock::Alerts {
+        let rows = app_state.alerts.iter().map(|v| {
+            let style = match (v.alert.state.as_str(), v.severity) {
+                ("resolved", _) => Style::default().fg(Color::DarkGray),
+                ("pending", _) => Style::default().fg(Color::Yellow),
+                _ if v.acked() => Style::default().fg(Color::Blue),
+                _ if v.alert.caused_by.is_some() => Style::default().fg(Color::DarkGray),
+                (_, Severity::Critical) => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                (_, Severity::Warning) => Style::default().fg(Color::LightRed),
+                (_, Severity::Info) => Style::default(),
+            };
+            let state = if v.acked() && v.alert.state != "resolved" { "ACK".to_string() } else { v.alert.state.to_uppercase() };
+            Row::new(vec![
+                v.alert.id.to_string(), v.severity.as_str().to_string(), state, fmt_eta(v.age()),
+                v.server.clone(), v.rule.clone(), format!("{:.2}", v.alert.value), v.alert.esc_level.to_string(), note_text(v),
+            ]).style(style)
+        });
+        let firing = app_state.alerts.iter().filter(|v| v.alert.state == "firing").count();
+        let title = if app_state.alert_history { " ALERTY - história | [H] Aktívne | [G] Na server ".to_string() }
+                    else { format!(" ALERTY ({} firing) | [K] Potvrdiť | [G] Na server | [H] História ", firing) };
+        let table = Table::new(rows, [Constraint::Length(5), Constraint::Length(8), Constraint::Length(8), Constraint::Length(8), Constraint::Percentage(15), Constraint::Percentage(15), Constraint::Length(9), Constraint::Length(5), Constraint::Percentage(30)])
+            .header(Row::new(vec!["ID", "ZÁVAŽN.", "STAV", "VEK", "SERVER", "PRAVIDLO", "HODNOTA", "ESK.", "POZNÁMKA"]).style(Style::default().fg(Color::Yellow)))
+            .block(Block::default().borders(Borders::ALL).title(title).border_style(Style::default().fg(Color::Yellow)))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut alert_state = TableState::default().with_selected((!app_state.alerts.is_empty()).then_some(app_state.alert_selected));
+        f.render_stateful_widget(table, chunks[1], &mut alert_state);
+    } else if app_state.show_deps {
+        let selected = state.selected().and_then(|i| data.get(i));
+        let title = match selected {
+            Some(r) => format!(" ZÁVISLOSTI: {} | [D] Logy ", r.server.name),
+            None => " ZÁVISLOSTI | [D] Logy ".to_string(),
+        };
+        let mut lines = Vec::new();
+        if let Some(r) = selected {
+            let g = &app_state.deps;
+            if let Some(root) = g.root_cause(r.server.id, |id| data.iter().find(|x| x.server.id == id).is_some_and(|x| !row_up(x))) {
+                let name = data.iter().find(|x| x.server.id == root).map(|x| x.server.name.as_str()).unwrap_or("?");
+                lines.push(ListItem::new(format!(" Príčina výpadku: {}", name)).style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)));
+            }
+            lines.push(ListItem::new(" Závisí od:").style(Style::default().fg(Color::Yellow)));
+            dep_tree(&mut lines, data, r.server.id, 1, &|id| g.depends_on(id).to_vec(), &mut vec![r.server.id]);
+            lines.push(ListItem::new(" Závisia od neho:").style(Style::default().fg(Color::Yellow)));
+            dep_tree(&mut lines, data, r.server.id, 1, &|id| g.dependents(id).to_vec(), &mut vec![r.server.id]);
+        }
+        f.render_widget(List::new(lines).block(Block::default().borders(Borders::ALL).title(title)), chunks[1]);
+    } else if app_state.show_slo {
+        let pct = |v: Option<f64>, prec: usize| v.map(|v| format!("{:.*}%", prec, v)).unwrap_or_else(|| "-".into());
+        let rate = |v: Option<f64>| v.map(|v| format!("{:.2}x", v)).unwrap_or_else(|| "-".into());
+        let rows = app_state.slo.iter().map(|r| {
+            let style = if r.breached() || r.budget_remaining().is_some_and(|b| b < 0.0) { Style::default().fg(Color::Red) }
+                        else if r.recent_burn_rate().is_some_and(|b| b > 1.0) { Style::default().fg(Color::Yellow) }
+                        else { Style::default().fg(Color::Green) };
+            Row::new(vec![
+                r.target.scope.clone(), format!("{}% / {}d", r.target.objective, r.target.window_days), r.servers.len().to_string(),
+                pct(r.availability(), 3), pct(r.budget_remaining(), 1), rate(r.burn_rate()), rate(r.recent_burn_rate()),
+            ]).style(style)
+        });
+        let table = Table::new(rows, [Constraint::Percentage(25), Constraint::Length(14), Constraint::Length(8), Constraint::Length(10), Constraint::Length(10), Constraint::Length(8), Constraint::Length(8)])
+            .header(Row::new(vec!["ROZSAH", "CIEĽ", "SERVERY", "DOSTUPNÉ", "ROZPOČET", "BURN", "BURN 1h"]).style(Style::default().fg(Color::Yellow)))
+            .block(Block::default().borders(Borders::ALL).title(" SLO | [S] Logy "));
+        f.render_widget(table, chunks[1]);
+    } else {
+        //LOGS
+        let logs: Vec<ListItem> = app_state.logs.iter().rev()
+            .map(|l| {
+                let s = if l.contains("ERROR") { Style::default().fg(Color::Red) }
+                        else if l.contains("ANOMÁLIA") { Style::default().fg(Color::Magenta) }
+                        else if l.contains("FLAPPING") { Style::default().fg(Color::LightMagenta) }
+                        else if l.contains("Starting") || l.contains("Stopping") || l.contains("Restarting") { Style::default().fg(Color::Cyan) }
+                        else { Style::default() };
+                ListItem::new(l.as_str()).style(s)
+            }).collect();
+        let firing = if app_state.alert_history { 0 } else { app_state.alerts.iter().filter(|v| v.alert.state == "firing" && !v.acked()).count() };
+        let title = if firing > 0 { format!(" LOGS | {} nepotvrdených alertov [Tab] ", firing) } else { " LOGS ".to_string() };
+        f.render_widget(List::new(logs).block(Block::default().borders(Borders::ALL).title(title)), chunks[1]);
+    }
     
     //NÁPOVEDA
     let help_menu = Paragraph::new(" q: Exit | Tab: Switch Panel ")
         .style(Style::default().fg(Color::Gray).bg(Color::Rgb(40, 40, 40)));
     f.render_widget(help_menu, chunks[2]);
-}
\ No newline at end of file
+}
+
+//server bezi a odpoveda
+fn row_up(r: &ServerRow) -> bool {
+    r.server.status == "ON" && is_up(&r.metrics, r.health.as_ref())
+}
+
+//strom zavislosti s odsadenim, path chrani pred zacyklenim
+fn dep_tree(lines: &mut Vec<ListItem<'static>>, data: &[ServerRow], id: i32, depth: usize, next: &dyn Fn(i32) -> Vec<i32>, path: &mut Vec<i32>) {
+    let children = next(id);
+    if children.is_empty() && depth == 1 {
+        lines.push(ListItem::new("  -"));
+    }
+    for child in children {
+        let Some(r) = data.iter().find(|x| x.server.id == child) else { continue };
+        let (state, style) = if r.server.status != "ON" { (r.server.status.clone(), Style::default().fg(Color::Red)) }
+                             else if !row_up(r) { ("DOWN".to_string(), Style::default().fg(Color::LightRed)) }
+                             else { (format!("ON {}ms", r.metrics.response_ms), Style::default().fg(Color::Green)) };
+        lines.push(ListItem::new(format!("{}└ {} ({}, port {})", "  ".repeat(depth), r.server.name, state, r.server.port)).style(style));
+        if !path.contains(&child) {
+            path.push(child);
+            dep_tree(lines, data, child, depth + 1, next, path);
+            path.pop();
+        }
+    }
+}
+
+//bajty za sekundu v kratkom tvare
+fn fmt_rate(bytes: f32) -> String {
+    if bytes >= 1_000_000.0 { format!("{:.1}M", bytes / 1_000_000.0) }
+    else if bytes >= 1_000.0 { format!("{:.0}K", bytes / 1_000.0) }
+    else { format!("{:.0}B", bytes) }
+}
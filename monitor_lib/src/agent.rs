@@ -0,0 +1,255 @@
+//push protokol agenta: ramec = 4 bajty dlzka (big endian) + JSON
+
+use crate::db::{find_agent_server, open_connection};
+use crate::latency::LatencyStats;
+use crate::models::Metrics;
+use crate::procfs::{local_connect_ms, LocalCollector};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+const MAX_FRAME: u32 = 1024 * 1024;
+//kolko sa caka na dalsi report, potom sa spojenie zavrie (agent sa znova pripoji)
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentReport {
+    pub name: String,
+    pub token: String,
+    pub metrics: Metrics,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AgentAck {
+    pub ok: bool,
+    pub error: Option<String>,
+}
+
+//posledny report kazdeho servera
+pub type AgentCache = Arc<RwLock<HashMap<i32, (Metrics, DateTime<Utc>)>>>;
+
+pub async fn write_frame<W: AsyncWrite + Unpin, T: Serialize>(w: &mut W, msg: &T) -> io::Result<()> {
+    let body = serde_json::to_vec(msg).map_err(io::Error::other)?;
+    w.write_u32(body.len() as u32).await?;
+    w.write_all(&body).await?;
+    w.flush().await
+}
+
+//None = druha strana zavrela spojenie
+pub async fn read_frame<R: AsyncRead + Unpin, T: DeserializeOwned>(r: &mut R) -> io::Result<Option<T>> {
+    let len = match r.read_u32().await {
+        Ok(l) => l,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    if len > MAX_FRAME {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("príliš veľký rámec: {} B", len)));
+    }
+    let mut body = vec![0; len as usize];
+    r.read_exact(&mut body).await?;
+    serde_json::from_slice(&body).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+//prijima reporty agentov a mapuje ich na servery podla mena a tokenu
+pub struct AgentListener {
+    addr: SocketAddr,
+    cache: AgentCache,
+    handle: JoinHandle<()>,
+}
+
+impl AgentListener {
+    pub async fn bind(addr: &str) -> io::Result<AgentListener> {
+        let listener = TcpListener::bind(addr).await?;
+        let addr = listener.local_addr()?;
+        let cache: AgentCache = Arc::new(RwLock::new(HashMap::new()));
+
+        let accept_cache = cache.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let cache = accept_cache.clone();
+                tokio::spawn(async move {
+                    let _ = serve_agent(stream, cache).await;
+                });
+            }
+        });
+        Ok(AgentListener { addr, cache, handle })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    pub fn cache(&self) -> AgentCache {
+        self.cache.clone()
+    }
+}
+
+impl Drop for AgentListener {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn serve_agent(mut stream: TcpStream, cache: AgentCache) -> io::Result<()> {
+    let mut conn = open_connection().map_err(io::Error::other)?;
+    loop {
+        let Some(report) = tokio::time::timeout(IDLE_TIMEOUT, read_frame::<_, AgentReport>(&mut stream)).await
+            .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "agent neposlal report"))??
+        else {
+            break;
+        };
+        let ack = match find_agent_server(&mut conn, &report.name, &report.token) {
+            Ok(Some(server)) => {
+                cache.write().unwrap().insert(server.id, (report.metrics, Utc::now()));
+                AgentAck { ok: true, error: None }
+            }
+            Ok(None) => AgentAck { ok: false, error: Some("neplatný názov alebo token".into()) },
+            Err(e) => AgentAck { ok: false, error: Some(e.to_string()) },
+        };
+        write_frame(&mut stream, &ack).await?;
+    }
+    Ok(())
+}
+
+pub async fn push(stream: &mut TcpStream, report: &AgentReport) -> io::Result<AgentAck> {
+    write_frame(stream, report).await?;
+    read_frame(stream).await?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "collector zavrel spojenie"))
+}
+
+pub struct AgentOptions {
+    pub collector: String,
+    pub name: String,
+    pub token: String,
+    pub interval: Duration,
+    //port sluzby na tomto stroji pre meranie odozvy
+    pub port: Option<u16>,
+    //pocet merani odozvy v jednom reporte
+    pub samples: usize,
+}
+
+//citanie /proc a meranie odozvy blokuju, bezia mimo async vlakien
+fn measure(local: &Mutex<LocalCollector>, port: Option<u16>, samples: usize) -> Metrics {
+    let mut metrics = local.lock().unwrap().collect();
+    match port {
+        Some(p) => {
+            let pings: Vec<i32> = (0..samples.max(1)).map(|_| local_connect_ms(p as i32)).collect();
+            LatencyStats::from_samples(&pings).apply(&mut metrics);
+        }
+        None => metrics.response_ms = 1,
+    }
+    metrics
+}
+
+//zbiera lokalne metriky a posiela ich, pri vypadku sa znova pripaja
+pub async fn run_agent(opts: AgentOptions, mut on_event: impl FnMut(String)) {
+    let local = Arc::new(Mutex::new(LocalCollector::new()));
+    let first = local.clone();
+    tokio::task::spawn_blocking(move || first.lock().unwrap().collect()).await.ok();
+    let mut stream: Option<TcpStream> = None;
+
+    loop {
+        tokio::time::sleep(opts.interval).await;
+
+        let (local, port, samples) = (local.clone(), opts.port, opts.samples);
+        let metrics = match tokio::task::spawn_blocking(move || measure(&local, port, samples)).await {
+            Ok(m) => m,
+            Err(e) => {
+                on_event(format!("ERROR: meranie: {}", e));
+                continue;
+            }
+        };
+        let report = AgentReport { name: opts.name.clone(), token: opts.token.clone(), metrics };
+
+        //collector mohol necinne spojenie zavriet, vtedy sa report posle znova cez nove
+        for _ in 0..2 {
+            let s = match stream.as_mut() {
+                Some(s) => s,
+                None => match TcpStream::connect(&opts.collector).await {
+                    Ok(s) => {
+                        on_event(format!("Pripojený ku {}", opts.collector));
+                        stream.insert(s)
+                    }
+                    Err(e) => {
+                        on_event(format!("ERROR: {}: {}", opts.collector, e));
+                        break;
+                    }
+                },
+            };
+            match push(s, &report).await {
+                Ok(AgentAck { ok: true, .. }) => break,
+                Ok(AgentAck { error, .. }) => {
+                    on_event(format!("ERROR: collector odmietol report: {}", error.unwrap_or_default()));
+                    break;
+                }
+                Err(e) => {
+                    on_event(format!("ERROR: spojenie prerušené: {}", e));
+                    stream = None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::set_agent_token;
+    use crate::db::tests::{test_connection, test_server};
+
+    fn report(name: &str, token: &str, cpu: f32) -> AgentReport {
+        AgentReport { name: name.to_string(), token: token.to_string(), metrics: Metrics { cpu_usage: cpu, response_ms: 3, ..Metrics::default() } }
+    }
+
+    #[tokio::test]
+    async fn push_round_trip() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "agent", 0);
+        set_agent_token(&mut conn, server.id, "tajne").unwrap();
+
+        let listener = AgentListener::bind("127.0.0.1:0").await.unwrap();
+        let mut stream = TcpStream::connect(listener.local_addr()).await.unwrap();
+
+        let bad = push(&mut stream, &report(&server.name, "zle", 10.0)).await.unwrap();
+        assert!(!bad.ok);
+        assert!(bad.error.is_some());
+        assert!(listener.cache().read().unwrap().get(&server.id).is_none());
+
+        let unknown = push(&mut stream, &report("neexistuje", "tajne", 10.0)).await.unwrap();
+        assert!(!unknown.ok);
+
+        let good = push(&mut stream, &report(&server.name, "tajne", 42.0)).await.unwrap();
+        assert!(good.ok, "{:?}", good.error);
+        let cached = listener.cache().read().unwrap().get(&server.id).copied();
+        let (metrics, _) = cached.expect("report v cache");
+        assert_eq!(metrics.cpu_usage, 42.0);
+        assert_eq!(metrics.response_ms, 3);
+        assert_eq!(listener.cache().read().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn oversized_frame_closes_connection() {
+        let listener = AgentListener::bind("127.0.0.1:0").await.unwrap();
+        let mut stream = TcpStream::connect(listener.local_addr()).await.unwrap();
+        stream.write_u32(MAX_FRAME + 1).await.unwrap();
+        let mut buf = [0u8; 1];
+        assert_eq!(stream.read(&mut buf).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn frames_round_trip() {
+        let (mut a, mut b) = tokio::io::duplex(1024);
+        write_frame(&mut a, &AgentAck { ok: true, error: None }).await.unwrap();
+        drop(a);
+        let ack: AgentAck = read_frame(&mut b).await.unwrap().unwrap();
+        assert!(ack.ok);
+        assert!(read_frame::<_, AgentAck>(&mut b).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,510 @@
+//vyhodnocovanie pravidiel nad prichadzajucimi metrikami: pending -> firing -> resolved
+
+use crate::db::{escalate_alert, fire_alert, get_active_alerts, get_alert_history, get_alert_rules, get_all_servers, get_escalation_policies, get_tags, open_alert, resolve_alert, set_alert_muted, SqliteConnection};
+use crate::models::{Alert, AlertRule, EscalationPolicy, Metrics, Scope, Server};
+use crate::rules::{Condition, Hold};
+use chrono::{NaiveDateTime, Utc};
+use diesel::QueryResult;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+//pravidla pridane cez CLI sa prejavia bez restartu daemona
+const RELOAD_EVERY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertState {
+    Pending,
+    Firing,
+    Resolved,
+}
+
+impl AlertState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertState::Pending => "pending",
+            AlertState::Firing => "firing",
+            AlertState::Resolved => "resolved",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Critical,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    pub fn parse(s: &str) -> Option<Severity> {
+        match s.trim().to_lowercase().as_str() {
+            "critical" | "crit" => Some(Severity::Critical),
+            "warning" | "warn" => Some(Severity::Warning),
+            "info" => Some(Severity::Info),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Critical => "critical",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AlertTransition {
+    pub alert_id: i32,
+    pub rule: AlertRule,
+    pub server: Server,
+    pub state: AlertState,
+    pub value: f64,
+    //meranie, ktore prechod sposobilo (pri eskalacii nie je)
+    pub metrics: Option<Metrics>,
+}
+
+impl AlertTransition {
+    pub fn describe(&self) -> String {
+        let head = match self.state {
+            AlertState::Pending => "ALERT pending",
+            AlertState::Firing => "ALERT FIRING",
+            AlertState::Resolved => "ALERT vyriešený",
+        };
+        format!("{}: {} na {}: {} (hodnota {:.2})", head, self.rule.name, self.server.name, self.rule.expr, self.value)
+    }
+}
+
+//dalsia uroven eskalacie nepotvrdeneho alertu
+#[derive(Debug, Clone)]
+pub struct Escalation {
+    pub transition: AlertTransition,
+    pub policy: String,
+    pub level: i32,
+    //kanaly tejto urovne oddelene ciarkou
+    pub channels: String,
+}
+
+impl Escalation {
+    pub fn describe(&self) -> String {
+        format!("ESKALÁCIA {} ({}): {} na {} nepotvrdený -> {}", self.level, self.policy, self.transition.rule.name, self.transition.server.name, self.channels)
+    }
+}
+
+//kanaly pre uroven, po poslednej sa opakuje posledna; None = limit vycerpany
+pub fn tier_for(policy: &EscalationPolicy, level: i32) -> Option<String> {
+    let tiers = policy.tier_list();
+    if level < 1 || tiers.is_empty() || level > tiers.len() as i32 + policy.max_repeats.max(0) {
+        return None;
+    }
+    Some(tiers[(level as usize).min(tiers.len()) - 1].clone())
+}
+
+//rozpracovany stav jedneho pravidla na jednom serveri
+#[derive(Debug, Clone, Default)]
+struct Tracking {
+    alert_id: Option<i32>,
+    firing: bool,
+    since: Option<NaiveDateTime>,
+    count: u32,
+}
+
+#[derive(Default)]
+pub struct AlertEngine {
+    rules: Vec<(AlertRule, Option<Scope>, Condition)>,
+    tags: HashMap<i32, Vec<String>>,
+    tracking: HashMap<(i32, i32), Tracking>,
+    loaded_at: Option<Instant>,
+    escalated_at: Option<Instant>,
+    unmuted_at: Option<Instant>,
+}
+
+impl AlertEngine {
+    pub fn new() -> Self {
+        AlertEngine::default()
+    }
+
+    //pravidla s neplatnym vyrazom sa preskocia
+    pub fn reload(&mut self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        //rozbehnute alerty z databazy pokracuju, po restarte sa nezdvoja
+        if self.loaded_at.is_none() {
+            for a in get_active_alerts(conn)? {
+                let t = Tracking { alert_id: Some(a.id), firing: a.state == "firing", since: Some(a.started_at), count: 1 };
+                self.tracking.insert((a.rule_id, a.server_id), t);
+            }
+        }
+        self.rules = get_alert_rules(conn)?
+            .into_iter()
+            .filter_map(|r| {
+                let cond = Condition::parse(&r.expr).ok()?;
+                let scope = Scope::parse(&r.scope);
+                Some((r, scope, cond))
+            })
+            .collect();
+        self.tags = get_tags(conn)?;
+        let ids: Vec<i32> = self.rules.iter().map(|(r, _, _)| r.id).collect();
+        self.tracking.retain(|(rule, _), _| ids.contains(rule));
+        self.loaded_at = Some(Instant::now());
+        Ok(())
+    }
+
+    pub fn evaluate(&mut self, conn: &mut SqliteConnection, server: &Server, m: &Metrics) -> QueryResult<Vec<AlertTransition>> {
+        if self.loaded_at.is_none_or(|t| t.elapsed() >= RELOAD_EVERY) {
+            self.reload(conn)?;
+        }
+        let now = Utc::now().naive_utc();
+        let tags = self.tags.get(&server.id).map(Vec::as_slice).unwrap_or_default();
+
+        let mut transitions = Vec::new();
+        for (rule, scope, cond) in &self.rules {
+            let (value, hit) = cond.check(server, m);
+            let transition = |alert_id, state| AlertTransition { alert_id, rule: rule.clone(), server: server.clone(), state, value, metrics: Some(*m) };
+            //server po zmene tagov alebo pravidla uz nespada do rozsahu, jeho alert sa uzavrie
+            if !scope.as_ref().is_some_and(|s| s.matches(server, tags)) {
+                if let Some(t) = self.tracking.remove(&(rule.id, server.id))
+                    && let Some(id) = t.alert_id {
+                    resolve_alert(conn, id, now)?;
+                    if t.firing {
+                        transitions.push(transition(id, AlertState::Resolved));
+                    }
+                }
+                continue;
+            }
+            let t = self.tracking.entry((rule.id, server.id)).or_default();
+
+            if !hit {
+                if let Some(id) = t.alert_id {
+                    resolve_alert(conn, id, now)?;
+                    //pending, ktory nikdy nevystrelil, sa iba potichu uzavrie
+                    if t.firing {
+                        transitions.push(transition(id, AlertState::Resolved));
+                    }
+                }
+                *t = Tracking::default();
+                continue;
+            }
+
+            t.count += 1;
+            let since = *t.since.get_or_insert(now);
+            let alert_id = match t.alert_id {
+                Some(id) => id,
+                None => *t.alert_id.insert(open_alert(conn, rule.id, server.id, value as f32, now)?.id),
+            };
+            let held = match cond.hold {
+                Hold::Immediately => true,
+                Hold::For(d) => now - since >= d,
+                Hold::Samples(n) => t.count >= n,
+            };
+            if held && !t.firing {
+                t.firing = true;
+                fire_alert(conn, alert_id, value as f32, now)?;
+                transitions.push(transition(alert_id, AlertState::Firing));
+            } else if !held && t.count == 1 {
+                transitions.push(transition(alert_id, AlertState::Pending));
+            }
+        }
+        Ok(transitions)
+    }
+
+    //nepotvrdene firing alerty posunie na dalsiu uroven, stav je v tabulke alerts;
+    //muted = alert je v udrzbe alebo stiseny, eskalacia pocka
+    pub fn escalate(&mut self, conn: &mut SqliteConnection, muted: impl Fn(&AlertTransition) -> bool) -> QueryResult<Vec<Escalation>> {
+        if self.escalated_at.is_some_and(|t| t.elapsed() < RELOAD_EVERY) {
+            return Ok(Vec::new());
+        }
+        self.escalated_at = Some(Instant::now());
+        if self.loaded_at.is_none() {
+            self.reload(conn)?;
+        }
+        let policies: Vec<(EscalationPolicy, Option<Scope>)> = get_escalation_policies(conn)?.into_iter().map(|p| { let sc = Scope::parse(&p.scope); (p, sc) }).collect();
+        if policies.is_empty() {
+            return Ok(Vec::new());
+        }
+        let servers: HashMap<i32, Server> = get_all_servers(conn)?.into_iter().map(|s| (s.id, s)).collect();
+        let now = Utc::now().naive_utc();
+
+        let mut escalations = Vec::new();
+        for a in get_active_alerts(conn)? {
+            if a.state != "firing" || a.ack_at.is_some() {
+                continue;
+            }
+            let (Some(server), Some((rule, _, _))) = (servers.get(&a.server_id), self.rules.iter().find(|(r, _, _)| r.id == a.rule_id)) else {
+                continue;
+            };
+            let tags = self.tags.get(&server.id).map(Vec::as_slice).unwrap_or_default();
+            let Some((policy, _)) = policies.iter().find(|(_, sc)| sc.as_ref().is_some_and(|sc| sc.matches(server, tags))) else {
+                continue;
+            };
+            let since = a.esc_at.or(a.fired_at).unwrap_or(a.started_at);
+            if now - since < chrono::Duration::minutes(policy.every_mins.max(1) as i64) {
+                continue;
+            }
+            let level = a.esc_level + 1;
+            let Some(channels) = tier_for(policy, level) else {
+                continue;
+            };
+            let transition = AlertTransition { alert_id: a.id, rule: rule.clone(), server: server.clone(), state: AlertState::Firing, value: a.value as f64, metrics: None };
+            if muted(&transition) {
+                continue;
+            }
+            escalate_alert(conn, a.id, level, now)?;
+            escalations.push(Escalation { transition, policy: policy.name.clone(), level, channels });
+        }
+        Ok(escalations)
+    }
+
+    //dalsie volanie unmuted skontroluje potichu vystrelene alerty hned, napr. po skonceni flappingu
+    pub fn recheck_muted(&mut self) {
+        self.unmuted_at = None;
+    }
+
+    //alerty, ktore vystrelili potichu a ich potlacenie uz skoncilo; vrati ich na ohlasenie
+    //muted = alert je stale v udrzbe, stiseny alebo flapuje
+    pub fn unmuted(&mut self, conn: &mut SqliteConnection, muted: impl Fn(&AlertTransition) -> bool) -> QueryResult<Vec<AlertTransition>> {
+        if self.unmuted_at.is_some_and(|t| t.elapsed() < RELOAD_EVERY) {
+            return Ok(Vec::new());
+        }
+        self.unmuted_at = Some(Instant::now());
+        if self.loaded_at.is_none() {
+            self.reload(conn)?;
+        }
+        let pending: Vec<Alert> = get_active_alerts(conn)?.into_iter().filter(|a| a.muted && a.state == "firing").collect();
+        if pending.is_empty() {
+            return Ok(Vec::new());
+        }
+        let servers: HashMap<i32, Server> = get_all_servers(conn)?.into_iter().map(|s| (s.id, s)).collect();
+
+        let mut announced = Vec::new();
+        for a in pending {
+            let (Some(server), Some((rule, _, _))) = (servers.get(&a.server_id), self.rules.iter().find(|(r, _, _)| r.id == a.rule_id)) else {
+                continue;
+            };
+            let transition = AlertTransition { alert_id: a.id, rule: rule.clone(), server: server.clone(), state: AlertState::Firing, value: a.value as f64, metrics: None };
+            if muted(&transition) {
+                continue;
+            }
+            set_alert_muted(conn, a.id, false)?;
+            announced.push(transition);
+        }
+        Ok(announced)
+    }
+}
+
+//alert s menom pravidla a servera pre zoznamy v CLI a TUI
+#[derive(Debug, Clone)]
+pub struct AlertView {
+    pub alert: Alert,
+    pub rule: String,
+    pub expr: String,
+    pub severity: Severity,
+    pub server: String,
+}
+
+impl AlertView {
+    //od vystrelenia (pending od zaciatku), vyrieseny po koniec
+    pub fn age(&self) -> chrono::Duration {
+        let from = self.alert.fired_at.unwrap_or(self.alert.started_at);
+        self.alert.resolved_at.unwrap_or_else(|| Utc::now().naive_utc()) - from
+    }
+
+    pub fn acked(&self) -> bool {
+        self.alert.ack_at.is_some()
+    }
+}
+
+//aktivne: firing pred pending, najzavaznejsie a najstarsie prve; historia: najnovsie prve
+pub fn alert_views(conn: &mut SqliteConnection, history: Option<i64>) -> QueryResult<Vec<AlertView>> {
+    let alerts = match history {
+        Some(limit) => get_alert_history(conn, limit)?,
+        None => get_active_alerts(conn)?,
+    };
+    let rules: HashMap<i32, AlertRule> = get_alert_rules(conn)?.into_iter().map(|r| (r.id, r)).collect();
+    let servers: HashMap<i32, String> = get_all_servers(conn)?.into_iter().map(|s| (s.id, s.name)).collect();
+
+    let mut views: Vec<AlertView> = alerts.into_iter().map(|a| {
+        let rule = rules.get(&a.rule_id);
+        AlertView {
+            rule: rule.map(|r| r.name.clone()).unwrap_or_else(|| format!("#{}", a.rule_id)),
+            expr: rule.map(|r| r.expr.clone()).unwrap_or_default(),
+            severity: rule.and_then(|r| Severity::parse(&r.severity)).unwrap_or(Severity::Warning),
+            server: servers.get(&a.server_id).cloned().unwrap_or_else(|| format!("#{}", a.server_id)),
+            alert: a,
+        }
+    }).collect();
+    if history.is_none() {
+        views.sort_by_key(|v| (v.alert.state != "firing", v.severity, v.alert.started_at));
+    }
+    Ok(views)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::{test_connection, test_server};
+    use crate::db::{ack_alert, add_alert_rule, add_escalation_policy, add_tag, escalate_alert, fire_alert, get_alert, get_alert_rules, remove_tag, set_alert_muted};
+
+    fn policy(tiers: &str, max_repeats: i32) -> EscalationPolicy {
+        EscalationPolicy { id: 1, name: "p".into(), scope: "all".into(), every_mins: 5, tiers: tiers.into(), max_repeats }
+    }
+
+    #[test]
+    fn tiers_repeat_last_until_limit() {
+        let p = policy("ops; lead, cto ;", 2);
+        assert_eq!(tier_for(&p, 0), None);
+        assert_eq!(tier_for(&p, 1).as_deref(), Some("ops"));
+        assert_eq!(tier_for(&p, 2).as_deref(), Some("lead,cto"));
+        assert_eq!(tier_for(&p, 3).as_deref(), Some("lead,cto"));
+        assert_eq!(tier_for(&p, 4).as_deref(), Some("lead,cto"));
+        assert_eq!(tier_for(&p, 5), None);
+        assert_eq!(tier_for(&policy("ops", 0), 2), None);
+        assert_eq!(tier_for(&policy(" ; ", 3), 1), None);
+    }
+
+    //vystreleny alert so samostatnou politikou, posledna zmena pred 6 minutami
+    fn escalating_alert(conn: &mut SqliteConnection, prefix: &str) -> i32 {
+        let server = test_server(conn, prefix, 80);
+        let name = format!("{}-{}", prefix, server.id);
+        add_alert_rule(conn, &name, &server.name, "cpu_usage > 1", "", "critical").unwrap();
+        add_escalation_policy(conn, &name, &format!("server:{}", server.name), 5, "ops;lead", 1).unwrap();
+        let rule = get_alert_rules(conn).unwrap().into_iter().find(|r| r.name == name).unwrap();
+        let id = open_alert(conn, rule.id, server.id, 2.0, Utc::now().naive_utc()).unwrap().id;
+        fire_alert(conn, id, 2.0, ago(6)).unwrap();
+        id
+    }
+
+    fn ago(mins: i64) -> NaiveDateTime {
+        Utc::now().naive_utc() - chrono::Duration::minutes(mins)
+    }
+
+    //uroven a kanaly eskalacie daneho alertu, obmedzenie na 10s sa obide
+    fn step(engine: &mut AlertEngine, conn: &mut SqliteConnection, id: i32, muted: bool) -> Option<(i32, String)> {
+        engine.escalated_at = None;
+        let escalations = engine.escalate(conn, |t| muted && t.alert_id == id).unwrap();
+        escalations.into_iter().find(|e| e.transition.alert_id == id).map(|e| (e.level, e.channels))
+    }
+
+    #[test]
+    fn escalates_through_tiers_then_stops() {
+        let mut conn = test_connection();
+        let id = escalating_alert(&mut conn, "esc");
+        let mut engine = AlertEngine::new();
+        assert_eq!(step(&mut engine, &mut conn, id, false), Some((1, "ops".into())));
+        //dalsia uroven az po every_mins od poslednej
+        assert_eq!(step(&mut engine, &mut conn, id, false), None);
+        escalate_alert(&mut conn, id, 1, ago(6)).unwrap();
+        assert_eq!(step(&mut engine, &mut conn, id, false), Some((2, "lead".into())));
+        //posledna uroven sa zopakuje max_repeats krat
+        escalate_alert(&mut conn, id, 2, ago(6)).unwrap();
+        assert_eq!(step(&mut engine, &mut conn, id, false), Some((3, "lead".into())));
+        escalate_alert(&mut conn, id, 3, ago(60)).unwrap();
+        assert_eq!(step(&mut engine, &mut conn, id, false), None);
+        assert_eq!(get_alert(&mut conn, id).unwrap().unwrap().esc_level, 3);
+    }
+
+    #[test]
+    fn acked_or_muted_alert_waits() {
+        let mut conn = test_connection();
+        let mut engine = AlertEngine::new();
+        let muted = escalating_alert(&mut conn, "esc-muted");
+        assert_eq!(step(&mut engine, &mut conn, muted, true), None);
+        assert_eq!(get_alert(&mut conn, muted).unwrap().unwrap().esc_level, 0);
+        //po skonceni potlacenia pokracuje
+        assert_eq!(step(&mut engine, &mut conn, muted, false), Some((1, "ops".into())));
+
+        let acked = escalating_alert(&mut conn, "esc-acked");
+        ack_alert(&mut conn, acked, "oncall", "riesim").unwrap();
+        assert_eq!(step(&mut engine, &mut conn, acked, false), None);
+        assert_eq!(get_alert(&mut conn, acked).unwrap().unwrap().esc_level, 0);
+    }
+
+    #[test]
+    fn escalation_survives_restart() {
+        let mut conn = test_connection();
+        let id = escalating_alert(&mut conn, "esc-restart");
+        assert_eq!(step(&mut AlertEngine::new(), &mut conn, id, false), Some((1, "ops".into())));
+        let alert = get_alert(&mut conn, id).unwrap().unwrap();
+        assert!(alert.esc_at.is_some());
+
+        //novy engine (restart daemona) pokracuje z databazy, nezacina od prvej urovne
+        let mut restarted = AlertEngine::new();
+        assert_eq!(step(&mut restarted, &mut conn, id, false), None);
+        escalate_alert(&mut conn, id, alert.esc_level, ago(6)).unwrap();
+        assert_eq!(step(&mut AlertEngine::new(), &mut conn, id, false), Some((2, "lead".into())));
+    }
+
+    #[test]
+    fn out_of_scope_alert_resolves() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "scope", 80);
+        let tag = format!("scope-{}", server.id);
+        add_tag(&mut conn, server.id, &tag).unwrap();
+        let rule_name = format!("cpu-{}", server.id);
+        add_alert_rule(&mut conn, &rule_name, &format!("tag:{}", tag), "cpu_usage > 50", "", "warning").unwrap();
+        let rule = get_alert_rules(&mut conn).unwrap().into_iter().find(|r| r.name == rule_name).unwrap();
+
+        let mut engine = AlertEngine::new();
+        let hot = Metrics { cpu_usage: 90.0, ..Metrics::default() };
+        let fired = engine.evaluate(&mut conn, &server, &hot).unwrap();
+        let fired: Vec<_> = fired.into_iter().filter(|t| t.rule.id == rule.id).collect();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].state, AlertState::Firing);
+        let alert_id = fired[0].alert_id;
+
+        //bez tagu uz pravidlo na server nepatri, aj ked je CPU stale vysoko
+        remove_tag(&mut conn, server.id, &tag).unwrap();
+        engine.reload(&mut conn).unwrap();
+        let resolved: Vec<_> = engine.evaluate(&mut conn, &server, &hot).unwrap().into_iter().filter(|t| t.rule.id == rule.id).collect();
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].state, AlertState::Resolved);
+        assert_eq!(get_alert(&mut conn, alert_id).unwrap().unwrap().state, "resolved");
+
+        //dalsie vzorky uz nic nehlasia
+        assert!(engine.evaluate(&mut conn, &server, &hot).unwrap().iter().all(|t| t.rule.id != rule.id));
+    }
+
+    #[test]
+    fn pending_out_of_scope_closes_quietly() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "scope", 80);
+        let tag = format!("pending-{}", server.id);
+        add_tag(&mut conn, server.id, &tag).unwrap();
+        let rule_name = format!("ram-{}", server.id);
+        add_alert_rule(&mut conn, &rule_name, &format!("tag:{}", tag), "ram_usage > 1 for 3 samples", "", "info").unwrap();
+        let rule = get_alert_rules(&mut conn).unwrap().into_iter().find(|r| r.name == rule_name).unwrap();
+
+        let mut engine = AlertEngine::new();
+        let hot = Metrics { ram_usage: 4.0, ..Metrics::default() };
+        let pending: Vec<_> = engine.evaluate(&mut conn, &server, &hot).unwrap().into_iter().filter(|t| t.rule.id == rule.id).collect();
+        assert_eq!(pending[0].state, AlertState::Pending);
+
+        remove_tag(&mut conn, server.id, &tag).unwrap();
+        engine.reload(&mut conn).unwrap();
+        assert!(engine.evaluate(&mut conn, &server, &hot).unwrap().iter().all(|t| t.rule.id != rule.id));
+        assert_eq!(get_alert(&mut conn, pending[0].alert_id).unwrap().unwrap().state, "resolved");
+    }
+
+    #[test]
+    fn muted_alert_announced_after_suppression() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "muted", 80);
+        let rule_name = format!("load-{}", server.id);
+        add_alert_rule(&mut conn, &rule_name, &server.name, "load_avg > 2", "", "warning").unwrap();
+        let rule = get_alert_rules(&mut conn).unwrap().into_iter().find(|r| r.name == rule_name).unwrap();
+
+        let mut engine = AlertEngine::new();
+        let fired = engine.evaluate(&mut conn, &server, &Metrics { load_avg: 5.0, ..Metrics::default() }).unwrap();
+        let alert_id = fired.iter().find(|t| t.rule.id == rule.id).unwrap().alert_id;
+        set_alert_muted(&mut conn, alert_id, true).unwrap();
+
+        //pocas potlacenia sa neohlasi
+        let ours = |ts: Vec<AlertTransition>| ts.into_iter().filter(|t| t.alert_id == alert_id).count();
+        assert_eq!(ours(engine.unmuted(&mut conn, |_| true).unwrap()), 0);
+        //kontrola najviac raz za 10s
+        engine.unmuted_at = None;
+        assert_eq!(ours(engine.unmuted(&mut conn, |_| false).unwrap()), 1);
+        assert!(!get_alert(&mut conn, alert_id).unwrap().unwrap().muted);
+        engine.unmuted_at = None;
+        assert_eq!(ours(engine.unmuted(&mut conn, |_| false).unwrap()), 0);
+    }
+}
@@ -0,0 +1,287 @@
+//detekcia anomalii: EWMA baseline + z-score pre CPU, RAM a odozvu
+
+use crate::db::{get_history, record_event, setting_or, SqliteConnection};
+use crate::models::{HistoryEntry, Metrics, Server};
+use chrono::{Duration, Utc};
+use diesel::QueryResult;
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnomalyMetric {
+    Cpu,
+    Ram,
+    Latency,
+}
+
+impl AnomalyMetric {
+    pub const ALL: [AnomalyMetric; 3] = [AnomalyMetric::Cpu, AnomalyMetric::Ram, AnomalyMetric::Latency];
+
+    //cast kind udalosti, napr. "anomaly.cpu"
+    pub fn key(&self) -> &'static str {
+        match self {
+            AnomalyMetric::Cpu => "cpu",
+            AnomalyMetric::Ram => "ram",
+            AnomalyMetric::Latency => "latency",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<AnomalyMetric> {
+        AnomalyMetric::ALL.into_iter().find(|m| m.key() == key)
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AnomalyMetric::Cpu => "CPU",
+            AnomalyMetric::Ram => "RAM",
+            AnomalyMetric::Latency => "odozva",
+        }
+    }
+
+    //nedostupny server nema odozvu, ktoru by sa dalo porovnat
+    pub fn value(&self, m: &Metrics) -> Option<f64> {
+        match self {
+            AnomalyMetric::Cpu => Some(m.cpu_usage as f64),
+            AnomalyMetric::Ram => Some(m.ram_usage as f64),
+            AnomalyMetric::Latency if m.response_ms > 0 => Some(m.response_ms as f64),
+            AnomalyMetric::Latency => None,
+        }
+    }
+
+    //najmensia odchylka, aby ustaleny sum nevyzeral ako anomalia
+    fn min_std(&self, mean: f64) -> f64 {
+        match self {
+            AnomalyMetric::Cpu => 5.0,
+            AnomalyMetric::Ram => (mean.abs() * 0.1).max(0.05),
+            AnomalyMetric::Latency => (mean.abs() * 0.2).max(2.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct AnomalyConfig {
+    //vaha novej hodnoty v EWMA
+    pub alpha: f64,
+    pub z_threshold: f64,
+    //pocet vzoriek, kym sa baseline povazuje za naucenu
+    pub warmup: u32,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig { alpha: 0.1, z_threshold: 3.0, warmup: 30 }
+    }
+}
+
+impl AnomalyConfig {
+    pub fn load(conn: &mut SqliteConnection) -> Self {
+        let d = AnomalyConfig::default();
+        AnomalyConfig {
+            alpha: setting_or(conn, "anomaly_alpha", d.alpha).clamp(0.001, 1.0),
+            z_threshold: setting_or(conn, "anomaly_z", d.z_threshold),
+            warmup: setting_or(conn, "anomaly_warmup", d.warmup),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Baseline {
+    mean: f64,
+    var: f64,
+    count: u32,
+}
+
+impl Baseline {
+    //z-score voci doterajsiemu stavu, potom sa hodnota zapocita
+    fn update(&mut self, x: f64, min_std: f64, cfg: &AnomalyConfig) -> Option<f64> {
+        if self.count == 0 {
+            self.mean = x;
+            self.count = 1;
+            return None;
+        }
+        let std = self.var.sqrt().max(min_std);
+        let z = (x - self.mean) / std;
+
+        let diff = x - self.mean;
+        let incr = cfg.alpha * diff;
+        self.mean += incr;
+        self.var = (1.0 - cfg.alpha) * (self.var + diff * incr);
+        self.count = self.count.saturating_add(1);
+
+        (self.count > cfg.warmup).then_some(z)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Anomaly {
+    pub server_id: i32,
+    pub metric: AnomalyMetric,
+    pub value: f64,
+    pub baseline: f64,
+    pub z: f64,
+}
+
+impl Anomaly {
+    pub fn describe(&self, server_name: &str) -> String {
+        format!("{}: {} {:.1} (obvykle {:.1}, z={:.1})", server_name, self.metric.label(), self.value, self.baseline, self.z)
+    }
+}
+
+pub struct AnomalyDetector {
+    config: AnomalyConfig,
+    baselines: HashMap<(i32, AnomalyMetric), Baseline>,
+    //metriky, ktore boli v poslednej vzorke mimo normy
+    flagged: HashMap<i32, HashSet<AnomalyMetric>>,
+    primed: HashSet<i32>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig) -> Self {
+        AnomalyDetector { config, baselines: HashMap::new(), flagged: HashMap::new(), primed: HashSet::new() }
+    }
+
+    //prejde historiu a vrati vsetky body mimo normy
+    pub fn scan(&mut self, server_id: i32, history: &[HistoryEntry]) -> Vec<Anomaly> {
+        history.iter().flat_map(|h| self.observe_all(server_id, &h.metrics())).collect()
+    }
+
+    //vracia iba nove anomalie, trvajuca anomalia sa nehlasi znova
+    pub fn observe(&mut self, server_id: i32, m: &Metrics) -> Vec<Anomaly> {
+        let was = self.flagged.get(&server_id).cloned().unwrap_or_default();
+        self.observe_all(server_id, m).into_iter().filter(|a| !was.contains(&a.metric)).collect()
+    }
+
+    fn observe_all(&mut self, server_id: i32, m: &Metrics) -> Vec<Anomaly> {
+        let mut found = Vec::new();
+        for metric in AnomalyMetric::ALL {
+            let Some(x) = metric.value(m) else { continue };
+            let baseline = self.baselines.entry((server_id, metric)).or_default();
+            let mean = baseline.mean;
+            if let Some(z) = baseline.update(x, metric.min_std(mean), &self.config)
+                && z.abs() >= self.config.z_threshold {
+                found.push(Anomaly { server_id, metric, value: x, baseline: mean, z });
+            }
+        }
+        self.flagged.insert(server_id, found.iter().map(|a| a.metric).collect());
+        found
+    }
+
+    pub fn flagged(&self, server_id: i32) -> Vec<AnomalyMetric> {
+        let set = self.flagged.get(&server_id);
+        AnomalyMetric::ALL.into_iter().filter(|m| set.is_some_and(|s| s.contains(m))).collect()
+    }
+
+    //pri prvej vzorke servera sa baseline nauci z poslednych 24h historie
+    pub fn check(&mut self, conn: &mut SqliteConnection, server: &Server, m: &Metrics) -> QueryResult<Vec<String>> {
+        if self.primed.insert(server.id) {
+            let history = get_history(conn, server.id, (Utc::now() - Duration::hours(24)).naive_utc())?;
+            self.scan(server.id, &history);
+        }
+
+        let mut logs = Vec::new();
+        for a in self.observe(server.id, m) {
+            let line = a.describe(&server.name);
+            record_event(conn, server.id, &format!("anomaly.{}", a.metric.key()), &line)?;
+            logs.push(format!("ANOMÁLIA: {}", line));
+        }
+        Ok(logs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::get_recent_server_events;
+    use crate::db::tests::{test_connection, test_server};
+
+    //pri alpha 0.1 by druha vzorka spicky vysla presne na hranici z=3
+    const CFG: AnomalyConfig = AnomalyConfig { alpha: 0.05, z_threshold: 3.0, warmup: 10 };
+
+    //ustaleny server s malym deterministickym sumom
+    fn steady(i: usize) -> Metrics {
+        let jitter = (i % 3) as f32;
+        Metrics { cpu_usage: 40.0 + jitter, ram_usage: 4.0 + jitter * 0.01, response_ms: 20 + jitter as i32, ..Metrics::default() }
+    }
+
+    fn spike(i: usize) -> Metrics {
+        Metrics { cpu_usage: 95.0, ..steady(i) }
+    }
+
+    #[test]
+    fn steady_series_is_quiet() {
+        let mut d = AnomalyDetector::new(CFG);
+        for i in 0..200 {
+            assert!(d.observe(1, &steady(i)).is_empty(), "vzorka {}", i);
+        }
+        assert!(d.flagged(1).is_empty());
+    }
+
+    #[test]
+    fn nothing_during_warmup() {
+        let mut d = AnomalyDetector::new(CFG);
+        for i in 0..5 {
+            d.observe(1, &steady(i));
+        }
+        assert!(d.observe(1, &spike(5)).is_empty());
+    }
+
+    #[test]
+    fn spike_reported_once() {
+        let mut d = AnomalyDetector::new(CFG);
+        for i in 0..50 {
+            d.observe(1, &steady(i));
+        }
+        let found = d.observe(1, &spike(50));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].metric, AnomalyMetric::Cpu);
+        assert!(found[0].z >= CFG.z_threshold);
+        assert!((found[0].baseline - 41.0).abs() < 1.0, "{}", found[0].baseline);
+
+        //trvajuca anomalia ostava oznacena, ale nehlasi sa znova
+        assert!(d.observe(1, &spike(51)).is_empty());
+        assert_eq!(d.flagged(1), vec![AnomalyMetric::Cpu]);
+
+        assert!(d.observe(1, &steady(52)).is_empty());
+        assert!(d.flagged(1).is_empty());
+    }
+
+    #[test]
+    fn servers_have_own_baselines() {
+        let mut d = AnomalyDetector::new(CFG);
+        for i in 0..50 {
+            d.observe(1, &steady(i));
+            d.observe(2, &spike(i));
+        }
+        assert!(d.observe(2, &spike(50)).is_empty());
+        assert_eq!(d.observe(1, &spike(50)).len(), 1);
+    }
+
+    #[test]
+    fn unreachable_has_no_latency() {
+        let mut d = AnomalyDetector::new(CFG);
+        for i in 0..50 {
+            d.observe(1, &steady(i));
+        }
+        let down = Metrics { response_ms: -1, ..steady(50) };
+        assert!(d.observe(1, &down).is_empty());
+        assert_eq!(d.baselines[&(1, AnomalyMetric::Latency)].count, 50);
+    }
+
+    #[test]
+    fn check_records_event() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "anomaly", 80);
+        let mut d = AnomalyDetector::new(CFG);
+        for i in 0..50 {
+            assert!(d.check(&mut conn, &server, &steady(i)).unwrap().is_empty());
+        }
+        let logs = d.check(&mut conn, &server, &spike(50)).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert!(logs[0].starts_with("ANOMÁLIA: ") && logs[0].contains(&server.name), "{}", logs[0]);
+        assert!(d.check(&mut conn, &server, &spike(51)).unwrap().is_empty());
+
+        let events = get_recent_server_events(&mut conn, server.id, "anomaly.", 10).unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, "anomaly.cpu");
+        assert!(events[0].message.contains("CPU 95.0"), "{}", events[0].message);
+    }
+}
@@ -2,19 +2,88 @@
 
 use diesel::prelude::*;
 pub use diesel::sqlite::SqliteConnection;
-use crate::models::Server;
-use crate::schema::servers;
+use chrono::{NaiveDateTime, Utc};
+use crate::models::{Alert, AlertRule, Channel, CpuProfile, Delivery, EscalationPolicy, Event, FlapState, HistoryEntry, LifecycleCommand, MaintenanceWindow, Metrics, PrometheusSource, Server, ServerTransition, Silence, SloTarget};
+use crate::schema::{agent_tokens, alert_rules, alerts, channels, deliveries, cpu_profiles, escalation_policies, events, flap_state, history, lifecycle_commands, maintenance_windows, prometheus_sources, server_dependencies, server_tags, server_transitions, servers, settings, silences, slo_targets};
+use std::collections::HashMap;
+use std::sync::OnceLock;
 
-pub fn establish_connection() -> SqliteConnection {
-    let database_url = "servers.db";
-    let mut conn = SqliteConnection::establish(database_url).expect("Chyba DB");
+//stlpce pridane neskor, starsie databazy ich dostanu cez ALTER (duplicate column = uz existuje)
+const UPGRADES: &[&str] = &[
+    "ALTER TABLE servers ADD COLUMN source TEXT NOT NULL DEFAULT 'sim'",
+    "ALTER TABLE servers ADD COLUMN interval_ms INTEGER NOT NULL DEFAULT 1000",
+    "ALTER TABLE servers ADD COLUMN timeout_ms INTEGER NOT NULL DEFAULT 2000",
+    "ALTER TABLE servers ADD COLUMN host TEXT NOT NULL DEFAULT '127.0.0.1'",
+    "ALTER TABLE servers ADD COLUMN probe TEXT NOT NULL DEFAULT 'auto'",
+    "ALTER TABLE history ADD COLUMN disk_usage REAL NOT NULL DEFAULT 0",
+    "ALTER TABLE history ADD COLUMN disk_io REAL NOT NULL DEFAULT 0",
+    "ALTER TABLE history ADD COLUMN net_rx REAL NOT NULL DEFAULT 0",
+    "ALTER TABLE history ADD COLUMN net_tx REAL NOT NULL DEFAULT 0",
+    "ALTER TABLE history ADD COLUMN load_avg REAL NOT NULL DEFAULT 0",
+    "ALTER TABLE history ADD COLUMN connections INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE history ADD COLUMN rtt_min INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE history ADD COLUMN rtt_max INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE history ADD COLUMN rtt_p95 INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE history ADD COLUMN loss_pct REAL NOT NULL DEFAULT 0",
+    "ALTER TABLE alert_rules ADD COLUMN channels TEXT NOT NULL DEFAULT ''",
+    "ALTER TABLE alert_rules ADD COLUMN severity TEXT NOT NULL DEFAULT 'warning'",
+    "ALTER TABLE alerts ADD COLUMN ack_by TEXT",
+    "ALTER TABLE alerts ADD COLUMN ack_at DATETIME",
+    "ALTER TABLE alerts ADD COLUMN ack_note TEXT",
+    "ALTER TABLE alerts ADD COLUMN esc_level INTEGER NOT NULL DEFAULT 0",
+    "ALTER TABLE alerts ADD COLUMN esc_at DATETIME",
+    "ALTER TABLE alerts ADD COLUMN caused_by TEXT",
+    "ALTER TABLE alerts ADD COLUMN muted BOOLEAN NOT NULL DEFAULT 0",
+];
+
+const CPU_SEEDED: &str = "cpu_profiles_seeded";
+
+//testy pouzivaju vlastny docasny subor, aby nesiahali na servers.db
+pub fn database_url() -> &'static str {
+    static URL: OnceLock<String> = OnceLock::new();
+    URL.get_or_init(|| {
+        if cfg!(test) {
+            std::env::temp_dir().join(format!("monitor-test-{}.db", std::process::id())).to_string_lossy().into_owned()
+        } else {
+            "servers.db".to_string()
+        }
+    })
+}
+
+//otvori databazu, vytvori / doplni schemu; GUI, daemon a CLI zdielaju jeden subor
+pub fn establish_connection() -> Result<SqliteConnection, String> {
+    let mut conn = SqliteConnection::establish(database_url()).map_err(|e| format!("Chyba DB {}: {}", database_url(), e))?;
+    //WAL: citatelia neblokuju zapis daemona; busy_timeout: pri zamku sa caka, nie hned chyba
+    diesel::sql_query("PRAGMA busy_timeout = 5000;").execute(&mut conn).map_err(|e| format!("Chyba DB: {}", e))?;
+    diesel::sql_query("PRAGMA journal_mode = WAL;").execute(&mut conn).map_err(|e| format!("Chyba DB: {}", e))?;
     let sql = include_str!("../../schema.sql");
     for query in sql.split(';') {
         if !query.trim().is_empty() {
-            diesel::sql_query(query).execute(&mut conn).expect("Chyba schemy");
+            diesel::sql_query(query).execute(&mut conn).map_err(|e| format!("Chyba schémy: {}", e))?;
+        }
+    }
+    for query in UPGRADES {
+        if let Err(e) = diesel::sql_query(*query).execute(&mut conn)
+            && !e.to_string().contains("duplicate column name") {
+            return Err(format!("Chyba schémy: {}", e));
         }
     }
-    conn
+    //vstavany katalog iba raz, odstranene profily sa nevracaju
+    if get_setting(&mut conn, CPU_SEEDED).map_err(|e| format!("Chyba nastavení: {}", e))?.is_none() {
+        diesel::insert_or_ignore_into(cpu_profiles::table)
+            .values(&CpuProfile::builtin())
+            .execute(&mut conn)
+            .and_then(|_| set_setting(&mut conn, CPU_SEEDED, "1"))
+            .map_err(|e| format!("Chyba CPU katalógu: {}", e))?;
+    }
+    Ok(conn)
+}
+
+//pre kratke zapisy z paralelnych uloh: bez schemy (tu by sa zamkla), s cakanim na zamok
+pub fn open_connection() -> ConnectionResult<SqliteConnection> {
+    let mut conn = SqliteConnection::establish(database_url())?;
+    diesel::sql_query("PRAGMA busy_timeout = 5000;").execute(&mut conn).ok();
+    Ok(conn)
 }
 
 pub fn add_server(conn: &mut SqliteConnection, name_str: &str, ram: f32, port_val: i32, cpu: &str) {
@@ -33,12 +102,587 @@ pub fn get_all_servers(conn: &mut SqliteConnection) -> QueryResult<Vec<Server>>
     servers::table.load::<Server>(conn)
 }
 
+pub fn get_status(conn: &mut SqliteConnection, s_id: i32) -> QueryResult<Option<String>> {
+    servers::table.find(s_id).select(servers::status).first(conn).optional()
+}
+
+//kazda zmena stavu sa zapise aj ako udalost, z nich sa pocita dostupnost
+//rovnaky stav znova neprida udalost
 pub fn update_status(conn: &mut SqliteConnection, s_id: i32, new_status: &str) {
+    let old: Option<String> = servers::table.find(s_id).select(servers::status).first(conn).optional().expect("Chyba statusu");
     diesel::update(servers::table.filter(servers::id.eq(s_id)))
         .set(servers::status.eq(new_status))
         .execute(conn).expect("Chyba statusu");
+    if old.as_deref() != Some(new_status) {
+        record_event(conn, s_id, "status", new_status).ok();
+    }
+}
+
+//zmena stavu iba ak server este ma stav `from`, 0 = niekto iny ho uz zmenil
+pub fn claim_status(conn: &mut SqliteConnection, s_id: i32, from: &str, to: &str) -> QueryResult<usize> {
+    let n = diesel::update(servers::table.filter(servers::id.eq(s_id)).filter(servers::status.eq(from)))
+        .set(servers::status.eq(to))
+        .execute(conn)?;
+    if n > 0 && from != to {
+        record_event(conn, s_id, "status", to)?;
+    }
+    Ok(n)
+}
+
+pub fn update_source(conn: &mut SqliteConnection, s_id: i32, new_source: &str) -> QueryResult<usize> {
+    diesel::update(servers::table.filter(servers::id.eq(s_id)))
+        .set(servers::source.eq(new_source))
+        .execute(conn)
+}
+
+pub fn update_probe(conn: &mut SqliteConnection, s_id: i32, new_host: &str, new_probe: &str) -> QueryResult<usize> {
+    diesel::update(servers::table.filter(servers::id.eq(s_id)))
+        .set((servers::host.eq(new_host), servers::probe.eq(new_probe)))
+        .execute(conn)
+}
+
+pub fn update_interval(conn: &mut SqliteConnection, s_id: i32, interval: i32, timeout: i32) -> QueryResult<usize> {
+    diesel::update(servers::table.filter(servers::id.eq(s_id)))
+        .set((servers::interval_ms.eq(interval), servers::timeout_ms.eq(timeout)))
+        .execute(conn)
 }
 
 pub fn remove_server(conn: &mut SqliteConnection, target_id: i32) -> QueryResult<usize> {
+    diesel::delete(prometheus_sources::table.filter(prometheus_sources::server_id.eq(target_id))).execute(conn)?;
+    diesel::delete(agent_tokens::table.filter(agent_tokens::server_id.eq(target_id))).execute(conn)?;
+    diesel::delete(events::table.filter(events::server_id.eq(target_id))).execute(conn)?;
+    diesel::delete(server_tags::table.filter(server_tags::server_id.eq(target_id))).execute(conn)?;
+    diesel::delete(flap_state::table.filter(flap_state::server_id.eq(target_id))).execute(conn)?;
+    diesel::delete(lifecycle_commands::table.filter(lifecycle_commands::server_id.eq(target_id))).execute(conn)?;
+    diesel::delete(server_transitions::table.filter(server_transitions::server_id.eq(target_id))).execute(conn)?;
+    diesel::delete(server_dependencies::table.filter(server_dependencies::server_id.eq(target_id).or(server_dependencies::depends_on.eq(target_id)))).execute(conn)?;
+    let alert_ids: Vec<i32> = alerts::table.filter(alerts::server_id.eq(target_id)).select(alerts::id).load(conn)?;
+    diesel::delete(deliveries::table.filter(deliveries::alert_id.eq_any(&alert_ids))).execute(conn)?;
+    diesel::delete(alerts::table.filter(alerts::server_id.eq(target_id))).execute(conn)?;
     diesel::delete(servers::table.filter(servers::id.eq(target_id))).execute(conn)
-}
\ No newline at end of file
+}
+
+pub fn get_cpu_profiles(conn: &mut SqliteConnection) -> QueryResult<Vec<CpuProfile>> {
+    cpu_profiles::table.order(cpu_profiles::name).load::<CpuProfile>(conn)
+}
+
+pub fn save_cpu_profile(conn: &mut SqliteConnection, profile: &CpuProfile) -> QueryResult<usize> {
+    diesel::replace_into(cpu_profiles::table).values(profile).execute(conn)
+}
+
+pub fn remove_cpu_profile(conn: &mut SqliteConnection, profile_name: &str) -> QueryResult<usize> {
+    diesel::delete(cpu_profiles::table.filter(cpu_profiles::name.eq(profile_name))).execute(conn)
+}
+
+pub fn record_metrics(conn: &mut SqliteConnection, s_id: i32, m: &Metrics) -> QueryResult<usize> {
+    diesel::insert_into(history::table)
+        .values((
+            history::server_id.eq(s_id),
+            history::timestamp.eq(Utc::now().naive_utc()),
+            history::response_ms.eq(m.response_ms),
+            history::ram_usage.eq(m.ram_usage),
+            history::cpu_usage.eq(m.cpu_usage),
+            history::disk_usage.eq(m.disk_usage),
+            history::disk_io.eq(m.disk_io),
+            history::net_rx.eq(m.net_rx),
+            history::net_tx.eq(m.net_tx),
+            history::load_avg.eq(m.load_avg),
+            history::connections.eq(m.connections),
+            history::rtt_min.eq(m.rtt_min),
+            history::rtt_max.eq(m.rtt_max),
+            history::rtt_p95.eq(m.rtt_p95),
+            history::loss_pct.eq(m.loss_pct),
+        ))
+        .execute(conn)
+}
+
+pub fn get_history(conn: &mut SqliteConnection, s_id: i32, since: NaiveDateTime) -> QueryResult<Vec<HistoryEntry>> {
+    history::table
+        .filter(history::server_id.eq(s_id))
+        .filter(history::timestamp.ge(since))
+        .order(history::timestamp.asc())
+        .select(HistoryEntry::as_select())
+        .load(conn)
+}
+
+#[derive(QueryableByName)]
+struct FailedSample {
+    #[diesel(sql_type = diesel::sql_types::Timestamp)]
+    failed_at: NaiveDateTime,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Timestamp>)]
+    next_at: Option<NaiveDateTime>,
+}
+
+//neuspesne merania v okne s casom nasledujuceho merania, bez nacitania celej historie
+pub fn get_failed_samples(conn: &mut SqliteConnection, s_id: i32, since: NaiveDateTime, until: NaiveDateTime) -> QueryResult<Vec<(NaiveDateTime, Option<NaiveDateTime>)>> {
+    use diesel::sql_types::{Integer, Timestamp};
+    let rows: Vec<FailedSample> = diesel::sql_query(
+        "SELECT h.timestamp AS failed_at, \
+            (SELECT MIN(n.timestamp) FROM history n WHERE n.server_id = h.server_id AND n.timestamp > h.timestamp) AS next_at \
+         FROM history h \
+         WHERE h.server_id = ? AND h.timestamp >= ? AND h.timestamp < ? AND h.response_ms < 0 \
+         ORDER BY h.timestamp",
+    )
+    .bind::<Integer, _>(s_id)
+    .bind::<Timestamp, _>(since)
+    .bind::<Timestamp, _>(until)
+    .load(conn)?;
+    Ok(rows.into_iter().map(|r| (r.failed_at, r.next_at)).collect())
+}
+
+pub fn get_latest_history(conn: &mut SqliteConnection, s_id: i32) -> QueryResult<Option<HistoryEntry>> {
+    history::table
+        .filter(history::server_id.eq(s_id))
+        .order(history::timestamp.desc())
+        .select(HistoryEntry::as_select())
+        .first(conn)
+        .optional()
+}
+
+pub fn get_prometheus_sources(conn: &mut SqliteConnection) -> QueryResult<Vec<PrometheusSource>> {
+    prometheus_sources::table.load::<PrometheusSource>(conn)
+}
+
+pub fn save_prometheus_source(conn: &mut SqliteConnection, source: &PrometheusSource) -> QueryResult<usize> {
+    diesel::replace_into(prometheus_sources::table).values(source).execute(conn)
+}
+
+pub fn set_agent_token(conn: &mut SqliteConnection, s_id: i32, new_token: &str) -> QueryResult<usize> {
+    diesel::replace_into(agent_tokens::table)
+        .values((agent_tokens::server_id.eq(s_id), agent_tokens::token.eq(new_token)))
+        .execute(conn)
+}
+
+pub fn find_agent_server(conn: &mut SqliteConnection, name_str: &str, token_str: &str) -> QueryResult<Option<Server>> {
+    servers::table
+        .inner_join(agent_tokens::table)
+        .filter(servers::name.eq(name_str))
+        .filter(agent_tokens::token.eq(token_str))
+        .select(Server::as_select())
+        .first(conn)
+        .optional()
+}
+
+pub fn get_setting(conn: &mut SqliteConnection, key_str: &str) -> QueryResult<Option<String>> {
+    settings::table.filter(settings::key.eq(key_str)).select(settings::value).first(conn).optional()
+}
+
+//chybajuce alebo neplatne nastavenie = predvolena hodnota
+pub fn setting_or<T: std::str::FromStr>(conn: &mut SqliteConnection, key_str: &str, default: T) -> T {
+    get_setting(conn, key_str).ok().flatten().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+pub fn set_setting(conn: &mut SqliteConnection, key_str: &str, value_str: &str) -> QueryResult<usize> {
+    diesel::replace_into(settings::table)
+        .values((settings::key.eq(key_str), settings::value.eq(value_str)))
+        .execute(conn)
+}
+
+pub fn remove_setting(conn: &mut SqliteConnection, key_str: &str) -> QueryResult<usize> {
+    diesel::delete(settings::table.filter(settings::key.eq(key_str))).execute(conn)
+}
+
+pub fn get_settings(conn: &mut SqliteConnection) -> QueryResult<Vec<(String, String)>> {
+    settings::table.order(settings::key).load(conn)
+}
+
+pub fn record_event(conn: &mut SqliteConnection, s_id: i32, kind_str: &str, msg: &str) -> QueryResult<usize> {
+    diesel::insert_into(events::table)
+        .values((
+            events::server_id.eq(s_id),
+            events::timestamp.eq(Utc::now().naive_utc()),
+            events::kind.eq(kind_str),
+            events::message.eq(msg),
+        ))
+        .execute(conn)
+}
+
+//udalosti od `since`, volitelne iba s danou predponou kind (napr. "anomaly.")
+pub fn get_events(conn: &mut SqliteConnection, since: NaiveDateTime, kind_prefix: Option<&str>) -> QueryResult<Vec<Event>> {
+    let mut query = events::table
+        .filter(events::timestamp.ge(since))
+        .order(events::timestamp.asc())
+        .select(Event::as_select())
+        .into_boxed();
+    if let Some(prefix) = kind_prefix {
+        query = query.filter(events::kind.like(format!("{}%", prefix)));
+    }
+    query.load(conn)
+}
+
+//udalosti jedneho servera v intervale [since, until)
+pub fn get_server_events(conn: &mut SqliteConnection, s_id: i32, kind_str: &str, since: NaiveDateTime, until: NaiveDateTime) -> QueryResult<Vec<Event>> {
+    events::table
+        .filter(events::server_id.eq(s_id))
+        .filter(events::kind.eq(kind_str))
+        .filter(events::timestamp.ge(since))
+        .filter(events::timestamp.lt(until))
+        .order(events::timestamp.asc())
+        .select(Event::as_select())
+        .load(conn)
+}
+
+//poslednych `limit` udalosti servera s predponou kind, najnovsie prve
+pub fn get_recent_server_events(conn: &mut SqliteConnection, s_id: i32, kind_prefix: &str, limit: i64) -> QueryResult<Vec<Event>> {
+    events::table
+        .filter(events::server_id.eq(s_id))
+        .filter(events::kind.like(format!("{}%", kind_prefix)))
+        .order(events::id.desc())
+        .limit(limit)
+        .select(Event::as_select())
+        .load(conn)
+}
+
+pub fn last_event_before(conn: &mut SqliteConnection, s_id: i32, kind_str: &str, before: NaiveDateTime) -> QueryResult<Option<Event>> {
+    events::table
+        .filter(events::server_id.eq(s_id))
+        .filter(events::kind.eq(kind_str))
+        .filter(events::timestamp.lt(before))
+        .order(events::timestamp.desc())
+        .select(Event::as_select())
+        .first(conn)
+        .optional()
+}
+
+pub fn add_tag(conn: &mut SqliteConnection, s_id: i32, tag_str: &str) -> QueryResult<usize> {
+    diesel::insert_or_ignore_into(server_tags::table)
+        .values((server_tags::server_id.eq(s_id), server_tags::tag.eq(tag_str)))
+        .execute(conn)
+}
+
+pub fn remove_tag(conn: &mut SqliteConnection, s_id: i32, tag_str: &str) -> QueryResult<usize> {
+    diesel::delete(server_tags::table.filter(server_tags::server_id.eq(s_id)).filter(server_tags::tag.eq(tag_str))).execute(conn)
+}
+
+//tagy vsetkych serverov podla id
+pub fn get_tags(conn: &mut SqliteConnection) -> QueryResult<HashMap<i32, Vec<String>>> {
+    let rows: Vec<(i32, String)> = server_tags::table.order(server_tags::tag).load(conn)?;
+    let mut tags: HashMap<i32, Vec<String>> = HashMap::new();
+    for (s_id, tag) in rows {
+        tags.entry(s_id).or_default().push(tag);
+    }
+    Ok(tags)
+}
+
+//cyklus treba skontrolovat pred zapisom (deps::DependencyGraph)
+pub fn add_dependency(conn: &mut SqliteConnection, s_id: i32, upstream: i32) -> QueryResult<usize> {
+    diesel::insert_or_ignore_into(server_dependencies::table)
+        .values((server_dependencies::server_id.eq(s_id), server_dependencies::depends_on.eq(upstream)))
+        .execute(conn)
+}
+
+pub fn remove_dependency(conn: &mut SqliteConnection, s_id: i32, upstream: i32) -> QueryResult<usize> {
+    diesel::delete(server_dependencies::table.filter(server_dependencies::server_id.eq(s_id)).filter(server_dependencies::depends_on.eq(upstream))).execute(conn)
+}
+
+//dvojice (server, od coho zavisi)
+pub fn get_dependencies(conn: &mut SqliteConnection) -> QueryResult<Vec<(i32, i32)>> {
+    server_dependencies::table.order((server_dependencies::server_id, server_dependencies::depends_on)).load(conn)
+}
+
+pub fn set_alert_cause(conn: &mut SqliteConnection, alert: i32, cause: Option<&str>) -> QueryResult<usize> {
+    diesel::update(alerts::table.filter(alerts::id.eq(alert))).set(alerts::caused_by.eq(cause)).execute(conn)
+}
+
+pub fn set_alert_muted(conn: &mut SqliteConnection, alert: i32, muted_flag: bool) -> QueryResult<usize> {
+    diesel::update(alerts::table.filter(alerts::id.eq(alert))).set(alerts::muted.eq(muted_flag)).execute(conn)
+}
+
+pub fn get_slo_targets(conn: &mut SqliteConnection) -> QueryResult<Vec<SloTarget>> {
+    slo_targets::table.order(slo_targets::id).load(conn)
+}
+
+pub fn add_slo_target(conn: &mut SqliteConnection, scope_str: &str, objective_pct: f32, days: i32) -> QueryResult<usize> {
+    diesel::insert_into(slo_targets::table)
+        .values((slo_targets::scope.eq(scope_str), slo_targets::objective.eq(objective_pct), slo_targets::window_days.eq(days)))
+        .execute(conn)
+}
+
+pub fn remove_slo_target(conn: &mut SqliteConnection, target_id: i32) -> QueryResult<usize> {
+    diesel::delete(slo_targets::table.filter(slo_targets::id.eq(target_id))).execute(conn)
+}
+
+pub fn get_alert_rules(conn: &mut SqliteConnection) -> QueryResult<Vec<AlertRule>> {
+    alert_rules::table.order(alert_rules::id).load(conn)
+}
+
+pub fn add_alert_rule(conn: &mut SqliteConnection, name_str: &str, scope_str: &str, expr_str: &str, channel_names: &str, severity_str: &str) -> QueryResult<usize> {
+    diesel::insert_into(alert_rules::table)
+        .values((
+            alert_rules::name.eq(name_str),
+            alert_rules::scope.eq(scope_str),
+            alert_rules::expr.eq(expr_str),
+            alert_rules::channels.eq(channel_names),
+            alert_rules::severity.eq(severity_str),
+        ))
+        .execute(conn)
+}
+
+//spolu s pravidlom zmiznu aj jeho alerty
+pub fn remove_alert_rule(conn: &mut SqliteConnection, rule: i32) -> QueryResult<usize> {
+    let ids: Vec<i32> = alerts::table.filter(alerts::rule_id.eq(rule)).select(alerts::id).load(conn)?;
+    diesel::delete(deliveries::table.filter(deliveries::alert_id.eq_any(&ids))).execute(conn)?;
+    diesel::delete(alerts::table.filter(alerts::rule_id.eq(rule))).execute(conn)?;
+    diesel::delete(alert_rules::table.filter(alert_rules::id.eq(rule))).execute(conn)
+}
+
+pub fn open_alert(conn: &mut SqliteConnection, rule: i32, s_id: i32, val: f32, at: NaiveDateTime) -> QueryResult<Alert> {
+    diesel::insert_into(alerts::table)
+        .values((alerts::rule_id.eq(rule), alerts::server_id.eq(s_id), alerts::state.eq("pending"), alerts::value.eq(val), alerts::started_at.eq(at)))
+        .returning(Alert::as_returning())
+        .get_result(conn)
+}
+
+pub fn fire_alert(conn: &mut SqliteConnection, alert: i32, val: f32, at: NaiveDateTime) -> QueryResult<usize> {
+    diesel::update(alerts::table.filter(alerts::id.eq(alert)))
+        .set((alerts::state.eq("firing"), alerts::value.eq(val), alerts::fired_at.eq(at)))
+        .execute(conn)
+}
+
+pub fn resolve_alert(conn: &mut SqliteConnection, alert: i32, at: NaiveDateTime) -> QueryResult<usize> {
+    diesel::update(alerts::table.filter(alerts::id.eq(alert)))
+        .set((alerts::state.eq("resolved"), alerts::resolved_at.eq(at)))
+        .execute(conn)
+}
+
+//pending a firing alerty
+pub fn get_active_alerts(conn: &mut SqliteConnection) -> QueryResult<Vec<Alert>> {
+    alerts::table
+        .filter(alerts::state.ne("resolved"))
+        .order(alerts::started_at.asc())
+        .select(Alert::as_select())
+        .load(conn)
+}
+
+//vyriesene alerty, najnovsie prve
+pub fn get_alert_history(conn: &mut SqliteConnection, limit: i64) -> QueryResult<Vec<Alert>> {
+    alerts::table
+        .filter(alerts::state.eq("resolved"))
+        .order(alerts::resolved_at.desc())
+        .limit(limit)
+        .select(Alert::as_select())
+        .load(conn)
+}
+
+pub fn get_alert(conn: &mut SqliteConnection, alert: i32) -> QueryResult<Option<Alert>> {
+    alerts::table.filter(alerts::id.eq(alert)).select(Alert::as_select()).first(conn).optional()
+}
+
+//iba aktivny alert, vyrieseny uz potvrdit nema zmysel
+pub fn ack_alert(conn: &mut SqliteConnection, alert: i32, by: &str, note: &str) -> QueryResult<usize> {
+    diesel::update(alerts::table.filter(alerts::id.eq(alert)).filter(alerts::state.ne("resolved")))
+        .set((alerts::ack_by.eq(by), alerts::ack_at.eq(Utc::now().naive_utc()), alerts::ack_note.eq(note)))
+        .execute(conn)
+}
+
+pub fn escalate_alert(conn: &mut SqliteConnection, alert: i32, level: i32, at: NaiveDateTime) -> QueryResult<usize> {
+    diesel::update(alerts::table.filter(alerts::id.eq(alert)))
+        .set((alerts::esc_level.eq(level), alerts::esc_at.eq(at)))
+        .execute(conn)
+}
+
+pub fn get_escalation_policies(conn: &mut SqliteConnection) -> QueryResult<Vec<EscalationPolicy>> {
+    escalation_policies::table.order(escalation_policies::id).load(conn)
+}
+
+pub fn add_escalation_policy(conn: &mut SqliteConnection, name_str: &str, scope_str: &str, every: i32, tiers_str: &str, repeats: i32) -> QueryResult<usize> {
+    diesel::insert_into(escalation_policies::table)
+        .values((
+            escalation_policies::name.eq(name_str),
+            escalation_policies::scope.eq(scope_str),
+            escalation_policies::every_mins.eq(every),
+            escalation_policies::tiers.eq(tiers_str),
+            escalation_policies::max_repeats.eq(repeats),
+        ))
+        .execute(conn)
+}
+
+pub fn remove_escalation_policy(conn: &mut SqliteConnection, policy: i32) -> QueryResult<usize> {
+    diesel::delete(escalation_policies::table.filter(escalation_policies::id.eq(policy))).execute(conn)
+}
+
+pub fn get_channels(conn: &mut SqliteConnection) -> QueryResult<Vec<Channel>> {
+    channels::table.order(channels::id).load(conn)
+}
+
+pub fn add_channel(conn: &mut SqliteConnection, name_str: &str, kind_str: &str, target_str: &str, config_json: &str) -> QueryResult<usize> {
+    diesel::insert_into(channels::table)
+        .values((channels::name.eq(name_str), channels::kind.eq(kind_str), channels::target.eq(target_str), channels::config.eq(config_json)))
+        .execute(conn)
+}
+
+pub fn remove_channel(conn: &mut SqliteConnection, channel: i32) -> QueryResult<usize> {
+    diesel::delete(deliveries::table.filter(deliveries::channel_id.eq(channel))).execute(conn)?;
+    diesel::delete(channels::table.filter(channels::id.eq(channel))).execute(conn)
+}
+
+pub fn record_delivery(conn: &mut SqliteConnection, alert: i32, channel: i32, state_str: &str, attempt_no: i32, error_msg: Option<&str>) -> QueryResult<usize> {
+    diesel::insert_into(deliveries::table)
+        .values((
+            deliveries::alert_id.eq(alert),
+            deliveries::channel_id.eq(channel),
+            deliveries::state.eq(state_str),
+            deliveries::attempt.eq(attempt_no),
+            deliveries::ok.eq(error_msg.is_none()),
+            deliveries::error.eq(error_msg),
+            deliveries::timestamp.eq(Utc::now().naive_utc()),
+        ))
+        .execute(conn)
+}
+
+//najnovsie pokusy o dorucenie
+pub fn get_deliveries(conn: &mut SqliteConnection, limit: i64) -> QueryResult<Vec<Delivery>> {
+    deliveries::table.order(deliveries::id.desc()).limit(limit).select(Delivery::as_select()).load(conn)
+}
+
+pub fn get_maintenance_windows(conn: &mut SqliteConnection) -> QueryResult<Vec<MaintenanceWindow>> {
+    maintenance_windows::table.order(maintenance_windows::starts_at).load(conn)
+}
+
+pub fn add_maintenance_window(conn: &mut SqliteConnection, scope_str: &str, start: NaiveDateTime, mins: i32, recur_str: &str, comment_str: &str) -> QueryResult<usize> {
+    diesel::insert_into(maintenance_windows::table)
+        .values((
+            maintenance_windows::scope.eq(scope_str),
+            maintenance_windows::starts_at.eq(start),
+            maintenance_windows::duration_mins.eq(mins),
+            maintenance_windows::recur.eq(recur_str),
+            maintenance_windows::comment.eq(comment_str),
+        ))
+        .execute(conn)
+}
+
+pub fn remove_maintenance_window(conn: &mut SqliteConnection, window: i32) -> QueryResult<usize> {
+    diesel::delete(maintenance_windows::table.filter(maintenance_windows::id.eq(window))).execute(conn)
+}
+
+//iba este platne
+pub fn get_silences(conn: &mut SqliteConnection) -> QueryResult<Vec<Silence>> {
+    silences::table.filter(silences::expires_at.gt(Utc::now().naive_utc())).order(silences::expires_at).load(conn)
+}
+
+pub fn add_silence(conn: &mut SqliteConnection, matcher_str: &str, author_str: &str, comment_str: &str, until: NaiveDateTime) -> QueryResult<Silence> {
+    diesel::insert_into(silences::table)
+        .values((
+            silences::matcher.eq(matcher_str),
+            silences::author.eq(author_str),
+            silences::comment.eq(comment_str),
+            silences::created_at.eq(Utc::now().naive_utc()),
+            silences::expires_at.eq(until),
+        ))
+        .returning(Silence::as_returning())
+        .get_result(conn)
+}
+
+//silence sa nemaze, iba sa ukonci, aby ostala v historii
+pub fn expire_silence(conn: &mut SqliteConnection, silence: i32) -> QueryResult<usize> {
+    let now = Utc::now().naive_utc();
+    diesel::update(silences::table.filter(silences::id.eq(silence)).filter(silences::expires_at.gt(now)))
+        .set(silences::expires_at.eq(now))
+        .execute(conn)
+}
+
+pub fn get_flap_states(conn: &mut SqliteConnection) -> QueryResult<Vec<FlapState>> {
+    flap_state::table.load(conn)
+}
+
+pub fn save_flap_state(conn: &mut SqliteConnection, state: &FlapState) -> QueryResult<usize> {
+    diesel::replace_into(flap_state::table).values(state).execute(conn)
+}
+
+pub fn get_lifecycle_commands(conn: &mut SqliteConnection) -> QueryResult<Vec<LifecycleCommand>> {
+    lifecycle_commands::table.order(lifecycle_commands::server_id).load(conn)
+}
+
+pub fn get_lifecycle_command(conn: &mut SqliteConnection, s_id: i32) -> QueryResult<Option<LifecycleCommand>> {
+    lifecycle_commands::table.find(s_id).first(conn).optional()
+}
+
+pub fn save_lifecycle_command(conn: &mut SqliteConnection, cmd: &LifecycleCommand) -> QueryResult<usize> {
+    diesel::replace_into(lifecycle_commands::table).values(cmd).execute(conn)
+}
+
+pub fn remove_lifecycle_command(conn: &mut SqliteConnection, s_id: i32) -> QueryResult<usize> {
+    diesel::delete(lifecycle_commands::table.find(s_id)).execute(conn)
+}
+
+pub fn get_transitions(conn: &mut SqliteConnection) -> QueryResult<Vec<ServerTransition>> {
+    server_transitions::table.load(conn)
+}
+
+pub fn get_transition(conn: &mut SqliteConnection, s_id: i32) -> QueryResult<Option<ServerTransition>> {
+    server_transitions::table.find(s_id).first(conn).optional()
+}
+
+//novy prechod nahradi predchadzajuci zaznam servera
+pub fn begin_transition(conn: &mut SqliteConnection, t: &ServerTransition) -> QueryResult<usize> {
+    diesel::replace_into(server_transitions::table).values(t).execute(conn)
+}
+
+pub fn fail_transition(conn: &mut SqliteConnection, s_id: i32, why: &str) -> QueryResult<usize> {
+    diesel::update(server_transitions::table.find(s_id))
+        .set(server_transitions::reason.eq(why))
+        .execute(conn)
+}
+
+pub fn end_transition(conn: &mut SqliteConnection, s_id: i32) -> QueryResult<usize> {
+    diesel::delete(server_transitions::table.find(s_id)).execute(conn)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    //schema sa vytvori raz, testy potom beziace paralelne iba otvaraju spojenia
+    pub(crate) fn test_connection() -> SqliteConnection {
+        static INIT: OnceLock<()> = OnceLock::new();
+        INIT.get_or_init(|| {
+            //databazy z predoslych behov, ktorych proces uz neexistuje
+            for entry in std::fs::read_dir(std::env::temp_dir()).into_iter().flatten().flatten() {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if let Some(pid) = name.strip_prefix("monitor-test-").and_then(|n| n.strip_suffix(".db"))
+                    && !std::path::Path::new("/proc").join(pid).exists() {
+                    std::fs::remove_file(entry.path()).ok();
+                }
+            }
+            establish_connection().unwrap();
+        });
+        open_connection().unwrap()
+    }
+
+    //server s jedinecnym menom, testy zdielaju jednu databazu
+    pub(crate) fn test_server(conn: &mut SqliteConnection, prefix: &str, port_val: i32) -> Server {
+        static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let unique = format!("{}-{}", prefix, NEXT.fetch_add(1, std::sync::atomic::Ordering::SeqCst));
+        add_server(conn, &unique, 8.0, port_val, "intel-i5");
+        servers::table.filter(servers::name.eq(&unique)).first(conn).unwrap()
+    }
+
+    #[test]
+    fn same_status_records_no_event() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "status", 80);
+        let since = Utc::now().naive_utc() - chrono::Duration::seconds(1);
+        update_status(&mut conn, server.id, "ON");
+        update_status(&mut conn, server.id, "ON");
+        update_status(&mut conn, server.id, "OFF");
+        let until = Utc::now().naive_utc() + chrono::Duration::seconds(1);
+        let kinds: Vec<String> = get_server_events(&mut conn, server.id, "status", since, until).unwrap().into_iter().map(|e| e.message).collect();
+        assert_eq!(kinds, ["ON", "OFF"]);
+    }
+
+    #[test]
+    fn only_one_claim_wins() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "claim", 80);
+        let since = Utc::now().naive_utc() - chrono::Duration::seconds(1);
+        update_status(&mut conn, server.id, "OFF");
+        assert_eq!(claim_status(&mut conn, server.id, "OFF", "Starting").unwrap(), 1);
+        //druhy proces videl este OFF
+        assert_eq!(claim_status(&mut conn, server.id, "OFF", "Starting").unwrap(), 0);
+        assert_eq!(claim_status(&mut conn, server.id, "OFF", "Stopping").unwrap(), 0);
+        assert_eq!(get_status(&mut conn, server.id).unwrap().as_deref(), Some("Starting"));
+        let until = Utc::now().naive_utc() + chrono::Duration::seconds(1);
+        let kinds: Vec<String> = get_server_events(&mut conn, server.id, "status", since, until).unwrap().into_iter().map(|e| e.message).collect();
+        assert_eq!(kinds, ["OFF", "Starting"]);
+    }
+}
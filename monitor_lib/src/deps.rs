@@ -0,0 +1,173 @@
+//zavislosti medzi servermi: kontrola cyklov a hladanie pricinneho vypadku
+
+use crate::db::{get_all_servers, get_dependencies, SqliteConnection};
+use crate::models::Server;
+use diesel::QueryResult;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+const RELOAD_EVERY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    //server -> od coho zavisi
+    upstream: HashMap<i32, Vec<i32>>,
+    downstream: HashMap<i32, Vec<i32>>,
+}
+
+impl DependencyGraph {
+    pub fn from_edges(edges: &[(i32, i32)]) -> Self {
+        let mut g = DependencyGraph::default();
+        for &(s, up) in edges {
+            g.upstream.entry(s).or_default().push(up);
+            g.downstream.entry(up).or_default().push(s);
+        }
+        g
+    }
+
+    pub fn load(conn: &mut SqliteConnection) -> QueryResult<Self> {
+        Ok(DependencyGraph::from_edges(&get_dependencies(conn)?))
+    }
+
+    pub fn depends_on(&self, id: i32) -> &[i32] {
+        self.upstream.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn dependents(&self, id: i32) -> &[i32] {
+        self.downstream.get(&id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    //vsetky servery dosiahnutelne po hranach, bez pociatocneho
+    fn reachable(&self, id: i32, next: impl Fn(i32) -> Vec<i32>) -> Vec<i32> {
+        let mut seen = HashSet::from([id]);
+        let mut stack = vec![id];
+        let mut out = Vec::new();
+        while let Some(cur) = stack.pop() {
+            for n in next(cur) {
+                if seen.insert(n) {
+                    out.push(n);
+                    stack.push(n);
+                }
+            }
+        }
+        out
+    }
+
+    pub fn all_upstream(&self, id: i32) -> Vec<i32> {
+        self.reachable(id, |c| self.depends_on(c).to_vec())
+    }
+
+    pub fn all_downstream(&self, id: i32) -> Vec<i32> {
+        self.reachable(id, |c| self.dependents(c).to_vec())
+    }
+
+    //nova hrana server -> upstream by uzavrela kruh
+    pub fn would_cycle(&self, server: i32, upstream: i32) -> bool {
+        server == upstream || self.all_upstream(upstream).contains(&server)
+    }
+
+    //najvzdialenejsi nedostupny predok, ktory sam nema nedostupneho predka
+    pub fn root_cause(&self, id: i32, is_down: impl Fn(i32) -> bool) -> Option<i32> {
+        let down: Vec<i32> = self.all_upstream(id).into_iter().filter(|u| is_down(*u)).collect();
+        down.iter().copied().find(|d| !self.all_upstream(*d).iter().any(|u| down.contains(u)))
+    }
+}
+
+//graf s aktualnym stavom serverov pre potlacanie alertov
+#[derive(Default)]
+pub struct RootCauses {
+    graph: DependencyGraph,
+    servers: HashMap<i32, Server>,
+    //posledne meranie serverov, ktore su ON
+    up: HashMap<i32, bool>,
+    loaded_at: Option<Instant>,
+}
+
+impl RootCauses {
+    pub fn new() -> Self {
+        RootCauses::default()
+    }
+
+    pub fn refresh(&mut self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        if self.loaded_at.is_none_or(|t| t.elapsed() >= RELOAD_EVERY) {
+            self.graph = DependencyGraph::load(conn)?;
+            self.servers = get_all_servers(conn)?.into_iter().map(|s| (s.id, s)).collect();
+            self.loaded_at = Some(Instant::now());
+        }
+        Ok(())
+    }
+
+    pub fn observe(&mut self, server_id: i32, up: bool) {
+        self.up.insert(server_id, up);
+    }
+
+    //vypnuty server je pre zavisle servery tiez nedostupny
+    fn is_down(&self, id: i32) -> bool {
+        match self.servers.get(&id) {
+            Some(s) if s.status == "ON" => self.up.get(&id) == Some(&false),
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    //meno nadradeneho servera, ktory je pricinou
+    pub fn cause(&self, server_id: i32) -> Option<&str> {
+        let root = self.graph.root_cause(server_id, |id| self.is_down(id))?;
+        self.servers.get(&root).map(|s| s.name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn self_loop_is_cycle() {
+        let g = DependencyGraph::default();
+        assert!(g.would_cycle(1, 1));
+        assert!(!g.would_cycle(1, 2));
+    }
+
+    #[test]
+    fn indirect_cycle() {
+        //1 -> 2 -> 3
+        let g = DependencyGraph::from_edges(&[(1, 2), (2, 3)]);
+        assert!(g.would_cycle(3, 1));
+        assert!(g.would_cycle(3, 2));
+        assert!(!g.would_cycle(1, 3));
+        assert!(!g.would_cycle(4, 1));
+    }
+
+    #[test]
+    fn diamond_is_not_cycle() {
+        //1 zavisi od 2 a 3, oba od 4
+        let g = DependencyGraph::from_edges(&[(1, 2), (1, 3), (2, 4), (3, 4)]);
+        assert!(!g.would_cycle(1, 4));
+        assert!(g.would_cycle(4, 1));
+        let mut up = g.all_upstream(1);
+        up.sort();
+        assert_eq!(up, vec![2, 3, 4]);
+        let mut down = g.all_downstream(4);
+        down.sort();
+        assert_eq!(down, vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn root_cause_is_topmost_down() {
+        let g = DependencyGraph::from_edges(&[(1, 2), (1, 3), (2, 4), (3, 4)]);
+        assert_eq!(g.root_cause(1, |_| false), None);
+        assert_eq!(g.root_cause(1, |id| id == 3), Some(3));
+        //4 je predkom 2 aj 3, takze je pricinou
+        assert_eq!(g.root_cause(1, |id| id != 1), Some(4));
+        assert_eq!(g.root_cause(2, |id| id == 3), None);
+        assert_eq!(g.root_cause(4, |_| true), None);
+    }
+
+    #[test]
+    fn root_cause_terminates_on_cycle() {
+        //kruh z poskodenej DB nesmie zacyklit hladanie; v kruhu nie je jedina pricina
+        let g = DependencyGraph::from_edges(&[(1, 2), (2, 3), (3, 2)]);
+        assert_eq!(g.root_cause(1, |_| true), None);
+        assert_eq!(g.root_cause(1, |id| id == 2), Some(2));
+    }
+}
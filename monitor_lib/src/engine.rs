@@ -1,45 +1,343 @@
 //matematika za generovanim metrik
 
-use rand::Rng;
-use crate::models::Server;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use crate::latency::{LatencyStats, DEFAULT_SAMPLES};
+use crate::lifecycle;
+use crate::models::{CpuProfile, Metrics, Server};
+use crate::scenario::Scenario;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-pub async fn simulate_server_metrics(server: &Server) -> (i32, f32, f32) {
-    if matches!(server.status.as_str(), "OFF" | "/" | "STOPPING") {
-        return (0, 0.0, 0.0);
-    }
-
-    let mut rng = rand::rng();
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as f64;
-
-    let offset = (server.id as f64) * 1337.42;
-
-    let base_ms = match server.port {
-        80 | 443 => 15.0,
-        27017    => 5.0,
-        _        => 40.0,
-    };
-    
-    let ms = (base_ms + rng.random_range(-0.5..0.5)) as i32;
-
-    let (eff_mult, base_load) = match server.cpu_model.to_lowercase() {
-        m if m.contains("i9") || m.contains("ryzen 9") => (0.4, 15.0), 
-        m if m.contains("i7") || m.contains("ryzen 7") => (0.7, 25.0),
-        _ => (1.0, 35.0), 
-    };
-
-    let cpu_wave = ((now + offset) / 5000.0).sin(); 
-    let cpu_jitter = ((now + offset) / 1000.0).cos() * 0.5;
-    let cpu = (base_load + (cpu_wave * 15.0 * eff_mult) + cpu_jitter).clamp(1.0, 99.0) as f32;
-
-    let ram_offset = (server.id as f64) * 9876.54;
-    let ram_wave = ((now + ram_offset) / 12000.0).sin(); 
-    let server_base_ram = 0.2 + ((server.id % 5) as f32 * 0.1); 
-    let ram_percent = (server_base_ram + (ram_wave as f32 * 0.15)).clamp(0.1, 0.9);
-    let ram = (server.max_ram * ram_percent).clamp(0.1, server.max_ram);
-
-    (if server.port == 0 { -1 } else { ms.max(1) }, cpu, ram)
-}
\ No newline at end of file
+//zdroj casu pre simulator, aby sa dal podvrhnut v testoch a demach
+pub trait Clock: Send + Sync {
+    fn now_millis(&self) -> f64;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as f64
+    }
+}
+
+//rucne posuvany cas
+pub struct ManualClock {
+    millis: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new(start_millis: u64) -> Self {
+        ManualClock { millis: AtomicU64::new(start_millis) }
+    }
+
+    pub fn advance(&self, ms: u64) {
+        self.millis.fetch_add(ms, Ordering::SeqCst);
+    }
+
+    pub fn set(&self, ms: u64) {
+        self.millis.store(ms, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> f64 {
+        self.millis.load(Ordering::SeqCst) as f64
+    }
+}
+
+//2024-01-01 00:00:00 UTC, zaciatok pre simulaciu so seedom
+pub const SEED_EPOCH_MS: u64 = 1_704_067_200_000;
+
+//nahoda a cas jedneho servera, aby poradie vzorkovania neovplyvnilo priebeh
+struct Stream {
+    rng: StdRng,
+    //simulovany cas servera od zaciatku, pri krokovani sucet jeho intervalov
+    millis: f64,
+    //kedy sa v case servera zacal scenar
+    scenario_from: f64,
+}
+
+pub struct Simulator {
+    clock: Arc<dyn Clock>,
+    seed: Option<u64>,
+    //cas servera bezi po jeho vzorkach, nie podla hodin
+    stepped: bool,
+    streams: HashMap<i32, Stream>,
+    scenario: Option<(Scenario, f64)>,
+    profiles: Vec<CpuProfile>,
+    latency_samples: usize,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Simulator { clock: Arc::new(SystemClock), seed: None, stepped: false, streams: HashMap::new(), scenario: None, profiles: CpuProfile::builtin(), latency_samples: DEFAULT_SAMPLES }
+    }
+}
+
+impl Simulator {
+    pub fn new(seed: u64, clock: Arc<dyn Clock>) -> Self {
+        Simulator { clock, seed: Some(seed), ..Simulator::default() }
+    }
+
+    //rovnaky seed a rovnake servery = rovnaky priebeh kazdeho servera, kazda vzorka posunie jeho cas o interval
+    pub fn seeded(seed: u64) -> Self {
+        Simulator { stepped: true, ..Simulator::new(seed, Arc::new(ManualClock::new(SEED_EPOCH_MS))) }
+    }
+
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    //scenar sa prehrava od momentu nacitania
+    pub fn set_scenario(&mut self, scenario: Option<Scenario>) {
+        let start = self.clock.now_millis();
+        for stream in self.streams.values_mut() {
+            stream.scenario_from = stream.millis;
+        }
+        self.scenario = scenario.map(|s| (s, start));
+    }
+
+    fn stream(&self, server_id: i32) -> Stream {
+        let rng = match self.seed {
+            //rozne servery s rovnakym seedom nesmu mat rovnaku postupnost
+            Some(seed) => StdRng::seed_from_u64(seed ^ (server_id as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+            None => StdRng::from_os_rng(),
+        };
+        Stream { rng, millis: 0.0, scenario_from: 0.0 }
+    }
+
+    pub fn set_profiles(&mut self, profiles: Vec<CpuProfile>) {
+        self.profiles = profiles;
+    }
+
+    pub fn set_latency_samples(&mut self, n: usize) {
+        self.latency_samples = n.max(1);
+    }
+
+    pub fn profile(&self, cpu_model: &str) -> CpuProfile {
+        self.profiles.iter()
+            .find(|p| p.name.eq_ignore_ascii_case(cpu_model.trim()))
+            .or_else(|| self.profiles.iter().find(|p| p.matches_model(cpu_model)))
+            .cloned()
+            .unwrap_or_else(CpuProfile::fallback)
+    }
+
+    pub fn sample(&mut self, server: &Server) -> Metrics {
+        if lifecycle::is_inactive(&server.status) {
+            return Metrics::default();
+        }
+        let mut stream = match self.streams.remove(&server.id) {
+            Some(stream) => stream,
+            None => self.stream(server.id),
+        };
+        let (now, scenario_ms) = if self.stepped {
+            (SEED_EPOCH_MS as f64 + stream.millis, stream.millis - stream.scenario_from)
+        } else {
+            let now = self.clock.now_millis();
+            (now, now - self.scenario.as_ref().map_or(now, |(_, start)| *start))
+        };
+        let m = self.generate(server, &mut stream.rng, now, scenario_ms);
+        stream.millis += server.interval_ms.max(1) as f64;
+        self.streams.insert(server.id, stream);
+        m
+    }
+
+    fn generate(&self, server: &Server, rng: &mut StdRng, now: f64, scenario_ms: f64) -> Metrics {
+        let offset = (server.id as f64) * 1337.42;
+
+        let base_ms = match server.port {
+            80 | 443 => 15.0,
+            27017    => 5.0,
+            _        => 40.0,
+        };
+
+        //kazde meranie ma maly sum, obcas spicku alebo stratu
+        let mut pings: Vec<i32> = (0..self.latency_samples)
+            .map(|_| {
+                if rng.random_bool(0.01) {
+                    return -1;
+                }
+                let spike = if rng.random_bool(0.05) { rng.random_range(0.0..base_ms * 2.0) } else { 0.0 };
+                ((base_ms + rng.random_range(-0.5..0.5) + spike) as i32).max(1)
+            })
+            .collect();
+
+        let profile = self.profile(&server.cpu_model);
+        let (eff_mult, base_load) = (profile.efficiency as f64, profile.base_load as f64);
+
+        let cpu_wave = ((now + offset) / 5000.0).sin();
+        let cpu_jitter = ((now + offset) / 1000.0).cos() * 0.5;
+        let cpu = (base_load + (cpu_wave * 15.0 * eff_mult) + cpu_jitter).clamp(1.0, 99.0) as f32;
+
+        let ram_offset = (server.id as f64) * 9876.54;
+        let ram_wave = ((now + ram_offset) / 12000.0).sin();
+        let server_base_ram = 0.2 + ((server.id % 5) as f32 * 0.1);
+        let ram_percent = (server_base_ram + (ram_wave as f32 * 0.15)).clamp(0.1, 0.9);
+        let ram = (server.max_ram * ram_percent).clamp(0.1, server.max_ram);
+
+        if server.port == 0 {
+            pings.iter_mut().for_each(|ms| *ms = -1);
+        }
+
+        let mut m = Metrics {
+            cpu_usage: cpu,
+            ram_usage: ram,
+            ..Metrics::default()
+        };
+
+        if let Some((scenario, _)) = &self.scenario {
+            let effect = scenario.effect(&server.name, scenario_ms / 1000.0);
+            if effect.down {
+                return Metrics { response_ms: -1, ..Metrics::default() };
+            }
+            if let Some(spike) = effect.cpu {
+                m.cpu_usage = (spike + cpu_jitter as f32).clamp(1.0, 99.0);
+            }
+            if let Some((target, progress)) = effect.ram_leak {
+                let goal = server.max_ram * target;
+                m.ram_usage = (ram + (goal - ram).max(0.0) * progress).clamp(0.1, server.max_ram);
+            }
+            pings.iter_mut().filter(|ms| **ms > 0).for_each(|ms| *ms += effect.extra_ms);
+        }
+        LatencyStats::from_samples(&pings).apply(&mut m);
+
+        //odvodene metriky sleduju zataz CPU
+        let busy = m.cpu_usage / 100.0;
+        let disk_wave = ((now + offset) / 60000.0).sin() as f32;
+        m.disk_usage = (30.0 + (server.id % 7) as f32 * 6.0 + disk_wave * 2.0).clamp(1.0, 99.0);
+        m.disk_io = (busy * 4000.0 + rng.random_range(0.0..200.0)).max(0.0);
+
+        let traffic = match server.port {
+            80 | 443 | 3000 | 8080 => 2_000_000.0,
+            27017 => 800_000.0,
+            _ => 200_000.0,
+        };
+        m.net_rx = traffic * busy * rng.random_range(0.9..1.1);
+        m.net_tx = traffic * busy * 1.6 * rng.random_range(0.9..1.1);
+        m.load_avg = busy * profile.cores as f32 * 1.2;
+        m.connections = (traffic / 20_000.0 * busy) as i32 + rng.random_range(0..5);
+
+        m
+    }
+}
+
+pub async fn simulate_server_metrics(server: &Server) -> Metrics {
+    Simulator::default().sample(server)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(id: i32, port: i32) -> Server {
+        Server {
+            id,
+            name: format!("srv{}", id),
+            status: "ON".into(),
+            port,
+            cpu_model: "Intel Xeon".into(),
+            max_ram: 16.0,
+            source: "sim".into(),
+            interval_ms: 1000,
+            timeout_ms: 2000,
+            host: "127.0.0.1".into(),
+            probe: String::new(),
+        }
+    }
+
+    fn stream(sim: &mut Simulator, n: usize) -> Vec<(f32, f32, i32, f32, f32)> {
+        let servers = [server(1, 80), server(2, 5432)];
+        (0..n)
+            .flat_map(|_| servers.iter().map(|s| sim.sample(s)).collect::<Vec<_>>())
+            .map(|m| (m.cpu_usage, m.ram_usage, m.response_ms, m.net_rx, m.disk_io))
+            .collect()
+    }
+
+    #[test]
+    fn same_seed_same_stream() {
+        let a = stream(&mut Simulator::seeded(42), 50);
+        let b = stream(&mut Simulator::seeded(42), 50);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn order_does_not_change_streams() {
+        let (a, b) = (server(1, 80), server(2, 5432));
+        let mut forward = Simulator::seeded(42);
+        let mut backward = Simulator::seeded(42);
+        let mut only_a = Simulator::seeded(42);
+        for _ in 0..20 {
+            let fa = forward.sample(&a);
+            let fb = forward.sample(&b);
+            let bb = backward.sample(&b);
+            let ba = backward.sample(&a);
+            assert_eq!(format!("{:?}", fa), format!("{:?}", ba));
+            assert_eq!(format!("{:?}", fb), format!("{:?}", bb));
+            //dalsi server neposunie cas ani nahodu prveho
+            assert_eq!(format!("{:?}", fa), format!("{:?}", only_a.sample(&a)));
+        }
+    }
+
+    #[test]
+    fn seeded_time_follows_server_interval() {
+        use crate::scenario::{Incident, IncidentKind};
+        //vypadok od 10. sekundy: pri intervale 1s je to 11. vzorka, pri 5s tretia
+        let incident = |server: &str| Incident { server: server.into(), kind: IncidentKind::Outage, start_secs: 10.0, duration_secs: 100.0 };
+        let scenario = Scenario { loop_secs: None, incidents: vec![incident("srv1"), incident("srv2")] };
+        let mut sim = Simulator::seeded(3);
+        sim.set_scenario(Some(scenario));
+        let fast = server(1, 80);
+        let slow = Server { interval_ms: 5000, ..server(2, 80) };
+        let down = |sim: &mut Simulator, s: &Server, n: usize| (0..n).map(|_| sim.sample(s).response_ms < 0).collect::<Vec<_>>();
+        assert_eq!(down(&mut sim, &slow, 3), [false, false, true]);
+        let fast_down = down(&mut sim, &fast, 12);
+        assert_eq!(fast_down.iter().position(|d| *d), Some(10));
+    }
+
+    #[test]
+    fn different_seed_different_stream() {
+        let a = stream(&mut Simulator::seeded(1), 50);
+        let b = stream(&mut Simulator::seeded(2), 50);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn manual_clock_drives_waves() {
+        let clock = Arc::new(ManualClock::new(SEED_EPOCH_MS));
+        let mut a = Simulator::new(7, clock.clone());
+        let mut b = Simulator::new(7, clock.clone());
+        let s = server(3, 80);
+        assert_eq!(a.sample(&s).cpu_usage, b.sample(&s).cpu_usage);
+        let before = a.sample(&s).cpu_usage;
+        clock.advance(2500);
+        assert_ne!(before, a.sample(&s).cpu_usage);
+    }
+
+    #[test]
+    fn profile_matches_free_form_models() {
+        let sim = Simulator::seeded(1);
+        assert_eq!(sim.profile("intel-i9").name, "intel-i9");
+        assert_eq!(sim.profile("RYZEN-7").name, "ryzen-7");
+        assert_eq!(sim.profile("Intel Core i7-12700K").name, "intel-i7");
+        assert_eq!(sim.profile("Core i5").name, "intel-i5");
+        assert_eq!(sim.profile("AMD Ryzen 9 5950X").name, "ryzen-9");
+        assert_eq!(sim.profile("Xeon 9").name, "generic");
+        assert_eq!(sim.profile("").name, "generic");
+    }
+
+    #[test]
+    fn stopped_server_is_empty() {
+        let mut sim = Simulator::seeded(1);
+        for status in ["OFF", "/", "Starting", "Stopping", "Restarting", "STOPPING", "FAILED"] {
+            let s = Server { status: status.into(), ..server(1, 80) };
+            assert_eq!(format!("{:?}", sim.sample(&s)), format!("{:?}", Metrics::default()), "{}", status);
+        }
+        assert!(sim.sample(&server(1, 80)).cpu_usage > 0.0);
+    }
+}
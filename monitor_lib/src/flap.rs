@@ -0,0 +1,202 @@
+//detekcia flappingu: percento zmien stavu v poslednych N meraniach s hysterezou
+
+use crate::db::{get_flap_states, record_event, save_flap_state, setting_or, SqliteConnection};
+use crate::models::{FlapState, Metrics, Server};
+use crate::probe::ProbeStatus;
+use chrono::Utc;
+use diesel::QueryResult;
+use std::collections::{HashMap, VecDeque};
+
+//kratsia historia nestaci na rozhodnutie
+const MIN_SAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FlapConfig {
+    pub window: usize,
+    //nad high zacne flapping, pod low skonci
+    pub high_pct: f64,
+    pub low_pct: f64,
+}
+
+impl Default for FlapConfig {
+    fn default() -> Self {
+        FlapConfig { window: 21, high_pct: 50.0, low_pct: 25.0 }
+    }
+}
+
+impl FlapConfig {
+    pub fn load(conn: &mut SqliteConnection) -> Self {
+        let d = FlapConfig::default();
+        let high_pct = setting_or(conn, "flap_high", d.high_pct).clamp(1.0, 100.0);
+        FlapConfig {
+            window: setting_or(conn, "flap_window", d.window).clamp(MIN_SAMPLES, 200),
+            high_pct,
+            low_pct: setting_or(conn, "flap_low", d.low_pct).clamp(0.0, high_pct),
+        }
+    }
+}
+
+//server je hore, ked odpoveda a sluzba nie je nedostupna
+pub fn is_up(m: &Metrics, health: Option<&ProbeStatus>) -> bool {
+    m.response_ms >= 0 && !matches!(health, Some(ProbeStatus::Down(_)) | Some(ProbeStatus::PortOpen(_)))
+}
+
+//zmeny v novsej casti okna vazia viac (0.8 az 1.2)
+pub fn change_pct(states: &VecDeque<bool>) -> f64 {
+    let n = states.len();
+    if n < 2 {
+        return 0.0;
+    }
+    let mut weighted = 0.0;
+    for i in 1..n {
+        if states[i] != states[i - 1] {
+            weighted += if n > 2 { 0.8 + 0.4 * (i - 1) as f64 / (n - 2) as f64 } else { 1.0 };
+        }
+    }
+    weighted / (n - 1) as f64 * 100.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlapChange {
+    Started(f64),
+    Stopped(f64),
+}
+
+impl FlapChange {
+    pub fn describe(&self, server: &str) -> String {
+        match self {
+            FlapChange::Started(pct) => format!("FLAPPING: {}: stav sa mení príliš často ({:.0}% zmien)", server, pct),
+            FlapChange::Stopped(pct) => format!("FLAPPING skončil: {}: stav je stabilný ({:.0}% zmien)", server, pct),
+        }
+    }
+}
+
+pub struct FlapDetector {
+    config: FlapConfig,
+    states: HashMap<i32, VecDeque<bool>>,
+    flapping: HashMap<i32, bool>,
+    loaded: bool,
+}
+
+impl FlapDetector {
+    pub fn new(config: FlapConfig) -> Self {
+        FlapDetector { config, states: HashMap::new(), flapping: HashMap::new(), loaded: false }
+    }
+
+    pub fn is_flapping(&self, server_id: i32) -> bool {
+        self.flapping.get(&server_id).copied().unwrap_or(false)
+    }
+
+    pub fn observe(&mut self, server_id: i32, up: bool) -> Option<FlapChange> {
+        let states = self.states.entry(server_id).or_default();
+        states.push_back(up);
+        while states.len() > self.config.window {
+            states.pop_front();
+        }
+        if states.len() < MIN_SAMPLES {
+            return None;
+        }
+        let pct = change_pct(states);
+        let flapping = self.flapping.entry(server_id).or_default();
+        if !*flapping && pct >= self.config.high_pct {
+            *flapping = true;
+            Some(FlapChange::Started(pct))
+        } else if *flapping && pct < self.config.low_pct {
+            *flapping = false;
+            Some(FlapChange::Stopped(pct))
+        } else {
+            None
+        }
+    }
+
+    //zmena sa zapise do flap_state a medzi udalosti
+    pub fn check(&mut self, conn: &mut SqliteConnection, server: &Server, m: &Metrics, health: Option<&ProbeStatus>) -> QueryResult<Option<FlapChange>> {
+        //flapping z minuleho behu, aby sa po restarte ohlasil aj koniec
+        if !self.loaded {
+            for st in get_flap_states(conn)? {
+                self.flapping.insert(st.server_id, st.flapping);
+            }
+            self.loaded = true;
+        }
+        let Some(change) = self.observe(server.id, is_up(m, health)) else {
+            return Ok(None);
+        };
+        let (flapping, pct) = match change {
+            FlapChange::Started(pct) => (true, pct),
+            FlapChange::Stopped(pct) => (false, pct),
+        };
+        save_flap_state(conn, &FlapState { server_id: server.id, flapping, change_pct: pct as f32, since: Utc::now().naive_utc() })?;
+        record_event(conn, server.id, if flapping { "flap.start" } else { "flap.stop" }, &change.describe(&server.name))?;
+        Ok(Some(change))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector() -> FlapDetector {
+        FlapDetector::new(FlapConfig { window: 10, high_pct: 50.0, low_pct: 25.0 })
+    }
+
+    #[test]
+    fn no_decision_before_min_samples() {
+        let mut d = detector();
+        //4 zmeny zo 4 moznych, ale iba 5. vzorka rozhoduje
+        for up in [true, false, true, false] {
+            assert_eq!(d.observe(1, up), None);
+        }
+        assert!(matches!(d.observe(1, true), Some(FlapChange::Started(pct)) if pct == 100.0));
+        assert!(d.is_flapping(1));
+        assert!(!d.is_flapping(2));
+    }
+
+    #[test]
+    fn stable_server_never_flaps() {
+        let mut d = detector();
+        for _ in 0..50 {
+            assert_eq!(d.observe(1, true), None);
+        }
+        assert_eq!(change_pct(&VecDeque::from(vec![true; 10])), 0.0);
+    }
+
+    #[test]
+    fn hysteresis_between_thresholds() {
+        let mut d = detector();
+        let mut changes = Vec::new();
+        for up in [true, false, true, false, true] {
+            changes.extend(d.observe(1, up));
+        }
+        assert_eq!(changes.len(), 1);
+        //stabilne vzorky postupne vytlacia zmeny z okna; medzi 25% a 50% sa stav nemeni
+        let mut stopped_after = None;
+        for i in 1..=10 {
+            if let Some(change) = d.observe(1, true) {
+                assert!(matches!(change, FlapChange::Stopped(pct) if pct < 25.0));
+                stopped_after = Some(i);
+                break;
+            }
+            assert!(d.is_flapping(1));
+        }
+        assert!(stopped_after.is_some_and(|i| i > 1));
+        assert!(!d.is_flapping(1));
+    }
+
+    #[test]
+    fn recent_changes_weigh_more() {
+        let old: VecDeque<bool> = [false, true, true, true, true, true].into_iter().collect();
+        let new: VecDeque<bool> = [true, true, true, true, true, false].into_iter().collect();
+        assert!(change_pct(&new) > change_pct(&old));
+        assert!((change_pct(&old) - 16.0).abs() < 1e-9);
+        assert!((change_pct(&new) - 24.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn down_health_is_not_up() {
+        let ok = Metrics { response_ms: 5, ..Metrics::default() };
+        assert!(is_up(&ok, None));
+        assert!(is_up(&ok, Some(&ProbeStatus::Healthy)));
+        assert!(!is_up(&ok, Some(&ProbeStatus::PortOpen("x".into()))));
+        assert!(!is_up(&Metrics { response_ms: -1, ..Metrics::default() }, None));
+    }
+}
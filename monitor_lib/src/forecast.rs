@@ -0,0 +1,200 @@
+//predpoved vycerpania RAM z trendu historie (Theil-Sen regresia)
+
+use crate::db::{get_history, SqliteConnection};
+use crate::models::{HistoryEntry, Server};
+use chrono::{Duration, Utc};
+use diesel::QueryResult;
+
+//menej bodov nestaci na rozumny trend
+const MIN_POINTS: usize = 10;
+//parove sklony su O(n^2), dlhsia historia sa preriedi
+const MAX_POINTS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RamForecast {
+    //vyrovnana hodnota v case poslednej vzorky
+    pub current_gb: f64,
+    pub slope_gb_per_hour: f64,
+    pub until_90: Option<Duration>,
+    pub until_full: Option<Duration>,
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_unstable_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] }
+}
+
+//cas do dosiahnutia hranice, None = trend k nej nesmeruje
+fn time_until(current: f64, slope: f64, limit: f64) -> Option<Duration> {
+    if current >= limit {
+        return Some(Duration::zero());
+    }
+    if slope <= 1e-6 {
+        return None;
+    }
+    let hours = (limit - current) / slope;
+    Duration::try_seconds((hours * 3600.0).min(i64::MAX as f64) as i64)
+}
+
+//vypadky (nulova RAM) sa do trendu nepocitaju
+pub fn forecast_ram(history: &[HistoryEntry], max_ram: f32) -> Option<RamForecast> {
+    let points: Vec<&HistoryEntry> = history.iter().filter(|h| h.ram_usage > 0.0).collect();
+    if points.len() < MIN_POINTS || max_ram <= 0.0 {
+        return None;
+    }
+    let step = points.len().div_ceil(MAX_POINTS);
+    let last = points[points.len() - 1].timestamp;
+    let xy: Vec<(f64, f64)> = points.iter().rev().step_by(step)
+        .map(|h| ((h.timestamp - last).num_milliseconds() as f64 / 3_600_000.0, h.ram_usage as f64))
+        .collect();
+
+    let mut slopes = Vec::with_capacity(xy.len() * xy.len() / 2);
+    for (i, (x1, y1)) in xy.iter().enumerate() {
+        for (x2, y2) in &xy[i + 1..] {
+            if (x1 - x2).abs() > 1e-9 {
+                slopes.push((y1 - y2) / (x1 - x2));
+            }
+        }
+    }
+    if slopes.is_empty() {
+        return None;
+    }
+    let slope = median(&mut slopes);
+    let mut intercepts: Vec<f64> = xy.iter().map(|(x, y)| y - slope * x).collect();
+    let current = median(&mut intercepts);
+
+    let max_ram = max_ram as f64;
+    Some(RamForecast {
+        current_gb: current,
+        slope_gb_per_hour: slope,
+        until_90: time_until(current, slope, max_ram * 0.9),
+        until_full: time_until(current, slope, max_ram),
+    })
+}
+
+pub fn server_forecast(conn: &mut SqliteConnection, server: &Server, window_hours: i64) -> QueryResult<Option<RamForecast>> {
+    let history = get_history(conn, server.id, (Utc::now() - Duration::hours(window_hours)).naive_utc())?;
+    Ok(forecast_ram(&history, server.max_ram))
+}
+
+//kratky zapis, napr. "3h 20m" alebo "2d 4h"
+pub fn fmt_eta(d: Duration) -> String {
+    let mins = d.num_minutes();
+    if mins < 1 {
+        "<1m".into()
+    } else if mins < 60 {
+        format!("{}m", mins)
+    } else if mins < 24 * 60 {
+        format!("{}h {}m", mins / 60, mins % 60)
+    } else {
+        format!("{}d {}h", mins / (24 * 60), mins / 60 % 24)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDateTime;
+
+    //n vzoriek po minute, posledna ma hodnotu last a rastie o slope GB/h
+    fn ramp(n: usize, last: f64, slope: f64) -> Vec<HistoryEntry> {
+        let end = NaiveDateTime::parse_from_str("2024-05-01 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        (0..n).map(|i| {
+            let mins_before = (n - 1 - i) as i64;
+            let ram = last - slope * mins_before as f64 / 60.0;
+            entry(end - Duration::minutes(mins_before), ram as f32)
+        }).collect()
+    }
+
+    fn entry(timestamp: NaiveDateTime, ram_usage: f32) -> HistoryEntry {
+        HistoryEntry { id: 0, server_id: 1, timestamp, response_ms: 5, ram_usage, cpu_usage: 0.0, disk_usage: 0.0, disk_io: 0.0, net_rx: 0.0, net_tx: 0.0, load_avg: 0.0, connections: 0, rtt_min: 0, rtt_max: 0, rtt_p95: 0, loss_pct: 0.0 }
+    }
+
+    fn close(d: Option<Duration>, expected: Duration) -> bool {
+        d.is_some_and(|d| (d - expected).num_seconds().abs() <= 60)
+    }
+
+    #[test]
+    fn linear_ramp() {
+        let f = forecast_ram(&ramp(60, 8.0, 0.5), 16.0).unwrap();
+        assert!((f.slope_gb_per_hour - 0.5).abs() < 1e-3, "{}", f.slope_gb_per_hour);
+        assert!((f.current_gb - 8.0).abs() < 1e-3);
+        //do 14.4 GB chyba 6.4 GB, do 16 GB 8 GB
+        assert!(close(f.until_90, Duration::minutes(12 * 60 + 48)), "{:?}", f.until_90);
+        assert!(close(f.until_full, Duration::hours(16)), "{:?}", f.until_full);
+    }
+
+    #[test]
+    fn already_full() {
+        let f = forecast_ram(&ramp(30, 15.0, 1.0), 16.0).unwrap();
+        assert_eq!(f.until_90, Some(Duration::zero()));
+        assert!(f.until_full.is_some_and(|d| d > Duration::zero()));
+    }
+
+    #[test]
+    fn flat_or_falling_never_fills() {
+        for slope in [0.0, -0.8] {
+            let f = forecast_ram(&ramp(60, 6.0, slope), 16.0).unwrap();
+            assert_eq!((f.until_90, f.until_full), (None, None), "{}", slope);
+        }
+    }
+
+    #[test]
+    fn outages_are_ignored() {
+        let mut history = ramp(60, 8.0, 0.5);
+        for h in history.iter_mut().step_by(3) {
+            h.ram_usage = 0.0;
+        }
+        let f = forecast_ram(&history, 16.0).unwrap();
+        assert!((f.slope_gb_per_hour - 0.5).abs() < 1e-3);
+        assert!((f.current_gb - 8.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn long_history_is_thinned() {
+        //5000 bodov by dalo 12.5 mil. parovych sklonov, po preriedeni ich je najviac ~20 tis.
+        let history = ramp(5000, 10.0, 0.1);
+        let start = std::time::Instant::now();
+        let f = forecast_ram(&history, 16.0).unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_secs(2));
+        assert!((f.slope_gb_per_hour - 0.1).abs() < 1e-3);
+        //posledna vzorka sa nikdy nevynecha
+        assert!((f.current_gb - 10.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn too_few_points() {
+        assert_eq!(forecast_ram(&ramp(MIN_POINTS - 1, 8.0, 0.5), 16.0), None);
+        assert!(forecast_ram(&ramp(MIN_POINTS, 8.0, 0.5), 16.0).is_some());
+        //dost zaznamov, ale vacsina su vypadky
+        let mut history = ramp(20, 8.0, 0.5);
+        history.iter_mut().skip(5).for_each(|h| h.ram_usage = 0.0);
+        assert_eq!(forecast_ram(&history, 16.0), None);
+        assert_eq!(forecast_ram(&ramp(20, 8.0, 0.5), 0.0), None);
+        //rovnaky cas vsetkych vzoriek nema sklon
+        let t = NaiveDateTime::parse_from_str("2024-05-01 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        assert_eq!(forecast_ram(&vec![entry(t, 4.0); 20], 16.0), None);
+    }
+
+    #[test]
+    fn eta_format() {
+        assert_eq!(fmt_eta(Duration::zero()), "<1m");
+        assert_eq!(fmt_eta(Duration::seconds(59)), "<1m");
+        assert_eq!(fmt_eta(Duration::minutes(1)), "1m");
+        assert_eq!(fmt_eta(Duration::minutes(59)), "59m");
+        assert_eq!(fmt_eta(Duration::minutes(60)), "1h 0m");
+        assert_eq!(fmt_eta(Duration::minutes(24 * 60 - 1)), "23h 59m");
+        assert_eq!(fmt_eta(Duration::days(1)), "1d 0h");
+        assert_eq!(fmt_eta(Duration::hours(52)), "2d 4h");
+    }
+
+    #[test]
+    fn time_until_limits() {
+        assert_eq!(time_until(5.0, 1.0, 5.0), Some(Duration::zero()));
+        assert_eq!(time_until(4.0, 0.0, 5.0), None);
+        assert_eq!(time_until(4.0, 2.0, 5.0), Some(Duration::minutes(30)));
+        //obrovsky cas sa nezruti
+        assert!(time_until(0.0, 1e-5, 1e300).is_none_or(|d| d > Duration::days(365)));
+    }
+}
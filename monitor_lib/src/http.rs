@@ -0,0 +1,331 @@
+//minimalny HTTP/1.1 klient (http:// aj https://), staci na scrape a webhooky
+
+use std::io;
+use std::sync::{Arc, OnceLock};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::crypto::ring;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    pub https: bool,
+    //host:port
+    pub authority: String,
+    pub path: String,
+}
+
+impl Target {
+    //meno pre SNI a overenie certifikatu, bez portu a hranatych zatvoriek IPv6
+    pub fn host(&self) -> &str {
+        let host = self.authority.rsplit_once(':').map_or(self.authority.as_str(), |(h, _)| h);
+        host.trim_start_matches('[').trim_end_matches(']')
+    }
+}
+
+pub fn split_url(url: &str) -> io::Result<Target> {
+    let (https, rest) = match (url.strip_prefix("http://"), url.strip_prefix("https://")) {
+        (Some(rest), _) => (false, rest),
+        (_, Some(rest)) => (true, rest),
+        _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("podporované je iba http:// a https://: {}", url))),
+    };
+    let (authority, path) = match rest.find('/') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    if authority.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("chýba adresa servera: {}", url)));
+    }
+    //port za poslednou ':' mimo IPv6 adresy v zatvorkach
+    let has_port = authority.rsplit_once(':').is_some_and(|(_, p)| !p.contains(']'));
+    let authority = if has_port { authority.to_string() } else { format!("{}:{}", authority, if https { 443 } else { 80 }) };
+    Ok(Target { https, authority, path: path.to_string() })
+}
+
+//certifikaty sa overuju voci zabudovanym korenovym CA (webpki-roots)
+fn default_config() -> Arc<ClientConfig> {
+    static CONFIG: OnceLock<Arc<ClientConfig>> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let roots = RootCertStore { roots: webpki_roots::TLS_SERVER_ROOTS.to_vec() };
+        client_config(roots)
+    }).clone()
+}
+
+fn client_config(roots: RootCertStore) -> Arc<ClientConfig> {
+    let config = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("TLS verzie")
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Arc::new(config)
+}
+
+pub async fn request(method: &str, url: &str, headers: &[(&str, &str)], body: Option<&[u8]>) -> io::Result<HttpResponse> {
+    request_with(default_config(), method, url, headers, body).await
+}
+
+async fn request_with(config: Arc<ClientConfig>, method: &str, url: &str, headers: &[(&str, &str)], body: Option<&[u8]>) -> io::Result<HttpResponse> {
+    let target = split_url(url)?;
+    let stream = TcpStream::connect(&target.authority).await?;
+
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nUser-Agent: monitor\r\n", method, target.path, target.authority);
+    for (k, v) in headers {
+        head.push_str(&format!("{}: {}\r\n", k, v));
+    }
+    if let Some(b) = body {
+        head.push_str(&format!("Content-Length: {}\r\n", b.len()));
+    }
+    head.push_str("\r\n");
+
+    let raw = if target.https {
+        let name = ServerName::try_from(target.host().to_string())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut tls = TlsConnector::from(config).connect(name, stream).await?;
+        exchange(&mut tls, head.as_bytes(), body).await?
+    } else {
+        let mut stream = stream;
+        exchange(&mut stream, head.as_bytes(), body).await?
+    };
+    parse_response(&raw)
+}
+
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, head: &[u8], body: Option<&[u8]>) -> io::Result<Vec<u8>> {
+    stream.write_all(head).await?;
+    if let Some(b) = body {
+        stream.write_all(b).await?;
+    }
+    stream.flush().await?;
+
+    let mut raw = Vec::new();
+    match stream.read_to_end(&mut raw).await {
+        Ok(_) => Ok(raw),
+        //vela serverov pri Connection: close zavrie TLS bez close_notify
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && !raw.is_empty() => Ok(raw),
+        Err(e) => Err(e),
+    }
+}
+
+pub async fn get(url: &str) -> io::Result<HttpResponse> {
+    request("GET", url, &[], None).await
+}
+
+pub async fn post_json(url: &str, body: &[u8]) -> io::Result<HttpResponse> {
+    request("POST", url, &[("Content-Type", "application/json")], Some(body)).await
+}
+
+fn parse_response(raw: &[u8]) -> io::Result<HttpResponse> {
+    let bad = |m: &str| io::Error::new(io::ErrorKind::InvalidData, m.to_string());
+    let split = raw.windows(4).position(|w| w == b"\r\n\r\n").ok_or_else(|| bad("neúplná HTTP hlavička"))?;
+    let head = String::from_utf8_lossy(&raw[..split]);
+    let mut lines = head.lines();
+
+    let status = lines.next()
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|c| c.parse().ok())
+        .ok_or_else(|| bad("chybný HTTP status"))?;
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|l| l.split_once(':'))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect();
+
+    let mut resp = HttpResponse { status, headers, body: String::new() };
+    let body = &raw[split + 4..];
+    resp.body = if resp.header("Transfer-Encoding").is_some_and(|v| v.eq_ignore_ascii_case("chunked")) {
+        String::from_utf8_lossy(&dechunk(body)).into_owned()
+    } else {
+        String::from_utf8_lossy(body).into_owned()
+    };
+    Ok(resp)
+}
+
+fn dechunk(mut body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    while let Some(eol) = body.windows(2).position(|w| w == b"\r\n") {
+        let size_line = String::from_utf8_lossy(&body[..eol]);
+        let size = usize::from_str_radix(size_line.split(';').next().unwrap_or("").trim(), 16).unwrap_or(0);
+        body = &body[eol + 2..];
+        if size == 0 || body.len() < size {
+            break;
+        }
+        out.extend_from_slice(&body[..size]);
+        body = body.get(size + 2..).unwrap_or(&[]);
+    }
+    out
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    //lokalny server: kazde spojenie dostane dalsiu odpoved, vrati prijate poziadavky
+    pub(crate) async fn serve(responses: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for resp in responses {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                requests.push(read_request(&mut sock).await);
+                sock.write_all(resp.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    async fn read_request(sock: &mut TcpStream) -> String {
+        let mut raw = Vec::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            let n = sock.read(&mut buf).await.unwrap();
+            raw.extend_from_slice(&buf[..n]);
+            if let Some(split) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&raw[..split]).to_lowercase();
+                let len = head.lines()
+                    .find_map(|l| l.strip_prefix("content-length:"))
+                    .and_then(|v| v.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+                if n == 0 || raw.len() >= split + 4 + len {
+                    break;
+                }
+            } else if n == 0 {
+                break;
+            }
+        }
+        String::from_utf8_lossy(&raw).into_owned()
+    }
+
+    #[test]
+    fn split_url_defaults_port() {
+        let t = split_url("http://example.com").unwrap();
+        assert_eq!((t.https, t.authority.as_str(), t.path.as_str()), (false, "example.com:80", "/"));
+        let t = split_url("http://10.0.0.1:9100/metrics?x=1").unwrap();
+        assert_eq!((t.authority.as_str(), t.path.as_str(), t.host()), ("10.0.0.1:9100", "/metrics?x=1", "10.0.0.1"));
+        let t = split_url("https://hooks.example.com/x").unwrap();
+        assert_eq!((t.https, t.authority.as_str(), t.host()), (true, "hooks.example.com:443", "hooks.example.com"));
+        let t = split_url("https://[::1]/x").unwrap();
+        assert_eq!((t.authority.as_str(), t.host()), ("[::1]:443", "::1"));
+        assert_eq!(split_url("http://[::1]:8080").unwrap().authority, "[::1]:8080");
+        assert!(split_url("ftp://example.com").is_err());
+        assert!(split_url("https:///x").is_err());
+    }
+
+    #[tokio::test]
+    async fn get_plain_body() {
+        let (url, server) = serve(vec!["HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nhello\nworld".into()]).await;
+        let resp = get(&format!("{}/metrics", url)).await.unwrap();
+        assert_eq!(resp.status, 200);
+        assert_eq!(resp.header("content-type"), Some("text/plain"));
+        assert_eq!(resp.body, "hello\nworld");
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("GET /metrics HTTP/1.1\r\n"));
+    }
+
+    #[tokio::test]
+    async fn get_chunked_body() {
+        let (url, _server) = serve(vec!["HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6;ext=1\r\n world\r\n0\r\n\r\n".into()]).await;
+        let resp = get(&url).await.unwrap();
+        assert_eq!(resp.body, "hello world");
+    }
+
+    #[tokio::test]
+    async fn malformed_status_is_error() {
+        let (url, _server) = serve(vec!["HTTP/1.1 OK\r\n\r\nbody".into()]).await;
+        let err = get(&url).await.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[tokio::test]
+    async fn post_json_sends_body() {
+        let (url, server) = serve(vec!["HTTP/1.1 204 No Content\r\n\r\n".into()]).await;
+        let resp = post_json(&format!("{}/hook", url), br#"{"a":1}"#).await.unwrap();
+        assert_eq!(resp.status, 204);
+        let request = &server.await.unwrap()[0];
+        assert!(request.starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(request.contains("Content-Type: application/json\r\n"));
+        assert!(request.contains("Content-Length: 7\r\n"));
+        assert!(request.ends_with("\r\n\r\n{\"a\":1}"));
+    }
+
+    //https server s certifikatom pre localhost od vlastnej CA, vrati CA pre klienta
+    async fn serve_tls(response: &'static str) -> (String, RootCertStore) {
+        use rcgen::{BasicConstraints, CertificateParams, IsCa, Issuer, KeyPair};
+        use tokio_rustls::rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+        use tokio_rustls::rustls::ServerConfig;
+        use tokio_rustls::TlsAcceptor;
+
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let leaf_key = KeyPair::generate().unwrap();
+        let leaf = CertificateParams::new(vec!["localhost".to_string()]).unwrap().signed_by(&leaf_key, &Issuer::new(ca_params, &ca_key)).unwrap();
+
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(leaf_key.serialize_der()));
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![leaf.der().clone()], key)
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (sock, _) = listener.accept().await.unwrap();
+            let Ok(mut tls) = acceptor.accept(sock).await else { return };
+            let mut buf = [0u8; 1024];
+            let _ = tls.read(&mut buf).await;
+            let _ = tls.write_all(response.as_bytes()).await;
+            let _ = tls.shutdown().await;
+        });
+
+        let mut roots = RootCertStore::empty();
+        roots.add(ca.der().clone()).unwrap();
+        (format!("https://localhost:{}", port), roots)
+    }
+
+    #[tokio::test]
+    async fn https_with_trusted_ca() {
+        let (url, roots) = serve_tls("HTTP/1.1 202 Accepted\r\nContent-Length: 2\r\n\r\nok").await;
+        let resp = request_with(client_config(roots), "POST", &format!("{}/hook", url), &[], Some(b"{}")).await.unwrap();
+        assert_eq!(resp.status, 202);
+        assert_eq!(resp.body, "ok");
+    }
+
+    #[tokio::test]
+    async fn https_rejects_unknown_ca() {
+        let (url, _roots) = serve_tls("HTTP/1.1 200 OK\r\n\r\n").await;
+        let err = post_json(&url, b"{}").await.unwrap_err();
+        assert!(err.to_string().contains("UnknownIssuer"), "{}", err);
+    }
+
+    #[test]
+    fn parse_response_incomplete_head() {
+        assert!(parse_response(b"HTTP/1.1 200 OK\r\nX: y").is_err());
+        assert!(parse_response(b"").is_err());
+    }
+
+    #[test]
+    fn dechunk_stops_on_truncated_chunk() {
+        assert_eq!(dechunk(b"3\r\nabc\r\n10\r\nshort"), b"abc");
+        assert_eq!(dechunk(b"zz\r\nabc\r\n"), b"");
+    }
+}
@@ -0,0 +1,157 @@
+//viac merani odozvy v jednom cykle: min/avg/max/p95 a strata paketov
+
+use crate::models::Metrics;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+//pocet merani, ak nie je nastavene latency_samples
+pub const DEFAULT_SAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyStats {
+    pub min: i32,
+    pub avg: i32,
+    pub max: i32,
+    pub p95: i32,
+    pub loss_pct: f32,
+}
+
+impl LatencyStats {
+    //zaporna hodnota = meranie sa nepodarilo
+    pub fn from_samples(samples: &[i32]) -> LatencyStats {
+        let mut ok: Vec<i32> = samples.iter().copied().filter(|ms| *ms >= 0).collect();
+        let loss_pct = match samples.len() {
+            0 => 100.0,
+            n => (n - ok.len()) as f32 * 100.0 / n as f32,
+        };
+        if ok.is_empty() {
+            return LatencyStats { min: -1, avg: -1, max: -1, p95: -1, loss_pct };
+        }
+
+        ok.sort_unstable();
+        //nearest-rank percentil
+        let rank = ((ok.len() as f64 * 0.95).ceil() as usize).clamp(1, ok.len());
+        LatencyStats {
+            min: ok[0],
+            avg: (ok.iter().map(|ms| *ms as i64).sum::<i64>() / ok.len() as i64) as i32,
+            max: ok[ok.len() - 1],
+            p95: ok[rank - 1],
+            loss_pct,
+        }
+    }
+
+    //zdroje s jednym meranim (Prometheus, starsi agenti, timeout planovaca)
+    pub fn fill_single(m: &mut Metrics) {
+        if m.response_ms != 0 && m.rtt_max == 0 && m.loss_pct == 0.0 {
+            LatencyStats::from_samples(&[m.response_ms]).apply(m);
+        }
+    }
+
+    //response_ms nesie priemer, aby stare pohlady ostali zmysluplne
+    pub fn apply(&self, m: &mut Metrics) {
+        m.response_ms = self.avg;
+        m.rtt_min = self.min;
+        m.rtt_max = self.max;
+        m.rtt_p95 = self.p95;
+        m.loss_pct = self.loss_pct;
+    }
+}
+
+//n pokusov o TCP spojenie, spolu najviac za `limit`
+pub async fn tcp_samples(host: &str, port: i32, n: usize, limit: Duration) -> Vec<i32> {
+    let n = n.max(1);
+    let each = limit / n as u32;
+    let addr = format!("{}:{}", host, port);
+    let mut samples = Vec::with_capacity(n);
+    for _ in 0..n {
+        let start = Instant::now();
+        let ms = match timeout(each, TcpStream::connect(&addr)).await {
+            Ok(Ok(_)) => (start.elapsed().as_millis() as i32).max(1),
+            _ => -1,
+        };
+        samples.push(ms);
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(min: i32, avg: i32, max: i32, p95: i32, loss_pct: f32) -> LatencyStats {
+        LatencyStats { min, avg, max, p95, loss_pct }
+    }
+
+    #[test]
+    fn from_samples_table() {
+        let twenty: Vec<i32> = (1..=20).collect();
+        let cases: [(&[i32], LatencyStats); 7] = [
+            (&[], stats(-1, -1, -1, -1, 100.0)),
+            (&[-1, -1, -1], stats(-1, -1, -1, -1, 100.0)),
+            (&[7], stats(7, 7, 7, 7, 0.0)),
+            //ceil(5 * 0.95) = 5, p95 je maximum
+            (&[30, 10, 50, 20, 40], stats(10, 30, 50, 50, 0.0)),
+            //ceil(20 * 0.95) = 19
+            (&twenty, stats(1, 10, 20, 19, 0.0)),
+            (&[10, -1, 20, -1], stats(10, 15, 20, 20, 50.0)),
+            (&[5, -1, -1, -1, -1], stats(5, 5, 5, 5, 80.0)),
+        ];
+        for (samples, expected) in cases {
+            assert_eq!(LatencyStats::from_samples(samples), expected, "{:?}", samples);
+        }
+    }
+
+    #[test]
+    fn fill_single_cases() {
+        let mut m = Metrics { response_ms: 30, ..Metrics::default() };
+        LatencyStats::fill_single(&mut m);
+        assert_eq!((m.response_ms, m.rtt_min, m.rtt_max, m.rtt_p95, m.loss_pct), (30, 30, 30, 30, 0.0));
+
+        let mut m = Metrics { response_ms: -1, ..Metrics::default() };
+        LatencyStats::fill_single(&mut m);
+        assert_eq!((m.response_ms, m.rtt_min, m.rtt_max, m.rtt_p95, m.loss_pct), (-1, -1, -1, -1, 100.0));
+
+        //bez merania sa nic nedopocitava
+        let mut m = Metrics::default();
+        LatencyStats::fill_single(&mut m);
+        assert_eq!((m.rtt_max, m.loss_pct), (0, 0.0));
+
+        //zdroj s vlastnou statistikou sa neprepisuje
+        let mut m = Metrics { response_ms: 30, rtt_min: 10, rtt_max: 80, rtt_p95: 70, ..Metrics::default() };
+        LatencyStats::fill_single(&mut m);
+        assert_eq!((m.response_ms, m.rtt_min, m.rtt_max, m.rtt_p95), (30, 10, 80, 70));
+        let mut m = Metrics { response_ms: -1, loss_pct: 40.0, ..Metrics::default() };
+        LatencyStats::fill_single(&mut m);
+        assert_eq!((m.rtt_max, m.loss_pct), (0, 40.0));
+    }
+
+    #[tokio::test]
+    async fn tcp_samples_open_port() {
+        //spojenia prijme backlog, accept netreba
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port() as i32;
+        let samples = tcp_samples("127.0.0.1", port, 3, Duration::from_secs(3)).await;
+        assert_eq!(samples.len(), 3);
+        assert!(samples.iter().all(|ms| *ms >= 1), "{:?}", samples);
+        assert_eq!(LatencyStats::from_samples(&samples).loss_pct, 0.0);
+    }
+
+    #[tokio::test]
+    async fn tcp_samples_closed_port() {
+        let port = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap().port() as i32
+        };
+        let samples = tcp_samples("127.0.0.1", port, 4, Duration::from_secs(2)).await;
+        assert_eq!(samples, vec![-1; 4]);
+        assert_eq!(LatencyStats::from_samples(&samples).loss_pct, 100.0);
+    }
+
+    #[tokio::test]
+    async fn tcp_samples_at_least_one() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port() as i32;
+        assert_eq!(tcp_samples("127.0.0.1", port, 0, Duration::from_secs(1)).await.len(), 1);
+    }
+}
@@ -1,7 +1,30 @@
+#![allow(non_snake_case)]
+
 pub mod schema;
 pub mod models;
 pub mod db;
 pub mod engine;
+pub mod scenario;
+pub mod procfs;
+pub mod scheduler;
+pub mod pipeline;
+pub mod http;
+pub mod prometheus;
+pub mod agent;
+pub mod probe;
+pub mod tls;
+pub mod latency;
+pub mod anomaly;
+pub mod forecast;
+pub mod slo;
+pub mod rules;
+pub mod alerts;
+pub mod notify;
+pub mod maintenance;
+pub mod flap;
+pub mod deps;
+pub mod template;
+pub mod lifecycle;
 
 pub use models::*;
 pub use db::*;
@@ -0,0 +1,564 @@
+//spustanie a zastavovanie serverov: shell prikazy alebo systemd, potom kontrola sondou
+
+use crate::db::{begin_transition, claim_status, end_transition, fail_transition, get_all_servers, get_lifecycle_command, get_status, get_transition, get_transitions, open_connection, record_event, update_status, SqliteConnection};
+use crate::models::{LifecycleCommand, Server, ServerTransition};
+use crate::probe::{self, ProbeKind, ProbeStatus};
+use chrono::Utc;
+use diesel::QueryResult;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::time::timeout;
+
+//server bez nastavenych prikazov sa iba simuluje
+const SIMULATED: Duration = Duration::from_secs(3);
+//rezerva nad limit prikazov, az potom sa prechod povazuje za prerusene
+const GRACE: Duration = Duration::from_secs(10);
+
+pub const FAILED: &str = "FAILED";
+
+//docasne stavy, aj starsie velkymi pismenami
+pub fn is_pending(status: &str) -> bool {
+    matches!(status, "Starting" | "Stopping" | "Restarting" | "STARTING" | "STOPPING")
+}
+
+//vypnuty, neznamy, prave prechadzajuci alebo zlyhany server sa nemeria
+pub fn is_inactive(status: &str) -> bool {
+    status == "OFF" || status == "/" || is_pending(status) || status == FAILED
+}
+//vystup prikazu v udalosti
+const MAX_OUTPUT: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Start,
+    Stop,
+    Restart,
+}
+
+impl Action {
+    pub fn parse(s: &str) -> Option<Action> {
+        match s.trim().to_lowercase().as_str() {
+            "start" => Some(Action::Start),
+            "stop" => Some(Action::Stop),
+            "restart" => Some(Action::Restart),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Start => "start",
+            Action::Stop => "stop",
+            Action::Restart => "restart",
+        }
+    }
+
+    //stav pocas prechodu
+    pub fn pending_status(&self) -> &'static str {
+        match self {
+            Action::Start => "Starting",
+            Action::Stop => "Stopping",
+            Action::Restart => "Restarting",
+        }
+    }
+
+    pub fn final_status(&self) -> &'static str {
+        match self {
+            Action::Stop => "OFF",
+            Action::Start | Action::Restart => "ON",
+        }
+    }
+
+    fn done_label(&self) -> &'static str {
+        match self {
+            Action::Start => "Started",
+            Action::Stop => "Stopped",
+            Action::Restart => "Restarted",
+        }
+    }
+}
+
+//akcia je povolena iba z ustaleneho stavu
+pub fn check(server: &Server, action: Action) -> Result<(), String> {
+    match (server.status.as_str(), action) {
+        ("/", _) => Err(format!("Server {} nie je aktivovaný!", server.name)),
+        ("OFF", Action::Start) | ("ON", Action::Stop | Action::Restart) => Ok(()),
+        ("ON", Action::Start) => Err(format!("Server {} už beží.", server.name)),
+        ("OFF", _) => Err(format!("Server {} nebeží.", server.name)),
+        (FAILED, _) => Err(format!("Server {} je v stave FAILED, použi retry-server alebo reset-server.", server.name)),
+        (status, _) => Err(format!("Server {} je v stave {}, počkaj na dokončenie.", server.name, status)),
+    }
+}
+
+//jednoduche uvodzovky pre sh
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+//prikazy pre akciu v poradi, prazdne = nic nie je nastavene
+pub fn steps(cfg: &LifecycleCommand, action: Action) -> Vec<String> {
+    let unit = cfg.unit.trim();
+    if !unit.is_empty() {
+        return vec![format!("systemctl {} {}", action.as_str(), shell_quote(unit))];
+    }
+    let set = |c: &str| (!c.trim().is_empty()).then(|| c.to_string());
+    match action {
+        Action::Start => set(&cfg.start_cmd).into_iter().collect(),
+        Action::Stop => set(&cfg.stop_cmd).into_iter().collect(),
+        //bez restart prikazu sa server zastavi a znova spusti
+        Action::Restart => match set(&cfg.restart_cmd) {
+            Some(c) => vec![c],
+            None => [set(&cfg.stop_cmd), set(&cfg.start_cmd)].into_iter().flatten().collect(),
+        },
+    }
+}
+
+fn truncate(s: &str) -> String {
+    match s.char_indices().nth(MAX_OUTPUT) {
+        Some((i, _)) => format!("{}…", &s[..i]),
+        None => s.to_string(),
+    }
+}
+
+//stdout a stderr spolu, chyba obsahuje navratovy kod
+pub async fn run_command(cmd: &str, limit: Duration) -> Result<String, String> {
+    //vlastna skupina procesov, aby sa pri timeoute dali zabit aj potomkovia sh
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let pgid = child.id();
+    let out = match timeout(limit, child.wait_with_output()).await {
+        Ok(r) => r.map_err(|e| e.to_string())?,
+        Err(_) => {
+            if let Some(pgid) = pgid {
+                unsafe { libc::killpg(pgid as libc::pid_t, libc::SIGKILL) };
+            }
+            return Err(format!("timeout {:.0}s", limit.as_secs_f64()));
+        }
+    };
+    let mut text = String::from_utf8_lossy(&out.stdout).trim().to_string();
+    let err = String::from_utf8_lossy(&out.stderr);
+    if !err.trim().is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(err.trim());
+    }
+    let text = truncate(&text);
+    if out.status.success() {
+        Ok(text)
+    } else if text.is_empty() {
+        Err(out.status.to_string())
+    } else {
+        Err(format!("{}: {}", out.status, text))
+    }
+}
+
+//opakuje sondu, kym sluzba neodpovie alebo nevyprsi cas
+pub async fn wait_healthy(server: &Server, limit: Duration) -> Result<(), String> {
+    let kind = ProbeKind::parse(&server.probe, server.port).unwrap_or(ProbeKind::Tcp);
+    let deadline = Instant::now() + limit;
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let reason = match probe::probe(&server.host, server.port, kind, left.min(Duration::from_secs(2))).await.status {
+            ProbeStatus::Healthy => return Ok(()),
+            ProbeStatus::Down(why) | ProbeStatus::PortOpen(why) => why,
+        };
+        if Instant::now() + Duration::from_secs(1) >= deadline {
+            return Err(format!("sonda neprešla: {}", reason));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    pub ok: bool,
+    //stav servera po prechode
+    pub status: String,
+    pub message: String,
+}
+
+async fn run_steps(conn: &mut SqliteConnection, server: &Server, cfg: &LifecycleCommand, action: Action, steps: &[String]) -> Result<(), String> {
+    let deadline = Instant::now() + Duration::from_secs(cfg.timeout_secs.max(1) as u64);
+    for cmd in steps {
+        let left = deadline.saturating_duration_since(Instant::now());
+        let result = run_command(cmd, left).await;
+        let output = match &result {
+            Ok(out) => out.clone(),
+            Err(e) => e.clone(),
+        };
+        let msg = if output.is_empty() { format!("$ {}", cmd) } else { format!("$ {}\n{}", cmd, output) };
+        record_event(conn, server.id, "lifecycle.output", &msg).ok();
+        result?;
+    }
+    if cfg.wait_probe && action != Action::Stop {
+        wait_healthy(server, deadline.saturating_duration_since(Instant::now())).await?;
+    }
+    Ok(())
+}
+
+//aktivacia noveho servera ("/" -> OFF), progress dostava percenta
+pub async fn activate(server: Server, mut progress: impl FnMut(u32)) -> Outcome {
+    if server.status != "/" {
+        return Outcome { ok: false, status: server.status.clone(), message: format!("ERROR: Server {} je už aktivovaný!", server.name) };
+    }
+    for pct in (0..=100).step_by(25) {
+        progress(pct);
+        tokio::time::sleep(Duration::from_millis(400)).await;
+    }
+    let mut conn = match open_connection() {
+        Ok(c) => c,
+        Err(e) => return Outcome { ok: false, status: server.status.clone(), message: format!("ERROR: {}: databáza: {}", server.name, e) },
+    };
+    update_status(&mut conn, server.id, "OFF");
+    let message = format!("{}: Activation complete", server.name);
+    record_event(&mut conn, server.id, "lifecycle.done", &message).ok();
+    Outcome { ok: true, status: "OFF".into(), message }
+}
+
+fn failed(conn: &mut SqliteConnection, server: &Server, reason: &str) {
+    update_status(conn, server.id, FAILED);
+    fail_transition(conn, server.id, reason).ok();
+}
+
+//cely prechod: docasny stav, prikazy, sonda a vysledok v udalostiach
+//termin sa uklada do databazy, aby sa prerusene prechody dali najst aj po restarte
+pub async fn transition(server: Server, action: Action) -> Outcome {
+    let prev_status = server.status.clone();
+    run(server, action, prev_status).await
+}
+
+//server.status je stav, z ktoreho sa prechod zabera, prev_status sa vrati pri resete
+async fn run(server: Server, action: Action, prev_status: String) -> Outcome {
+    let mut conn = match open_connection() {
+        Ok(c) => c,
+        Err(e) => return Outcome { ok: false, status: server.status.clone(), message: format!("ERROR: {}: databáza: {}", server.name, e) },
+    };
+    let start = Instant::now();
+    let cfg = get_lifecycle_command(&mut conn, server.id).ok().flatten();
+    let steps = cfg.as_ref().map(|c| steps(c, action)).unwrap_or_default();
+    let limit = match &cfg {
+        Some(c) if !steps.is_empty() => Duration::from_secs(c.timeout_secs.max(1) as u64),
+        _ => SIMULATED,
+    };
+    let now = Utc::now().naive_utc();
+    let record = ServerTransition {
+        server_id: server.id,
+        action: action.as_str().into(),
+        prev_status,
+        started_at: now,
+        deadline: now + chrono::Duration::from_std(limit + GRACE).unwrap_or_default(),
+        reason: None,
+    };
+    //stav aj zaznam naraz, dva prikazy z roznych procesov nespustia prechod dvakrat
+    let claimed = conn.immediate_transaction(|c| {
+        if claim_status(c, server.id, &server.status, action.pending_status())? == 0 {
+            return Ok(false);
+        }
+        begin_transition(c, &record).map(|_| true)
+    });
+    match claimed {
+        Ok(true) => {}
+        Ok(false) => {
+            let status = get_status(&mut conn, server.id).ok().flatten().unwrap_or_else(|| server.status.clone());
+            return Outcome { ok: false, message: format!("ERROR: {}: server medzitým prešiel do stavu {}", server.name, status), status };
+        }
+        Err(e) => return Outcome { ok: false, status: server.status.clone(), message: format!("ERROR: {}: databáza: {}", server.name, e) },
+    }
+
+    let result = match &cfg {
+        Some(cfg) if !steps.is_empty() => run_steps(&mut conn, &server, cfg, action, &steps).await,
+        _ => {
+            tokio::time::sleep(SIMULATED).await;
+            Ok(())
+        }
+    };
+    let secs = start.elapsed().as_secs();
+    //prechod medzitym mohol vyprsat (FAILED) alebo ho niekto resetoval, vysledok sa uz nezapise
+    let current = get_status(&mut conn, server.id).ok().flatten();
+    if !current.as_deref().is_some_and(is_pending) {
+        let status = current.unwrap_or_else(|| server.status.clone());
+        let message = format!("ERROR: {}: {} dobehol po {}s, ale server je už v stave {}", server.name, action.as_str(), secs, status);
+        record_event(&mut conn, server.id, "lifecycle.error", &message).ok();
+        return Outcome { ok: false, status, message };
+    }
+    match result {
+        Ok(()) => {
+            update_status(&mut conn, server.id, action.final_status());
+            end_transition(&mut conn, server.id).ok();
+            let message = format!("{}: {} (trvanie: {}s)", server.name, action.done_label(), secs);
+            record_event(&mut conn, server.id, "lifecycle.done", &message).ok();
+            Outcome { ok: true, status: action.final_status().into(), message }
+        }
+        Err(e) => {
+            let reason = format!("{} zlyhal po {}s: {}", action.as_str(), secs, e.lines().next().unwrap_or(""));
+            failed(&mut conn, &server, &reason);
+            let message = format!("ERROR: {}: {}", server.name, reason);
+            record_event(&mut conn, server.id, "lifecycle.error", &message).ok();
+            Outcome { ok: false, status: FAILED.into(), message }
+        }
+    }
+}
+
+//zopakuje akciu, ktora zlyhala
+pub async fn retry(server: Server) -> Outcome {
+    let record = match open_connection().map(|mut c| get_transition(&mut c, server.id)) {
+        Ok(Ok(Some(t))) if server.status == FAILED => t,
+        _ => return Outcome { ok: false, status: server.status.clone(), message: format!("ERROR: Server {} nie je v stave FAILED.", server.name) },
+    };
+    let Some(action) = Action::parse(&record.action) else {
+        return Outcome { ok: false, status: server.status.clone(), message: format!("ERROR: {}: neznáma akcia '{}'", server.name, record.action) };
+    };
+    //povodny stav ostava ten pred prvym pokusom
+    run(server, action, record.prev_status).await
+}
+
+//FAILED -> stav pred prechodom, bez spustenia prikazov
+pub fn reset(conn: &mut SqliteConnection, server: &Server) -> QueryResult<Result<String, String>> {
+    if server.status != FAILED {
+        return Ok(Err(format!("Server {} nie je v stave FAILED.", server.name)));
+    }
+    let status = get_transition(conn, server.id)?.map(|t| t.prev_status).unwrap_or_else(|| "OFF".into());
+    update_status(conn, server.id, &status);
+    end_transition(conn, server.id)?;
+    let message = format!("{}: reset na {}", server.name, status);
+    record_event(conn, server.id, "lifecycle.reset", &message)?;
+    Ok(Ok(message))
+}
+
+//prechody po termine a prechodne stavy bez zaznamu (napr. spadnuty proces) -> FAILED
+pub fn expire_transitions(conn: &mut SqliteConnection) -> QueryResult<Vec<String>> {
+    let now = Utc::now().naive_utc();
+    let records: HashMap<i32, ServerTransition> = get_transitions(conn)?.into_iter().map(|t| (t.server_id, t)).collect();
+    let mut logs = Vec::new();
+    for server in get_all_servers(conn)? {
+        if !is_pending(&server.status) {
+            continue;
+        }
+        let reason = match records.get(&server.id) {
+            Some(t) if t.deadline < now => format!("{} nedokončený do {} (proces prerušený?)", t.action, t.deadline.format("%H:%M:%S")),
+            Some(_) => continue,
+            None => {
+                //zaznam chyba, termin sa uz neda zistit
+                let action = if server.status.eq_ignore_ascii_case("Stopping") { Action::Stop } else { Action::Start };
+                begin_transition(conn, &ServerTransition {
+                    server_id: server.id,
+                    action: action.as_str().into(),
+                    prev_status: if action == Action::Stop { "ON".into() } else { "OFF".into() },
+                    started_at: now,
+                    deadline: now,
+                    reason: None,
+                })?;
+                format!("stav {} bez záznamu prechodu", server.status)
+            }
+        };
+        failed(conn, &server, &reason);
+        let message = format!("ERROR: {}: {}", server.name, reason);
+        record_event(conn, server.id, "lifecycle.error", &message)?;
+        logs.push(message);
+    }
+    Ok(logs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::save_lifecycle_command;
+    use crate::db::tests::{test_connection, test_server};
+    use tokio::net::TcpListener;
+
+    fn commands(server_id: i32, start: &str, stop: &str, restart: &str) -> LifecycleCommand {
+        LifecycleCommand { server_id, unit: String::new(), start_cmd: start.into(), stop_cmd: stop.into(), restart_cmd: restart.into(), timeout_secs: 5, wait_probe: false }
+    }
+
+    #[test]
+    fn steps_for_action() {
+        let cfg = commands(1, "start.sh", " ", "");
+        assert_eq!(steps(&cfg, Action::Start), ["start.sh"]);
+        assert!(steps(&cfg, Action::Stop).is_empty());
+        //restart bez prikazu = stop a start, prazdne sa vynechaju
+        assert_eq!(steps(&cfg, Action::Restart), ["start.sh"]);
+        assert_eq!(steps(&commands(1, "a", "b", ""), Action::Restart), ["b", "a"]);
+        assert_eq!(steps(&commands(1, "a", "b", "c"), Action::Restart), ["c"]);
+        let unit = LifecycleCommand { unit: "web's.service".into(), ..commands(1, "a", "b", "c") };
+        assert_eq!(steps(&unit, Action::Stop), ["systemctl stop 'web'\\''s.service'"]);
+    }
+
+    #[tokio::test]
+    async fn command_outcome() {
+        assert_eq!(run_command("true", Duration::from_secs(5)).await, Ok(String::new()));
+        assert_eq!(run_command("echo out; echo err >&2", Duration::from_secs(5)).await, Ok("out\nerr".into()));
+        assert_eq!(run_command("false", Duration::from_secs(5)).await, Err("exit status: 1".into()));
+        assert_eq!(run_command("echo zle; exit 3", Duration::from_secs(5)).await, Err("exit status: 3: zle".into()));
+    }
+
+    #[tokio::test]
+    async fn long_output_is_truncated() {
+        let out = run_command("head -c 5000 /dev/zero | tr '\\0' a", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(out.chars().count(), MAX_OUTPUT + 1);
+        assert!(out.ends_with("a…"));
+    }
+
+    #[tokio::test]
+    async fn timeout_kills_process_group() {
+        let pidfile = std::env::temp_dir().join(format!("monitor-test-sleep-{}.pid", std::process::id()));
+        let cmd = format!("sleep 30 & echo $! > {}; wait", pidfile.display());
+        let start = Instant::now();
+        assert_eq!(run_command(&cmd, Duration::from_millis(500)).await, Err("timeout 0s".into()));
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        //sleep bol potomok sh, nesmie prezit ani ako beziaci proces
+        let pid = std::fs::read_to_string(&pidfile).unwrap().trim().to_string();
+        std::fs::remove_file(&pidfile).ok();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let state = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+        assert!(state.is_empty() || state.contains(") Z "), "{}", state);
+    }
+
+    #[tokio::test]
+    async fn wait_for_probe() {
+        let mut conn = test_connection();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port() as i32;
+        let up = Server { probe: "tcp".into(), ..test_server(&mut conn, "probe", port) };
+        assert_eq!(wait_healthy(&up, Duration::from_secs(3)).await, Ok(()));
+
+        drop(listener);
+        let start = Instant::now();
+        let err = wait_healthy(&up, Duration::from_secs(2)).await.unwrap_err();
+        assert!(err.starts_with("sonda neprešla"), "{}", err);
+        assert!(start.elapsed() < Duration::from_secs(4));
+    }
+
+    #[tokio::test]
+    async fn transition_outcome() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "lifecycle", 80);
+        update_status(&mut conn, server.id, "OFF");
+        save_lifecycle_command(&mut conn, &commands(server.id, "echo hore", "false", "")).unwrap();
+
+        let started = transition(Server { status: "OFF".into(), ..server.clone() }, Action::Start).await;
+        assert!(started.ok, "{}", started.message);
+        assert_eq!(started.status, "ON");
+        assert!(get_transition(&mut conn, server.id).unwrap().is_none());
+
+        let stopped = transition(Server { status: "ON".into(), ..server.clone() }, Action::Stop).await;
+        assert!(!stopped.ok);
+        assert_eq!(stopped.status, FAILED);
+        let record = get_transition(&mut conn, server.id).unwrap().unwrap();
+        assert_eq!((record.action.as_str(), record.prev_status.as_str()), ("stop", "ON"));
+        assert!(record.reason.unwrap().starts_with("stop zlyhal"));
+    }
+
+    #[tokio::test]
+    async fn concurrent_transitions_claim_once() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "claim", 80);
+        update_status(&mut conn, server.id, "OFF");
+        save_lifecycle_command(&mut conn, &commands(server.id, "sleep 0.3", "", "")).unwrap();
+
+        //oba procesy videli OFF
+        let off = Server { status: "OFF".into(), ..server.clone() };
+        let (a, b) = tokio::join!(transition(off.clone(), Action::Start), transition(off, Action::Start));
+        assert!(a.ok != b.ok, "{} / {}", a.message, b.message);
+        let lost = if a.ok { b } else { a };
+        assert!(lost.message.contains("medzitým prešiel do stavu Starting"), "{}", lost.message);
+        assert_eq!(get_status(&mut conn, server.id).unwrap().as_deref(), Some("ON"));
+
+        //zastaraly stav v GUI uz nic nespusti
+        let stale = transition(Server { status: "OFF".into(), ..server.clone() }, Action::Start).await;
+        assert!(!stale.ok);
+        assert_eq!(stale.status, "ON");
+    }
+
+    fn record(server_id: i32, action: &str, prev: &str, deadline: chrono::NaiveDateTime) -> ServerTransition {
+        ServerTransition { server_id, action: action.into(), prev_status: prev.into(), started_at: deadline - chrono::Duration::seconds(60), deadline, reason: None }
+    }
+
+    #[test]
+    fn reset_restores_previous_status() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "reset", 80);
+        assert!(reset(&mut conn, &server).unwrap().is_err());
+
+        begin_transition(&mut conn, &record(server.id, "stop", "ON", Utc::now().naive_utc())).unwrap();
+        update_status(&mut conn, server.id, FAILED);
+        let message = reset(&mut conn, &Server { status: FAILED.into(), ..server.clone() }).unwrap().unwrap();
+        assert!(message.ends_with("reset na ON"));
+        assert_eq!(get_status(&mut conn, server.id).unwrap().as_deref(), Some("ON"));
+        assert!(get_transition(&mut conn, server.id).unwrap().is_none());
+
+        //bez zaznamu sa nevie, odkial prechod zacal
+        update_status(&mut conn, server.id, FAILED);
+        reset(&mut conn, &Server { status: FAILED.into(), ..server.clone() }).unwrap().unwrap();
+        assert_eq!(get_status(&mut conn, server.id).unwrap().as_deref(), Some("OFF"));
+    }
+
+    #[tokio::test]
+    async fn retry_repeats_failed_action() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "retry", 80);
+        let refused = retry(Server { status: "OFF".into(), ..server.clone() }).await;
+        assert!(!refused.ok);
+        assert!(refused.message.contains("nie je v stave FAILED"));
+
+        save_lifecycle_command(&mut conn, &commands(server.id, "true", "", "")).unwrap();
+        begin_transition(&mut conn, &record(server.id, "start", "OFF", Utc::now().naive_utc())).unwrap();
+        update_status(&mut conn, server.id, FAILED);
+        let outcome = retry(Server { status: FAILED.into(), ..server.clone() }).await;
+        assert!(outcome.ok, "{}", outcome.message);
+        assert_eq!(get_status(&mut conn, server.id).unwrap().as_deref(), Some("ON"));
+        assert!(get_transition(&mut conn, server.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn expired_transitions_fail() {
+        let mut conn = test_connection();
+        let now = Utc::now().naive_utc();
+        let (late, running, orphan) = (test_server(&mut conn, "late", 80), test_server(&mut conn, "running", 80), test_server(&mut conn, "orphan", 80));
+        begin_transition(&mut conn, &record(late.id, "start", "OFF", now - chrono::Duration::seconds(1))).unwrap();
+        begin_transition(&mut conn, &record(running.id, "start", "OFF", now + chrono::Duration::seconds(60))).unwrap();
+        update_status(&mut conn, late.id, "Starting");
+        update_status(&mut conn, running.id, "Starting");
+        update_status(&mut conn, orphan.id, "Stopping");
+
+        let logs = expire_transitions(&mut conn).unwrap();
+        assert!(logs.iter().any(|l| l.contains(&format!("{}: start nedokončený", late.name))));
+        assert!(logs.iter().any(|l| l.contains(&format!("{}: stav Stopping bez záznamu", orphan.name))));
+        assert!(!logs.iter().any(|l| l.contains(&running.name)));
+        assert_eq!(get_status(&mut conn, late.id).unwrap().as_deref(), Some(FAILED));
+        assert_eq!(get_status(&mut conn, running.id).unwrap().as_deref(), Some("Starting"));
+        //pre orphan vznikol zaznam, aby sa dal resetovat na ON
+        let t = get_transition(&mut conn, orphan.id).unwrap().unwrap();
+        assert_eq!((t.action.as_str(), t.prev_status.as_str(), t.reason.is_some()), ("stop", "ON", true));
+    }
+
+    #[tokio::test]
+    async fn late_result_keeps_failed() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "late-step", 80);
+        update_status(&mut conn, server.id, "OFF");
+        save_lifecycle_command(&mut conn, &commands(server.id, "sleep 1", "", "")).unwrap();
+
+        //prechod vyprsi skor, nez prikaz dobehne
+        let expire = async {
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            failed(&mut conn, &server, "start nedokončený");
+        };
+        let (outcome, ()) = tokio::join!(transition(Server { status: "OFF".into(), ..server.clone() }, Action::Start), expire);
+        assert!(!outcome.ok);
+        assert_eq!(outcome.status, FAILED);
+        assert_eq!(get_status(&mut conn, server.id).unwrap().as_deref(), Some(FAILED));
+        assert_eq!(get_transition(&mut conn, server.id).unwrap().unwrap().reason.as_deref(), Some("start nedokončený"));
+    }
+}
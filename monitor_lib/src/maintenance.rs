@@ -0,0 +1,222 @@
+//okna udrzby a stisenia: pocas nich sa alerty neposielaju do kanalov
+
+use crate::alerts::AlertTransition;
+use crate::db::{get_maintenance_windows, get_silences, get_tags, SqliteConnection};
+use crate::models::{MaintenanceWindow, Scope, Server, Silence};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::QueryResult;
+use std::collections::HashMap;
+use std::time::Instant;
+
+const RELOAD_EVERY: std::time::Duration = std::time::Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recur {
+    Once,
+    Daily,
+    Weekly,
+}
+
+impl Recur {
+    pub fn parse(s: &str) -> Option<Recur> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "once" => Some(Recur::Once),
+            "daily" | "day" => Some(Recur::Daily),
+            "weekly" | "week" => Some(Recur::Weekly),
+            _ => None,
+        }
+    }
+
+    //tak ako sa uklada do databazy
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Recur::Once => "",
+            Recur::Daily => "daily",
+            Recur::Weekly => "weekly",
+        }
+    }
+
+    fn period(&self) -> Option<Duration> {
+        match self {
+            Recur::Once => None,
+            Recur::Daily => Some(Duration::days(1)),
+            Recur::Weekly => Some(Duration::weeks(1)),
+        }
+    }
+}
+
+//koniec prebiehajuceho vyskytu okna, None = okno prave neplati
+pub fn window_end(w: &MaintenanceWindow, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    if now < w.starts_at {
+        return None;
+    }
+    let length = Duration::minutes(w.duration_mins.max(0) as i64);
+    let start = match Recur::parse(&w.recur).unwrap_or(Recur::Once).period() {
+        Some(p) => {
+            let n = (now - w.starts_at).num_seconds() / p.num_seconds();
+            w.starts_at + Duration::seconds(n * p.num_seconds())
+        }
+        None => w.starts_at,
+    };
+    (now < start + length).then_some(start + length)
+}
+
+//"90m", "2h", "1d", "1h30m"; prilis velka hodnota = None
+pub fn parse_duration(s: &str) -> Option<Duration> {
+    let mut total = Duration::zero();
+    let mut num = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            num.push(c);
+            continue;
+        }
+        let n: i64 = num.parse().ok()?;
+        num.clear();
+        let part = match c {
+            's' => Duration::try_seconds(n),
+            'm' => Duration::try_minutes(n),
+            'h' => Duration::try_hours(n),
+            'd' => Duration::try_days(n),
+            'w' => Duration::try_weeks(n),
+            _ => return None,
+        };
+        total = total.checked_add(&part?)?;
+    }
+    //samotne cislo su minuty
+    if !num.is_empty() {
+        total = total.checked_add(&Duration::try_minutes(num.parse().ok()?)?)?;
+    }
+    (total > Duration::zero()).then_some(total)
+}
+
+//hodnota s hviezdickou na konci porovnava iba zaciatok
+fn glob(pattern: &str, value: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => value.starts_with(prefix),
+        None => pattern == value,
+    }
+}
+
+//"rule=cpu-high,server=web*,tag=prod", vsetky casti musia platit
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Matcher {
+    pub rule: Option<String>,
+    pub server: Option<String>,
+    pub tag: Option<String>,
+}
+
+impl Matcher {
+    pub fn parse(src: &str) -> Result<Matcher, String> {
+        let mut m = Matcher::default();
+        for part in src.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let Some((key, value)) = part.split_once('=') else {
+                return Err(format!("očakávané kľúč=hodnota, nie '{}'", part));
+            };
+            let value = Some(value.trim().to_string());
+            match key.trim() {
+                "rule" => m.rule = value,
+                "server" => m.server = value,
+                "tag" => m.tag = value,
+                other => return Err(format!("neznámy kľúč '{}' (rule, server, tag)", other)),
+            }
+        }
+        if m == Matcher::default() {
+            return Err("prázdny matcher".into());
+        }
+        Ok(m)
+    }
+
+    pub fn matches(&self, rule: &str, server: &Server, tags: &[String]) -> bool {
+        self.rule.as_ref().is_none_or(|p| glob(p, rule))
+            && self.server.as_ref().is_none_or(|p| glob(p, &server.name))
+            && self.tag.as_ref().is_none_or(|p| tags.iter().any(|t| glob(p, t)))
+    }
+}
+
+//aktualne okna a stisenia, nacitane z databazy
+#[derive(Default)]
+pub struct Suppressions {
+    windows: Vec<(MaintenanceWindow, Option<Scope>)>,
+    silences: Vec<(Silence, Matcher)>,
+    tags: HashMap<i32, Vec<String>>,
+    loaded_at: Option<Instant>,
+}
+
+impl Suppressions {
+    pub fn new() -> Self {
+        Suppressions::default()
+    }
+
+    pub fn load(conn: &mut SqliteConnection) -> QueryResult<Self> {
+        let mut s = Suppressions::new();
+        s.reload(conn)?;
+        Ok(s)
+    }
+
+    pub fn reload(&mut self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        self.windows = get_maintenance_windows(conn)?.into_iter().map(|w| { let sc = Scope::parse(&w.scope); (w, sc) }).collect();
+        self.silences = get_silences(conn)?.into_iter().filter_map(|s| { let m = Matcher::parse(&s.matcher).ok()?; Some((s, m)) }).collect();
+        self.tags = get_tags(conn)?;
+        self.loaded_at = Some(Instant::now());
+        Ok(())
+    }
+
+    //zmeny z CLI sa prejavia do 10s
+    pub fn refresh(&mut self, conn: &mut SqliteConnection) -> QueryResult<()> {
+        if self.loaded_at.is_none_or(|t| t.elapsed() >= RELOAD_EVERY) {
+            self.reload(conn)?;
+        }
+        Ok(())
+    }
+
+    fn tags(&self, server: &Server) -> &[String] {
+        self.tags.get(&server.id).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    //prebiehajuce okno udrzby a jeho koniec
+    pub fn maintenance(&self, server: &Server) -> Option<(&MaintenanceWindow, NaiveDateTime)> {
+        let now = Utc::now().naive_utc();
+        let tags = self.tags(server);
+        self.windows.iter()
+            .filter(|(_, sc)| sc.as_ref().is_some_and(|sc| sc.matches(server, tags)))
+            .filter_map(|(w, _)| window_end(w, now).map(|end| (w, end)))
+            .max_by_key(|(_, end)| *end)
+    }
+
+    pub fn silenced(&self, rule: &str, server: &Server) -> Option<&Silence> {
+        let now = Utc::now().naive_utc();
+        let tags = self.tags(server);
+        self.silences.iter().find(|(s, m)| s.expires_at > now && m.matches(rule, server, tags)).map(|(s, _)| s)
+    }
+
+    //dovod potlacenia pre log, None = posiela sa normalne
+    pub fn reason(&self, t: &AlertTransition) -> Option<String> {
+        if let Some((w, end)) = self.maintenance(&t.server) {
+            return Some(format!("údržba #{} do {}", w.id, end.format("%H:%M")));
+        }
+        self.silenced(&t.rule.name, &t.server).map(|s| format!("stíšené #{} ({})", s.id, s.author))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("90m"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("45"), Some(Duration::minutes(45)));
+        assert_eq!(parse_duration("2w1d"), Some(Duration::days(15)));
+        assert_eq!(parse_duration("0m"), None);
+        assert_eq!(parse_duration("5x"), None);
+        assert_eq!(parse_duration("h"), None);
+    }
+
+    #[test]
+    fn huge_duration_is_rejected() {
+        assert_eq!(parse_duration("9999999999999999d"), None);
+        assert_eq!(parse_duration("99999999999999999999m"), None);
+        assert_eq!(parse_duration("9223372036854775807s9223372036854775807s"), None);
+    }
+}
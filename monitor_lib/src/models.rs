@@ -1,6 +1,7 @@
 //definicia datovej struktury
 
 use diesel::prelude::*;
+use chrono::NaiveDateTime;
 use serde::{Serialize, Deserialize};
 
 #[derive(Queryable, Selectable, Insertable, Serialize, Deserialize, Debug, Clone)]
@@ -12,4 +13,324 @@ pub struct Server {
     pub port: i32,
     pub cpu_model: String,
     pub max_ram: f32,
-}
\ No newline at end of file
+    pub source: String,
+    pub interval_ms: i32,
+    pub timeout_ms: i32,
+    pub host: String,
+    pub probe: String,
+}
+
+//jedna vzorka metrik servera, response_ms = -1 znamena nedostupny
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct Metrics {
+    pub response_ms: i32,
+    pub cpu_usage: f32,
+    pub ram_usage: f32,
+    pub disk_usage: f32,
+    pub disk_io: f32,
+    pub net_rx: f32,
+    pub net_tx: f32,
+    pub load_avg: f32,
+    pub connections: i32,
+    //statistika viacerych merani odozvy v cykle, response_ms je priemer
+    #[serde(default)]
+    pub rtt_min: i32,
+    #[serde(default)]
+    pub rtt_max: i32,
+    #[serde(default)]
+    pub rtt_p95: i32,
+    #[serde(default)]
+    pub loss_pct: f32,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::history)]
+pub struct HistoryEntry {
+    pub id: i32,
+    pub server_id: i32,
+    pub timestamp: NaiveDateTime,
+    pub response_ms: i32,
+    pub ram_usage: f32,
+    pub cpu_usage: f32,
+    pub disk_usage: f32,
+    pub disk_io: f32,
+    pub net_rx: f32,
+    pub net_tx: f32,
+    pub load_avg: f32,
+    pub connections: i32,
+    pub rtt_min: i32,
+    pub rtt_max: i32,
+    pub rtt_p95: i32,
+    pub loss_pct: f32,
+}
+
+impl HistoryEntry {
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            response_ms: self.response_ms,
+            cpu_usage: self.cpu_usage,
+            ram_usage: self.ram_usage,
+            disk_usage: self.disk_usage,
+            disk_io: self.disk_io,
+            net_rx: self.net_rx,
+            net_tx: self.net_tx,
+            load_avg: self.load_avg,
+            connections: self.connections,
+            rtt_min: self.rtt_min,
+            rtt_max: self.rtt_max,
+            rtt_p95: self.rtt_p95,
+            loss_pct: self.loss_pct,
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::cpu_profiles)]
+pub struct CpuProfile {
+    pub name: String,
+    pub cores: i32,
+    pub efficiency: f32,
+    pub base_load: f32,
+}
+
+impl CpuProfile {
+    fn new(name: &str, cores: i32, efficiency: f32, base_load: f32) -> Self {
+        CpuProfile { name: name.to_string(), cores, efficiency, base_load }
+    }
+
+    //zakladny katalog, ktorym sa naplni nova databaza
+    pub fn builtin() -> Vec<CpuProfile> {
+        vec![
+            CpuProfile::new("intel-i5", 6, 1.0, 35.0),
+            CpuProfile::new("intel-i7", 8, 0.7, 25.0),
+            CpuProfile::new("intel-i9", 16, 0.4, 15.0),
+            CpuProfile::new("ryzen-5", 6, 1.0, 35.0),
+            CpuProfile::new("ryzen-7", 8, 0.7, 25.0),
+            CpuProfile::new("ryzen-9", 16, 0.4, 15.0),
+        ]
+    }
+
+    //volny nazov modelu ("Intel Core i7-12700", "Ryzen 9 5950X") obsahuje vsetky casti nazvu profilu,
+    //alebo aspon poslednu, ak nie je iba cislo (i7)
+    pub fn matches_model(&self, model: &str) -> bool {
+        let words = |s: &str| s.to_lowercase().split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_string).collect::<Vec<_>>();
+        let (model, name) = (words(model), words(&self.name));
+        if name.is_empty() {
+            return false;
+        }
+        if name.iter().all(|w| model.contains(w)) {
+            return true;
+        }
+        name.last().is_some_and(|last| !last.chars().all(|c| c.is_ascii_digit()) && model.contains(last))
+    }
+
+    //pre modely mimo katalogu
+    pub fn fallback() -> Self {
+        CpuProfile::new("generic", 4, 1.0, 35.0)
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Deserialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::prometheus_sources)]
+pub struct PrometheusSource {
+    pub server_id: i32,
+    pub url: String,
+    pub cpu_series: String,
+    pub ram_series: String,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::events)]
+pub struct Event {
+    pub id: i32,
+    pub server_id: i32,
+    pub timestamp: NaiveDateTime,
+    pub kind: String,
+    pub message: String,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::slo_targets)]
+pub struct SloTarget {
+    pub id: i32,
+    pub scope: String,
+    pub objective: f32,
+    pub window_days: i32,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::alert_rules)]
+pub struct AlertRule {
+    pub id: i32,
+    pub name: String,
+    pub scope: String,
+    pub expr: String,
+    //mena kanalov oddelene ciarkou, prazdne = vsetky
+    pub channels: String,
+    //critical / warning / info
+    pub severity: String,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::alerts)]
+pub struct Alert {
+    pub id: i32,
+    pub rule_id: i32,
+    pub server_id: i32,
+    pub state: String,
+    pub value: f32,
+    pub started_at: NaiveDateTime,
+    pub fired_at: Option<NaiveDateTime>,
+    pub resolved_at: Option<NaiveDateTime>,
+    //potvrdenie, ze sa niekto alertu venuje
+    pub ack_by: Option<String>,
+    pub ack_at: Option<NaiveDateTime>,
+    pub ack_note: Option<String>,
+    //posledna odoslana uroven eskalacie (0 = iba kanaly pravidla) a kedy
+    pub esc_level: i32,
+    pub esc_at: Option<NaiveDateTime>,
+    //nadradeny server, ktoreho vypadok alert sposobil
+    pub caused_by: Option<String>,
+    //vystrelil pocas udrzby, stisenia alebo flappingu a este nebol ohlaseny
+    pub muted: bool,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::channels)]
+pub struct Channel {
+    pub id: i32,
+    pub name: String,
+    pub kind: String,
+    pub target: String,
+    pub config: String,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::deliveries)]
+pub struct Delivery {
+    pub id: i32,
+    pub alert_id: i32,
+    pub channel_id: i32,
+    pub state: String,
+    pub attempt: i32,
+    pub ok: bool,
+    pub error: Option<String>,
+    pub timestamp: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::escalation_policies)]
+pub struct EscalationPolicy {
+    pub id: i32,
+    pub name: String,
+    pub scope: String,
+    //po kolkych minutach bez potvrdenia ide dalsia uroven
+    pub every_mins: i32,
+    pub tiers: String,
+    //kolkokrat sa po poslednej urovni este zopakuje
+    pub max_repeats: i32,
+}
+
+impl EscalationPolicy {
+    pub fn tier_list(&self) -> Vec<String> {
+        self.tiers.split(';').map(|t| t.split(',').map(str::trim).filter(|c| !c.is_empty()).collect::<Vec<_>>().join(",")).filter(|t| !t.is_empty()).collect()
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::flap_state)]
+pub struct FlapState {
+    pub server_id: i32,
+    pub flapping: bool,
+    pub change_pct: f32,
+    pub since: NaiveDateTime,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::lifecycle_commands)]
+pub struct LifecycleCommand {
+    pub server_id: i32,
+    pub unit: String,
+    pub start_cmd: String,
+    pub stop_cmd: String,
+    pub restart_cmd: String,
+    pub timeout_secs: i32,
+    //start je hotovy az ked sonda hlasi zdravu sluzbu
+    pub wait_probe: bool,
+}
+
+#[derive(Queryable, Selectable, Insertable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::server_transitions)]
+pub struct ServerTransition {
+    pub server_id: i32,
+    pub action: String,
+    //stav pred prechodom, kam sa vrati reset
+    pub prev_status: String,
+    pub started_at: NaiveDateTime,
+    pub deadline: NaiveDateTime,
+    pub reason: Option<String>,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::maintenance_windows)]
+pub struct MaintenanceWindow {
+    pub id: i32,
+    pub scope: String,
+    pub starts_at: NaiveDateTime,
+    pub duration_mins: i32,
+    pub recur: String,
+    pub comment: String,
+}
+
+#[derive(Queryable, Selectable, Serialize, Debug, Clone)]
+#[diesel(table_name = crate::schema::silences)]
+pub struct Silence {
+    pub id: i32,
+    pub matcher: String,
+    pub author: String,
+    pub comment: String,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+//na ktore servery sa pravidlo vztahuje
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Scope {
+    All,
+    Server(String),
+    Tag(String),
+}
+
+impl Scope {
+    //"all", "server:web1", "tag:prod", samotny nazov = server
+    pub fn parse(s: &str) -> Option<Scope> {
+        let s = s.trim();
+        match s.split_once(':') {
+            Some(("server", name)) if !name.is_empty() => Some(Scope::Server(name.to_string())),
+            Some(("tag", tag)) if !tag.is_empty() => Some(Scope::Tag(tag.to_string())),
+            Some(_) => None,
+            None if s == "all" => Some(Scope::All),
+            None if !s.is_empty() => Some(Scope::Server(s.to_string())),
+            None => None,
+        }
+    }
+
+    pub fn matches(&self, server: &Server, tags: &[String]) -> bool {
+        match self {
+            Scope::All => true,
+            Scope::Server(name) => server.name == *name,
+            Scope::Tag(tag) => tags.contains(tag),
+        }
+    }
+}
+
+impl std::fmt::Display for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Scope::All => write!(f, "all"),
+            Scope::Server(name) => write!(f, "server:{}", name),
+            Scope::Tag(tag) => write!(f, "tag:{}", tag),
+        }
+    }
+}
+
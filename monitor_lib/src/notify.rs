@@ -0,0 +1,815 @@
+//dorucovanie alertov: JSON webhook, SMTP e-mail a lokalny prikaz, s opakovanim
+//texty sa skladaju zo sablon, pri zoskupeni ide viac alertov v jednej sprave
+
+use crate::alerts::{AlertState, AlertTransition, Escalation};
+use crate::db::{get_alert, get_channels, get_setting, get_tags, open_connection, record_delivery, set_alert_muted, setting_or, SqliteConnection};
+use crate::http;
+use crate::flap::FlapChange;
+use crate::models::{Channel, Metrics, Server};
+use crate::template::Template;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::process::Command;
+use tokio::task::JoinSet;
+use tokio::time::timeout;
+
+const RELOAD_EVERY: Duration = Duration::from_secs(10);
+//jeden pokus o dorucenie
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Debug, Clone)]
+pub struct Notification {
+    pub alert_id: i32,
+    pub state: String,
+    pub rule: String,
+    pub expr: String,
+    pub severity: String,
+    pub server: String,
+    pub server_id: i32,
+    pub value: f64,
+    pub timestamp: DateTime<Utc>,
+    //uroven eskalacie, 0 = prva notifikacia
+    pub level: i32,
+    //cely server a posledne meranie pre sablony
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_info: Option<Server>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<Metrics>,
+}
+
+impl Notification {
+    //skusobna notifikacia pre `channel test` a nahlad sablon
+    pub fn test() -> Self {
+        Notification {
+            alert_id: 0,
+            state: "test".into(),
+            rule: "test".into(),
+            expr: "-".into(),
+            severity: "info".into(),
+            server: "monitor".into(),
+            server_id: 0,
+            value: 0.0,
+            timestamp: Utc::now(),
+            level: 0,
+            server_info: None,
+            metrics: None,
+        }
+    }
+
+    //premenne sablony, server je objekt so vsetkymi polami
+    pub fn context(&self) -> Value {
+        let mut v = serde_json::to_value(self).unwrap_or_default();
+        if let Some(o) = v.as_object_mut() {
+            o.remove("server_info");
+            let server = match &self.server_info {
+                Some(s) => serde_json::to_value(s).unwrap_or_default(),
+                None => json!({ "id": self.server_id, "name": self.server }),
+            };
+            o.insert("server".into(), server);
+            o.insert("time".into(), self.timestamp.format("%Y-%m-%d %H:%M:%S UTC").to_string().into());
+        }
+        v
+    }
+}
+
+impl From<&AlertTransition> for Notification {
+    fn from(t: &AlertTransition) -> Self {
+        Notification {
+            alert_id: t.alert_id,
+            state: t.state.as_str().to_string(),
+            rule: t.rule.name.clone(),
+            expr: t.rule.expr.clone(),
+            severity: t.rule.severity.clone(),
+            server: t.server.name.clone(),
+            server_id: t.server.id,
+            value: t.value,
+            timestamp: Utc::now(),
+            level: 0,
+            server_info: Some(t.server.clone()),
+            metrics: t.metrics,
+        }
+    }
+}
+
+//rovnaky alert v skupine sa zluci, count = kolkokrat prisiel
+#[derive(Serialize, Debug, Clone)]
+pub struct DigestItem {
+    #[serde(flatten)]
+    pub notification: Notification,
+    pub count: usize,
+}
+
+//viac notifikacii z jedneho okna zoskupenia v jednej sprave
+#[derive(Serialize, Debug, Clone)]
+pub struct Digest {
+    pub group: String,
+    pub key: String,
+    pub alerts: Vec<DigestItem>,
+}
+
+impl Digest {
+    pub fn context(&self) -> Value {
+        let alerts: Vec<Value> = self.alerts.iter().map(|i| {
+            let mut v = i.notification.context();
+            v["count"] = i.count.into();
+            v["repeated"] = (i.count > 1).into();
+            v
+        }).collect();
+        let count_state = |state: &str| self.alerts.iter().filter(|i| i.notification.state == state).count();
+        json!({
+            "group": self.group,
+            "key": self.key,
+            "count": self.alerts.len(),
+            "firing": count_state("firing"),
+            "resolved": count_state("resolved"),
+            "alerts": alerts,
+            "time": Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        })
+    }
+}
+
+pub const DEFAULT_SUBJECT: &str = "[monitor] {{state | upper}}{{#if level}} (eskalácia {{level}}){{/if}} {{rule}} na {{server.name}}";
+pub const DEFAULT_BODY: &str = "Alert:     {{rule}}\nServer:    {{server.name}}\nStav:      {{state}}\nPodmienka: {{expr}}\nHodnota:   {{value}}\nČas:       {{time}}\n";
+pub const DEFAULT_GROUP_SUBJECT: &str = "[monitor] {{key}}: {{firing}} firing, {{resolved}} resolved";
+pub const DEFAULT_GROUP_BODY: &str = "Skupina: {{group}} = {{key}}\n{{#each alerts}}- {{state | upper}} {{rule}} na {{server.name}}: {{expr}} (hodnota {{value}}){{#if repeated}} {{count}}x{{/if}}\n{{/each}}Čas: {{time}}\n";
+
+//nastavenie -> predvolena sablona
+pub const TEMPLATES: [(&str, &str); 4] = [
+    ("template_subject", DEFAULT_SUBJECT),
+    ("template_body", DEFAULT_BODY),
+    ("template_group_subject", DEFAULT_GROUP_SUBJECT),
+    ("template_group_body", DEFAULT_GROUP_BODY),
+];
+
+//hotova sprava pre jeden kanal
+#[derive(Debug, Clone)]
+pub struct Message {
+    pub subject: String,
+    pub text: String,
+    //telo webhooku a stdin prikazu
+    pub payload: Value,
+    pub env: Vec<(&'static str, String)>,
+    //(alert, stav) pre zaznam dorucenia
+    pub alerts: Vec<(i32, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Templates {
+    subject: Template,
+    body: Template,
+    group_subject: Template,
+    group_body: Template,
+}
+
+impl Default for Templates {
+    fn default() -> Self {
+        let t = |src| Template::parse(src).expect("predvolená šablóna");
+        Templates { subject: t(DEFAULT_SUBJECT), body: t(DEFAULT_BODY), group_subject: t(DEFAULT_GROUP_SUBJECT), group_body: t(DEFAULT_GROUP_BODY) }
+    }
+}
+
+impl Templates {
+    //chybna sablona v nastaveniach sa nahradi predvolenou
+    pub fn load(conn: &mut SqliteConnection) -> Self {
+        let mut t = Templates::default();
+        for (key, _) in TEMPLATES {
+            let Some(parsed) = get_setting(conn, key).ok().flatten().and_then(|src| Template::parse(&src).ok()) else {
+                continue;
+            };
+            match key {
+                "template_subject" => t.subject = parsed,
+                "template_body" => t.body = parsed,
+                "template_group_subject" => t.group_subject = parsed,
+                _ => t.group_body = parsed,
+            }
+        }
+        t
+    }
+
+    pub fn single(&self, n: &Notification) -> Message {
+        let ctx = n.context();
+        let (subject, text) = (self.subject.render(&ctx).trim().to_string(), self.body.render(&ctx));
+        let mut payload = serde_json::to_value(n).unwrap_or_default();
+        payload["subject"] = subject.clone().into();
+        payload["text"] = text.clone().into();
+        let env = vec![
+            ("ALERT_ID", n.alert_id.to_string()),
+            ("ALERT_STATE", n.state.clone()),
+            ("ALERT_RULE", n.rule.clone()),
+            ("ALERT_EXPR", n.expr.clone()),
+            ("ALERT_SERVER", n.server.clone()),
+            ("ALERT_VALUE", format!("{:.2}", n.value)),
+            ("ALERT_SUBJECT", subject.clone()),
+        ];
+        Message { subject, text, payload, env, alerts: vec![(n.alert_id, n.state.clone())] }
+    }
+
+    pub fn digest(&self, d: &Digest) -> Message {
+        let ctx = d.context();
+        let (subject, text) = (self.group_subject.render(&ctx).trim().to_string(), self.group_body.render(&ctx));
+        let mut payload = serde_json::to_value(d).unwrap_or_default();
+        payload["count"] = d.alerts.len().into();
+        payload["subject"] = subject.clone().into();
+        payload["text"] = text.clone().into();
+        //pravidla a servery skupiny oddelene ciarkou
+        let joined = |f: fn(&Notification) -> &str| {
+            let mut v: Vec<&str> = d.alerts.iter().map(|i| f(&i.notification)).collect();
+            v.sort();
+            v.dedup();
+            v.join(",")
+        };
+        let env = vec![
+            ("ALERT_RULE", joined(|n| &n.rule)),
+            ("ALERT_SERVER", joined(|n| &n.server)),
+            ("ALERT_GROUP", d.group.clone()),
+            ("ALERT_KEY", d.key.clone()),
+            ("ALERT_COUNT", d.alerts.len().to_string()),
+            ("ALERT_SUBJECT", subject.clone()),
+        ];
+        let alerts = d.alerts.iter().map(|i| (i.notification.alert_id, i.notification.state.clone())).collect();
+        Message { subject, text, payload, env, alerts }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    pub attempts: u32,
+    //prva pauza, kazda dalsia je dvojnasobna
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy { attempts: 5, backoff: Duration::from_secs(1), max_backoff: Duration::from_secs(60) }
+    }
+}
+
+impl RetryPolicy {
+    pub fn load(conn: &mut SqliteConnection) -> Self {
+        let d = RetryPolicy::default();
+        RetryPolicy {
+            attempts: setting_or(conn, "notify_attempts", d.attempts).max(1),
+            backoff: Duration::from_millis(setting_or(conn, "notify_backoff_ms", d.backoff.as_millis() as u64)),
+            max_backoff: d.max_backoff,
+        }
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(1 << attempt.saturating_sub(1).min(16)).min(self.max_backoff)
+    }
+}
+
+//config kanala je JSON objekt, napr. {"from": "...", "to": "..."}
+fn config_str(channel: &Channel, key: &str) -> Option<String> {
+    let v: serde_json::Value = serde_json::from_str(&channel.config).ok()?;
+    v.get(key)?.as_str().map(str::to_string)
+}
+
+pub fn smtp_config(from: Option<&str>, to: &str) -> String {
+    let mut v = serde_json::json!({ "to": to });
+    if let Some(from) = from {
+        v["from"] = from.into();
+    }
+    v.to_string()
+}
+
+pub async fn deliver(channel: &Channel, m: &Message) -> Result<(), String> {
+    match channel.kind.as_str() {
+        "webhook" => webhook(&channel.target, m).await,
+        "smtp" => {
+            let from = config_str(channel, "from").unwrap_or_else(|| "monitor@localhost".into());
+            let to = config_str(channel, "to").ok_or("SMTP kanál nemá adresáta (to)")?;
+            let to: Vec<&str> = to.split(',').map(str::trim).filter(|a| !a.is_empty()).collect();
+            smtp(&channel.target, &from, &to, m).await
+        }
+        "exec" => exec(&channel.target, m).await,
+        other => Err(format!("neznámy typ kanála '{}'", other)),
+    }
+}
+
+async fn webhook(url: &str, m: &Message) -> Result<(), String> {
+    let body = serde_json::to_vec(&m.payload).map_err(|e| e.to_string())?;
+    let resp = http::post_json(url, &body).await.map_err(|e| e.to_string())?;
+    if (200..300).contains(&resp.status) { Ok(()) } else { Err(format!("HTTP {}", resp.status)) }
+}
+
+//odpoved SMTP servera, viacriadkova konci riadkom "250 ..." (bez pomlcky)
+async fn smtp_reply(reader: &mut BufReader<TcpStream>, expect: u16) -> Result<(), String> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await.map_err(|e| e.to_string())? == 0 {
+            return Err("SMTP server zavrel spojenie".into());
+        }
+        let code: u16 = line.get(..3).and_then(|c| c.parse().ok()).ok_or_else(|| format!("SMTP: chybná odpoveď '{}'", line.trim()))?;
+        if line.as_bytes().get(3) == Some(&b'-') {
+            continue;
+        }
+        return if code == expect { Ok(()) } else { Err(format!("SMTP: {}", line.trim())) };
+    }
+}
+
+async fn smtp_cmd(reader: &mut BufReader<TcpStream>, cmd: &str, expect: u16) -> Result<(), String> {
+    reader.get_mut().write_all(format!("{}\r\n", cmd).as_bytes()).await.map_err(|e| e.to_string())?;
+    smtp_reply(reader, expect).await
+}
+
+//bez TLS a autentifikacie, pre lokalny relay
+async fn smtp(addr: &str, from: &str, to: &[&str], m: &Message) -> Result<(), String> {
+    if to.is_empty() {
+        return Err("SMTP kanál nemá adresáta (to)".into());
+    }
+    let stream = TcpStream::connect(addr).await.map_err(|e| format!("{}: {}", addr, e))?;
+    let mut reader = BufReader::new(stream);
+    smtp_reply(&mut reader, 220).await?;
+    smtp_cmd(&mut reader, "EHLO monitor", 250).await?;
+    smtp_cmd(&mut reader, &format!("MAIL FROM:<{}>", from), 250).await?;
+    for rcpt in to {
+        smtp_cmd(&mut reader, &format!("RCPT TO:<{}>", rcpt), 250).await?;
+    }
+    smtp_cmd(&mut reader, "DATA", 354).await?;
+
+    let mut msg = format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+        from, to.join(", "), encode_header(&m.subject), Utc::now().to_rfc2822()
+    );
+    //riadky zacinajuce bodkou sa zdvoja (dot-stuffing)
+    for line in m.text.lines() {
+        if line.starts_with('.') {
+            msg.push('.');
+        }
+        msg.push_str(line);
+        msg.push_str("\r\n");
+    }
+    msg.push_str(".\r\n");
+    reader.get_mut().write_all(msg.as_bytes()).await.map_err(|e| e.to_string())?;
+    smtp_reply(&mut reader, 250).await?;
+    smtp_cmd(&mut reader, "QUIT", 221).await.ok();
+    Ok(())
+}
+
+//hlavicka s diakritikou ako RFC 2047 encoded-words (=?UTF-8?B?...?=), kazde najviac 75 znakov
+fn encode_header(value: &str) -> String {
+    //koniec riadku by v hlavicke zacal novu hlavicku
+    let value: String = value.chars().map(|c| if c.is_control() { ' ' } else { c }).collect();
+    if value.is_ascii() {
+        return value;
+    }
+    //45 bajtov = 60 znakov base64, so "=?UTF-8?B?" a "?=" 72; znak sa nedeli medzi slova
+    let mut words = Vec::new();
+    let mut chunk = String::new();
+    for c in value.chars() {
+        if chunk.len() + c.len_utf8() > 45 {
+            words.push(format!("=?UTF-8?B?{}?=", base64(chunk.as_bytes())));
+            chunk.clear();
+        }
+        chunk.push(c);
+    }
+    words.push(format!("=?UTF-8?B?{}?=", base64(chunk.as_bytes())));
+    words.join("\r\n ")
+}
+
+fn base64(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let b = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+//prikaz dostane alert v premennych prostredia a ako JSON na stdin
+async fn exec(cmd: &str, m: &Message) -> Result<(), String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .envs(m.env.iter().map(|(k, v)| (*k, v)))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        let body = serde_json::to_vec(&m.payload).map_err(|e| e.to_string())?;
+        //prikaz nemusi stdin citat, zlomena rura nie je chyba
+        stdin.write_all(&body).await.ok();
+    }
+    let out = child.wait_with_output().await.map_err(|e| e.to_string())?;
+    if out.status.success() {
+        Ok(())
+    } else {
+        let err = String::from_utf8_lossy(&out.stderr);
+        Err(format!("{} {}", out.status, err.lines().next().unwrap_or("")).trim().to_string())
+    }
+}
+
+//vsetky pokusy sa zapisu do deliveries
+pub async fn deliver_with_retry(channel: Channel, m: Message, policy: RetryPolicy) -> Result<(), String> {
+    let mut conn = open_connection().ok();
+    let mut last = Err("bez pokusu".to_string());
+    for attempt in 1..=policy.attempts {
+        last = match timeout(ATTEMPT_TIMEOUT, deliver(&channel, &m)).await {
+            Ok(r) => r,
+            Err(_) => Err("timeout".into()),
+        };
+        if let Some(conn) = conn.as_mut() {
+            for (alert, state) in &m.alerts {
+                record_delivery(conn, *alert, channel.id, state, attempt as i32, last.as_ref().err().map(String::as_str)).ok();
+            }
+        }
+        if last.is_ok() || attempt == policy.attempts {
+            break;
+        }
+        tokio::time::sleep(policy.delay(attempt)).await;
+    }
+    last
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GroupBy {
+    #[default]
+    None,
+    Server,
+    Tag,
+    Rule,
+}
+
+impl GroupBy {
+    pub fn parse(s: &str) -> Option<GroupBy> {
+        match s.trim().to_lowercase().as_str() {
+            "" | "none" => Some(GroupBy::None),
+            "server" => Some(GroupBy::Server),
+            "tag" => Some(GroupBy::Tag),
+            "rule" => Some(GroupBy::Rule),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GroupBy::None => "none",
+            GroupBy::Server => "server",
+            GroupBy::Tag => "tag",
+            GroupBy::Rule => "rule",
+        }
+    }
+}
+
+//notifikacie jedneho kanala a skupiny cakajuce na koniec okna
+struct Batch {
+    channel: Channel,
+    opened: Instant,
+    items: Vec<DigestItem>,
+}
+
+impl Batch {
+    //duplicita = rovnake pravidlo, server a stav, plati najnovsia
+    fn push(&mut self, n: Notification) {
+        let same = self.items.iter_mut().find(|i| {
+            i.notification.rule == n.rule && i.notification.server_id == n.server_id && i.notification.state == n.state
+        });
+        match same {
+            Some(item) => {
+                item.count += 1;
+                let level = item.notification.level.max(n.level);
+                item.notification = Notification { level, ..n };
+            }
+            None => self.items.push(DigestItem { notification: n, count: 1 }),
+        }
+    }
+}
+
+//posiela prechody alertov do kanalov pravidla na pozadi
+#[derive(Default)]
+pub struct Notifier {
+    channels: Vec<Channel>,
+    policy: RetryPolicy,
+    templates: Templates,
+    group_by: GroupBy,
+    window: Duration,
+    tags: HashMap<i32, Vec<String>>,
+    batches: HashMap<(i32, String), Batch>,
+    //bezace dorucenia, aby sa na ne dalo pri ukonceni pockat
+    pending: JoinSet<Result<(), String>>,
+    loaded_at: Option<Instant>,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        Notifier::default()
+    }
+
+    pub fn reload(&mut self, conn: &mut SqliteConnection) {
+        self.channels = get_channels(conn).unwrap_or_default();
+        self.policy = RetryPolicy::load(conn);
+        self.templates = Templates::load(conn);
+        self.group_by = get_setting(conn, "notify_group_by").ok().flatten().and_then(|g| GroupBy::parse(&g)).unwrap_or_default();
+        self.window = Duration::from_secs(setting_or(conn, "notify_group_window", 30));
+        self.tags = get_tags(conn).unwrap_or_default();
+        self.loaded_at = Some(Instant::now());
+    }
+
+    //kanaly pravidla, prazdny zoznam = vsetky
+    pub fn channels_for(&self, names: &str) -> Vec<Channel> {
+        let wanted: Vec<&str> = names.split(',').map(str::trim).filter(|n| !n.is_empty()).collect();
+        self.channels.iter().filter(|c| wanted.is_empty() || wanted.contains(&c.name.as_str())).cloned().collect()
+    }
+
+    //potlaceny firing sa neohlasi ani pri vyrieseni; priznak je v databaze, prezije restart
+    pub fn mute(&mut self, conn: &mut SqliteConnection, t: &AlertTransition) {
+        match t.state {
+            AlertState::Firing => { set_alert_muted(conn, t.alert_id, true).ok(); }
+            AlertState::Resolved => { set_alert_muted(conn, t.alert_id, false).ok(); }
+            AlertState::Pending => {}
+        }
+    }
+
+    //pending sa nehlasi, iba firing a resolved
+    pub fn notify(&mut self, conn: &mut SqliteConnection, t: &AlertTransition) {
+        if t.state == AlertState::Pending {
+            return;
+        }
+        if t.state == AlertState::Resolved && get_alert(conn, t.alert_id).ok().flatten().is_some_and(|a| a.muted) {
+            set_alert_muted(conn, t.alert_id, false).ok();
+            return;
+        }
+        self.dispatch(conn, Notification::from(t), &t.rule.channels);
+    }
+
+    //eskalacia ide do kanalov svojej urovne, nie pravidla
+    pub fn escalate(&mut self, conn: &mut SqliteConnection, e: &Escalation) {
+        let n = Notification { level: e.level, ..Notification::from(&e.transition) };
+        self.dispatch(conn, n, &e.channels);
+    }
+
+    //flapping sa hlasi do vsetkych kanalov, iba zaciatok a koniec
+    pub fn notify_flap(&mut self, conn: &mut SqliteConnection, server: &Server, change: FlapChange) {
+        let (state, pct) = match change {
+            FlapChange::Started(pct) => ("flapping", pct),
+            FlapChange::Stopped(pct) => ("stable", pct),
+        };
+        let n = Notification {
+            alert_id: 0,
+            state: state.into(),
+            rule: "flapping".into(),
+            expr: format!("zmeny stavu {:.0}%", pct),
+            severity: "warning".into(),
+            server: server.name.clone(),
+            server_id: server.id,
+            value: pct,
+            timestamp: Utc::now(),
+            level: 0,
+            server_info: Some(server.clone()),
+            metrics: None,
+        };
+        self.dispatch(conn, n, "");
+    }
+
+    //kluc skupiny, None = posiela sa hned
+    fn group_key(&self, n: &Notification) -> Option<String> {
+        if self.window.is_zero() {
+            return None;
+        }
+        match self.group_by {
+            GroupBy::None => None,
+            GroupBy::Server => Some(n.server.clone()),
+            GroupBy::Rule => Some(n.rule.clone()),
+            GroupBy::Tag => {
+                let tags = self.tags.get(&n.server_id);
+                Some(tags.and_then(|t| t.iter().min()).cloned().unwrap_or_else(|| "bez tagu".into()))
+            }
+        }
+    }
+
+    fn dispatch(&mut self, conn: &mut SqliteConnection, n: Notification, channels: &str) {
+        if self.loaded_at.is_none_or(|at| at.elapsed() >= RELOAD_EVERY) {
+            self.reload(conn);
+        }
+        let key = self.group_key(&n);
+        for channel in self.channels_for(channels) {
+            match &key {
+                Some(key) => {
+                    let batch = self.batches.entry((channel.id, key.clone())).or_insert_with(|| Batch { channel, opened: Instant::now(), items: Vec::new() });
+                    batch.push(n.clone());
+                }
+                None => {
+                    self.pending.spawn(deliver_with_retry(channel, self.templates.single(&n), self.policy));
+                }
+            }
+        }
+        self.flush(false);
+    }
+
+    //odosle skupiny, ktorym skoncilo okno (all = vsetky, napr. pri ukonceni)
+    pub fn flush(&mut self, all: bool) {
+        let due: Vec<(i32, String)> = self.batches.iter().filter(|(_, b)| all || b.opened.elapsed() >= self.window).map(|(k, _)| k.clone()).collect();
+        for k in due {
+            let Some(batch) = self.batches.remove(&k) else { continue };
+            let message = match batch.items.as_slice() {
+                [only] => self.templates.single(&only.notification),
+                _ => self.templates.digest(&Digest { group: self.group_by.as_str().into(), key: k.1, alerts: batch.items }),
+            };
+            self.pending.spawn(deliver_with_retry(batch.channel, message, self.policy));
+        }
+        while self.pending.try_join_next().is_some() {}
+    }
+
+    //odosle vsetky skupiny a pocka na dorucenia najviac `wait`, vracia pocet nedokoncenych
+    pub async fn shutdown(&mut self, wait: Duration) -> usize {
+        self.flush(true);
+        let pending = &mut self.pending;
+        timeout(wait, async { while pending.join_next().await.is_some() {} }).await.ok();
+        self.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::{test_connection, test_server};
+    use crate::db::{add_alert_rule, add_channel, get_alert_rules, get_deliveries, open_alert};
+    use crate::http::tests::serve;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    fn message(subject: &str, text: &str, alert_id: i32) -> Message {
+        Message { subject: subject.into(), text: text.into(), payload: json!({ "subject": subject }), env: Vec::new(), alerts: vec![(alert_id, "firing".into())] }
+    }
+
+    fn channel(conn: &mut SqliteConnection, kind: &str, target: &str, config: &str) -> Channel {
+        static NEXT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+        let name = format!("test-{}-{}", kind, NEXT.fetch_add(1, std::sync::atomic::Ordering::SeqCst));
+        add_channel(conn, &name, kind, target, config).unwrap();
+        get_channels(conn).unwrap().into_iter().find(|c| c.name == name).unwrap()
+    }
+
+    //deliveries odkazuju na skutocny alert
+    fn alert(conn: &mut SqliteConnection) -> i32 {
+        let server = test_server(conn, "notify", 80);
+        let name = format!("notify-{}", server.id);
+        add_alert_rule(conn, &name, &server.name, "cpu_usage > 1", "", "warning").unwrap();
+        let rule = get_alert_rules(conn).unwrap().into_iter().find(|r| r.name == name).unwrap();
+        open_alert(conn, rule.id, server.id, 2.0, Utc::now().naive_utc()).unwrap().id
+    }
+
+    fn quick(attempts: u32) -> RetryPolicy {
+        RetryPolicy { attempts, backoff: Duration::from_millis(10), max_backoff: Duration::from_millis(50) }
+    }
+
+    //pokusy o dorucenie daneho kanala, od prveho
+    fn attempts(conn: &mut SqliteConnection, channel: &Channel) -> Vec<(i32, bool)> {
+        let mut rows: Vec<(i32, bool)> = get_deliveries(conn, 1000).unwrap().into_iter().filter(|d| d.channel_id == channel.id).map(|d| (d.attempt, d.ok)).collect();
+        rows.reverse();
+        rows
+    }
+
+    #[tokio::test]
+    async fn webhook_retries_until_success() {
+        let mut conn = test_connection();
+        let (url, server) = serve(vec![
+            "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\n\r\n".into(),
+            "HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".into(),
+        ]).await;
+        let ch = channel(&mut conn, "webhook", &format!("{}/hook", url), "{}");
+        deliver_with_retry(ch.clone(), message("cpu-high", "telo", alert(&mut conn)), quick(3)).await.unwrap();
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[1].starts_with("POST /hook HTTP/1.1\r\n"));
+        assert!(requests[1].ends_with(r#"{"subject":"cpu-high"}"#));
+        assert_eq!(attempts(&mut conn, &ch), [(1, false), (2, true)]);
+    }
+
+    #[tokio::test]
+    async fn shutdown_sends_open_groups() {
+        let mut conn = test_connection();
+        let (url, server) = serve(vec!["HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n".into()]).await;
+        let ch = channel(&mut conn, "webhook", &url, "{}");
+        let mut notifier = Notifier { channels: vec![ch.clone()], policy: quick(1), group_by: GroupBy::Server, window: Duration::from_secs(600), loaded_at: Some(Instant::now()), ..Notifier::default() };
+        let s = test_server(&mut conn, "shutdown", 80);
+        notifier.notify_flap(&mut conn, &s, FlapChange::Started(60.0));
+        notifier.flush(false);
+        assert_eq!(notifier.batches.len(), 1);
+
+        assert_eq!(notifier.shutdown(Duration::from_secs(5)).await, 0);
+        assert!(notifier.batches.is_empty());
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn webhook_gives_up_after_attempts() {
+        let mut conn = test_connection();
+        let fail = "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n".to_string();
+        let (url, _server) = serve(vec![fail.clone(), fail]).await;
+        let ch = channel(&mut conn, "webhook", &url, "{}");
+        let err = deliver_with_retry(ch.clone(), message("x", "", alert(&mut conn)), quick(2)).await.unwrap_err();
+        assert_eq!(err, "HTTP 500");
+        assert_eq!(attempts(&mut conn, &ch), [(1, false), (2, false)]);
+    }
+
+    //SMTP server, ktory odmietne adresata `reject`; vrati prijatu spravu (DATA)
+    async fn fake_smtp(reject: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = tokio::spawn(async move {
+            let (sock, _) = listener.accept().await.unwrap();
+            let mut reader = BufReader::new(sock);
+            reader.get_mut().write_all(b"220 fake ESMTP\r\n").await.unwrap();
+            let mut data = String::new();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).await.unwrap() == 0 {
+                    break;
+                }
+                let reply: &[u8] = match line.trim_end() {
+                    l if l.starts_with("EHLO") => b"250-fake\r\n250 8BITMIME\r\n",
+                    l if l.starts_with("RCPT") && l.contains(reject) => b"550 no such user\r\n",
+                    l if l.starts_with("MAIL") || l.starts_with("RCPT") => b"250 OK\r\n",
+                    "DATA" => {
+                        reader.get_mut().write_all(b"354 go ahead\r\n").await.unwrap();
+                        while !data.ends_with("\r\n.\r\n") {
+                            let mut b = [0u8; 1024];
+                            let n = reader.read(&mut b).await.unwrap();
+                            if n == 0 {
+                                break;
+                            }
+                            data.push_str(&String::from_utf8_lossy(&b[..n]));
+                        }
+                        b"250 queued\r\n"
+                    }
+                    "QUIT" => {
+                        reader.get_mut().write_all(b"221 bye\r\n").await.unwrap();
+                        break;
+                    }
+                    _ => b"502 unknown\r\n",
+                };
+                reader.get_mut().write_all(reply).await.unwrap();
+            }
+            data
+        });
+        (addr, handle)
+    }
+
+    #[tokio::test]
+    async fn smtp_sends_encoded_subject() {
+        let mut conn = test_connection();
+        let (addr, server) = fake_smtp("nobody@").await;
+        let ch = channel(&mut conn, "smtp", &addr, &smtp_config(Some("mon@firma.sk"), "ops@firma.sk, dev@firma.sk"));
+        deliver_with_retry(ch.clone(), message("Výpadok: web1", "riadok\n.bodka\n", alert(&mut conn)), quick(1)).await.unwrap();
+
+        let data = server.await.unwrap();
+        assert!(data.contains("From: mon@firma.sk\r\nTo: ops@firma.sk, dev@firma.sk\r\n"));
+        assert!(data.contains(&format!("Subject: =?UTF-8?B?{}?=\r\n", base64("Výpadok: web1".as_bytes()))));
+        assert!(data.ends_with("\r\n\r\nriadok\r\n..bodka\r\n.\r\n"));
+        assert_eq!(attempts(&mut conn, &ch), [(1, true)]);
+    }
+
+    #[tokio::test]
+    async fn smtp_rejected_recipient_is_error() {
+        let mut conn = test_connection();
+        let (addr, _server) = fake_smtp("nobody@").await;
+        let ch = channel(&mut conn, "smtp", &addr, &smtp_config(None, "nobody@firma.sk"));
+        let err = deliver_with_retry(ch.clone(), message("x", "y", alert(&mut conn)), quick(1)).await.unwrap_err();
+        assert!(err.contains("550"), "{}", err);
+        assert_eq!(attempts(&mut conn, &ch), [(1, false)]);
+    }
+
+    #[test]
+    fn base64_padding() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64("ľ".as_bytes()), "xL4=");
+    }
+
+    #[test]
+    fn header_encoding() {
+        assert_eq!(encode_header("ALERT cpu-high"), "ALERT cpu-high");
+        assert_eq!(encode_header("a\r\nBcc: x"), "a  Bcc: x");
+        assert_eq!(encode_header("čaj"), "=?UTF-8?B?xI1hag==?=");
+        //dlhy predmet sa rozdeli na slova, ziadne nepresiahne 75 znakov ani nerozdeli znak
+        let long = "ž".repeat(40);
+        let encoded = encode_header(&long);
+        let words: Vec<&str> = encoded.split("\r\n ").collect();
+        assert_eq!(words.len(), 2);
+        assert!(words.iter().all(|w| w.len() <= 75 && w.starts_with("=?UTF-8?B?") && w.ends_with("?=")));
+    }
+}
@@ -0,0 +1,138 @@
+//spracovanie kazdej novej vzorky: zapis do historie a dalsie kontroly
+
+use crate::alerts::{AlertEngine, AlertState, AlertTransition};
+use crate::anomaly::{AnomalyConfig, AnomalyDetector};
+use crate::db::{establish_connection, record_metrics, set_alert_cause, setting_or, SqliteConnection};
+use crate::deps::RootCauses;
+use crate::flap::{is_up, FlapChange, FlapConfig, FlapDetector};
+use crate::maintenance::Suppressions;
+use crate::notify::Notifier;
+use crate::probe::ProbeStatus;
+use crate::scheduler::Sample;
+use crate::tls::TlsLevel;
+use diesel::QueryResult;
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub struct Pipeline {
+    conn: SqliteConnection,
+    last_health: HashMap<i32, ProbeStatus>,
+    last_tls: HashMap<i32, TlsLevel>,
+    tls_warn_days: i64,
+    tls_crit_days: i64,
+    anomalies: AnomalyDetector,
+    alerts: AlertEngine,
+    notifier: Notifier,
+    suppressions: Suppressions,
+    flaps: FlapDetector,
+    causes: RootCauses,
+}
+
+impl Pipeline {
+    pub fn new() -> Result<Self, String> {
+        let mut conn = establish_connection()?;
+        let tls_warn_days = setting_or(&mut conn, "tls_warn_days", 30);
+        let tls_crit_days = setting_or(&mut conn, "tls_crit_days", 7);
+        let anomalies = AnomalyDetector::new(AnomalyConfig::load(&mut conn));
+        let flaps = FlapDetector::new(FlapConfig::load(&mut conn));
+        Ok(Pipeline { conn, last_health: HashMap::new(), last_tls: HashMap::new(), tls_warn_days, tls_crit_days, anomalies, alerts: AlertEngine::new(), notifier: Notifier::new(), suppressions: Suppressions::new(), flaps, causes: RootCauses::new() })
+    }
+
+    //vracia riadky pre log
+    pub fn process(&mut self, sample: &Sample) -> QueryResult<Vec<String>> {
+        let mut logs = Vec::new();
+        if sample.server.status != "ON" {
+            return Ok(logs);
+        }
+
+        //baseline sa uci z historie bez prave spracovanej vzorky
+        logs.extend(self.anomalies.check(&mut self.conn, &sample.server, &sample.metrics)?);
+        record_metrics(&mut self.conn, sample.server.id, &sample.metrics)?;
+        self.suppressions.refresh(&mut self.conn)?;
+
+        //pri flappingu ide jedna suhrnna notifikacia namiesto kazdej zmeny
+        if let Some(change) = self.flaps.check(&mut self.conn, &sample.server, &sample.metrics, sample.health.as_ref())? {
+            //alerty, ktore vystrelili pocas flappingu a stale horia, sa ohlasia nizsie
+            if let FlapChange::Stopped(_) = change {
+                self.alerts.recheck_muted();
+            }
+            match self.suppressions.maintenance(&sample.server) {
+                Some((w, _)) => logs.push(format!("{} [údržba #{}]", change.describe(&sample.server.name), w.id)),
+                None => {
+                    self.notifier.notify_flap(&mut self.conn, &sample.server, change);
+                    logs.push(change.describe(&sample.server.name));
+                }
+            }
+        }
+        let flapping = self.flaps.is_flapping(sample.server.id);
+
+        //ked nejde server, od ktoreho tento zavisi, jeho alerty su iba nasledok
+        self.causes.refresh(&mut self.conn)?;
+        self.causes.observe(sample.server.id, is_up(&sample.metrics, sample.health.as_ref()));
+        let cause = self.causes.cause(sample.server.id).map(str::to_string);
+
+        for t in self.alerts.evaluate(&mut self.conn, &sample.server, &sample.metrics)? {
+            if let Some(c) = &cause
+                && t.state == AlertState::Firing {
+                set_alert_cause(&mut self.conn, t.alert_id, Some(c))?;
+            }
+            let reason = if flapping { Some("flapping".to_string()) }
+                         else if let Some(c) = &cause { Some(format!("spôsobené: {}", c)) }
+                         else { self.suppressions.reason(&t) };
+            match reason {
+                Some(why) => {
+                    self.notifier.mute(&mut self.conn, &t);
+                    logs.push(format!("{} [{}]", t.describe(), why));
+                }
+                None => {
+                    self.notifier.notify(&mut self.conn, &t);
+                    logs.push(t.describe());
+                }
+            }
+        }
+        //nepotvrdene a potichu vystrelene alerty sa kontroluju najviac raz za 10s
+        let (suppressions, flaps, causes) = (&self.suppressions, &self.flaps, &self.causes);
+        let muted = |t: &AlertTransition| flaps.is_flapping(t.server.id) || causes.cause(t.server.id).is_some() || suppressions.reason(t).is_some();
+        for e in self.alerts.escalate(&mut self.conn, muted)? {
+            self.notifier.escalate(&mut self.conn, &e);
+            logs.push(e.describe());
+        }
+        //po skonceni udrzby, stisenia alebo flappingu sa ohlasia alerty, ktore stale horia
+        for t in self.alerts.unmuted(&mut self.conn, muted)? {
+            self.notifier.notify(&mut self.conn, &t);
+            logs.push(format!("{} [koniec potlačenia]", t.describe()));
+        }
+        //zoskupene notifikacie odidu po skonceni okna
+        self.notifier.flush(false);
+        if sample.timed_out && !flapping {
+            logs.push(format!("ERROR: {}: meranie prekročilo timeout {}ms", sample.server.name, sample.server.timeout_ms));
+        }
+
+        //zmena stavu sluzby (OK / iba port / nedostupna)
+        if let Some(health) = &sample.health
+            && self.last_health.get(&sample.server.id) != Some(health) {
+            let line = match health {
+                ProbeStatus::Healthy => format!("{}: služba odpovedá", sample.server.name),
+                ProbeStatus::PortOpen(why) => format!("ERROR: {}: port otvorený, služba neodpovedá ({})", sample.server.name, why),
+                ProbeStatus::Down(why) => format!("ERROR: {}: nedostupný ({})", sample.server.name, why),
+            };
+            if !flapping {
+                logs.push(line);
+            }
+            self.last_health.insert(sample.server.id, health.clone());
+        }
+
+        if let Some(tls) = &sample.tls {
+            let level = tls.level(self.tls_warn_days, self.tls_crit_days);
+            if self.last_tls.insert(sample.server.id, level) != Some(level) && level != TlsLevel::Ok {
+                logs.push(format!("ERROR: {}: certifikát vyprší o {} dní (hostname {})", sample.server.name, tls.min_days_left(), if tls.hostname_ok { "OK" } else { "nesedí" }));
+            }
+        }
+        Ok(logs)
+    }
+
+    //pri ukonceni sa odoslu aj neuzavrete skupiny notifikacii
+    pub async fn shutdown(&mut self, wait: Duration) -> usize {
+        self.notifier.shutdown(wait).await
+    }
+}
@@ -0,0 +1,432 @@
+//skutocne overenie sluzby podla protokolu, nie iba otvoreny port
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use crate::tls::{self, TlsInfo};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProbeKind {
+    Tcp,
+    Http,
+    Mongo,
+    Redis,
+    Postgres,
+    Banner,
+}
+
+impl ProbeKind {
+    //"auto" vyberie protokol podla dobre znamych portov
+    pub fn parse(name: &str, port: i32) -> Option<ProbeKind> {
+        match name.trim().to_lowercase().as_str() {
+            "auto" | "" => Some(ProbeKind::for_port(port)),
+            "tcp" => Some(ProbeKind::Tcp),
+            "http" => Some(ProbeKind::Http),
+            "mongo" | "mongodb" => Some(ProbeKind::Mongo),
+            "redis" => Some(ProbeKind::Redis),
+            "postgres" | "postgresql" => Some(ProbeKind::Postgres),
+            "banner" => Some(ProbeKind::Banner),
+            _ => None,
+        }
+    }
+
+    pub fn for_port(port: i32) -> ProbeKind {
+        match port {
+            80 | 443 | 3000 | 8000 | 8080 => ProbeKind::Http,
+            27017 => ProbeKind::Mongo,
+            6379 => ProbeKind::Redis,
+            5432 => ProbeKind::Postgres,
+            _ => ProbeKind::Banner,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ProbeStatus {
+    //nepodarilo sa ani pripojit
+    Down(String),
+    //port je otvoreny, ale sluzba neodpoveda spravne
+    PortOpen(String),
+    Healthy,
+}
+
+impl ProbeStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProbeStatus::Down(_) => "DOWN",
+            ProbeStatus::PortOpen(_) => "OPEN",
+            ProbeStatus::Healthy => "OK",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub status: ProbeStatus,
+    //cela kontrola vratane protokolu a TLS
+    pub latency_ms: i32,
+    //iba nadviazanie TCP spojenia, porovnatelne s latency::tcp_samples
+    pub connect_ms: i32,
+    pub tls: Option<TlsInfo>,
+}
+
+pub async fn probe(host: &str, port: i32, kind: ProbeKind, limit: Duration) -> ProbeResult {
+    let start = Instant::now();
+    let addr = format!("{}:{}", host, port);
+
+    let stream = match timeout(limit, TcpStream::connect(&addr)).await {
+        Ok(Ok(s)) => s,
+        Ok(Err(e)) => return ProbeResult { status: ProbeStatus::Down(e.to_string()), latency_ms: -1, connect_ms: -1, tls: None },
+        Err(_) => return ProbeResult { status: ProbeStatus::Down("timeout".into()), latency_ms: -1, connect_ms: -1, tls: None },
+    };
+
+    let connect_ms = (start.elapsed().as_millis() as i32).max(1);
+    let deadline = start + limit;
+    let (status, tls) = match secured(stream, host, port, kind, deadline).await {
+        (Ok(()), tls) => (ProbeStatus::Healthy, tls),
+        (Err(reason), tls) => (ProbeStatus::PortOpen(reason), tls),
+    };
+    ProbeResult { status, latency_ms: (start.elapsed().as_millis() as i32).max(1), connect_ms, tls }
+}
+
+//na 443 ide protokol cez TLS a zaroven sa precita certifikat
+async fn secured(mut stream: TcpStream, host: &str, port: i32, kind: ProbeKind, deadline: Instant) -> (Result<(), String>, Option<TlsInfo>) {
+    let left = || deadline.saturating_duration_since(Instant::now());
+    if port != 443 {
+        return (timeout(left(), handshake(&mut stream, host, kind)).await.unwrap_or_else(|_| Err("bez odpovede".into())), None);
+    }
+    match timeout(left(), tls::handshake(stream, host)).await {
+        Ok(Ok((mut tls_stream, info))) => {
+            let result = timeout(left(), handshake(&mut tls_stream, host, kind)).await;
+            (result.unwrap_or_else(|_| Err("bez odpovede".into())), Some(info))
+        }
+        Ok(Err(e)) => (Err(e), None),
+        Err(_) => (Err("TLS: bez odpovede".into()), None),
+    }
+}
+
+async fn handshake<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, host: &str, kind: ProbeKind) -> Result<(), String> {
+    match kind {
+        ProbeKind::Tcp => Ok(()),
+        ProbeKind::Http => http_head(stream, host).await,
+        ProbeKind::Mongo => mongo_hello(stream).await,
+        ProbeKind::Redis => redis_ping(stream).await,
+        ProbeKind::Postgres => postgres_startup(stream).await,
+        ProbeKind::Banner => banner(stream).await,
+    }
+}
+
+async fn read_some<S: AsyncRead + Unpin>(stream: &mut S, buf: &mut [u8]) -> Result<usize, String> {
+    match stream.read(buf).await {
+        Ok(0) => Err("spojenie zatvorené".into()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+async fn http_head<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S, host: &str) -> Result<(), String> {
+    let req = format!("HEAD / HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nUser-Agent: monitor\r\n\r\n", host);
+    stream.write_all(req.as_bytes()).await.map_err(|e| e.to_string())?;
+
+    let mut buf = [0u8; 256];
+    let n = read_some(stream, &mut buf).await?;
+    let line = String::from_utf8_lossy(&buf[..n]);
+    let line = line.lines().next().unwrap_or("");
+    if !line.starts_with("HTTP/") {
+        return Err("nie je HTTP odpoveď".into());
+    }
+    match line.split_whitespace().nth(1).and_then(|c| c.parse::<u16>().ok()) {
+        Some(code) if code < 500 => Ok(()),
+        Some(code) => Err(format!("HTTP {}", code)),
+        None => Err("chybný HTTP status".into()),
+    }
+}
+
+//OP_MSG s BSON dokumentom { hello: 1, $db: "admin" }
+fn mongo_hello_message() -> Vec<u8> {
+    let mut doc = Vec::new();
+    doc.push(0x10);
+    doc.extend_from_slice(b"hello\0");
+    doc.extend_from_slice(&1i32.to_le_bytes());
+    doc.push(0x02);
+    doc.extend_from_slice(b"$db\0");
+    doc.extend_from_slice(&6i32.to_le_bytes());
+    doc.extend_from_slice(b"admin\0");
+    doc.push(0x00);
+    let mut bson = ((doc.len() + 4) as i32).to_le_bytes().to_vec();
+    bson.extend_from_slice(&doc);
+
+    let mut body = 0u32.to_le_bytes().to_vec();
+    body.push(0x00);
+    body.extend_from_slice(&bson);
+
+    let mut msg = ((body.len() + 16) as i32).to_le_bytes().to_vec();
+    msg.extend_from_slice(&1i32.to_le_bytes());
+    msg.extend_from_slice(&0i32.to_le_bytes());
+    msg.extend_from_slice(&2013i32.to_le_bytes());
+    msg.extend_from_slice(&body);
+    msg
+}
+
+//hodnota "ok" z BSON dokumentu na najvyssej urovni
+fn bson_ok(doc: &[u8]) -> Option<f64> {
+    let mut i = 4;
+    while i < doc.len() && doc[i] != 0 {
+        let tag = doc[i];
+        let name_end = i + 1 + doc[i + 1..].iter().position(|b| *b == 0)?;
+        let name = &doc[i + 1..name_end];
+        let v = name_end + 1;
+        let (value, size) = match tag {
+            0x01 => (Some(f64::from_le_bytes(doc.get(v..v + 8)?.try_into().ok()?)), 8),
+            0x10 => (Some(i32::from_le_bytes(doc.get(v..v + 4)?.try_into().ok()?) as f64), 4),
+            0x12 => (Some(i64::from_le_bytes(doc.get(v..v + 8)?.try_into().ok()?) as f64), 8),
+            0x08 => (Some(*doc.get(v)? as f64), 1),
+            0x02 | 0x0D | 0x0E => (None, 4 + i32::from_le_bytes(doc.get(v..v + 4)?.try_into().ok()?) as usize),
+            0x03 | 0x04 => (None, i32::from_le_bytes(doc.get(v..v + 4)?.try_into().ok()?) as usize),
+            0x05 => (None, 5 + i32::from_le_bytes(doc.get(v..v + 4)?.try_into().ok()?) as usize),
+            0x07 => (None, 12),
+            0x09 | 0x11 => (None, 8),
+            0x0A | 0x06 | 0x7F | 0xFF => (None, 0),
+            0x13 => (None, 16),
+            _ => return None,
+        };
+        if name == b"ok" {
+            return value;
+        }
+        i = v + size;
+    }
+    None
+}
+
+async fn mongo_hello<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<(), String> {
+    stream.write_all(&mongo_hello_message()).await.map_err(|e| e.to_string())?;
+
+    let mut header = [0u8; 16];
+    stream.read_exact(&mut header).await.map_err(|e| e.to_string())?;
+    let len = i32::from_le_bytes(header[0..4].try_into().unwrap());
+    let op = i32::from_le_bytes(header[12..16].try_into().unwrap());
+    if op != 2013 || !(21..=16 * 1024 * 1024).contains(&len) {
+        return Err("nie je MongoDB odpoveď".into());
+    }
+
+    let mut body = vec![0u8; len as usize - 16];
+    stream.read_exact(&mut body).await.map_err(|e| e.to_string())?;
+    //flagBits (4) + typ sekcie (1) + BSON
+    match bson_ok(&body[5..]) {
+        Some(1.0) => Ok(()),
+        Some(_) => Err("hello: ok != 1".into()),
+        None => Err("hello: chýba ok".into()),
+    }
+}
+
+async fn redis_ping<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<(), String> {
+    stream.write_all(b"*1\r\n$4\r\nPING\r\n").await.map_err(|e| e.to_string())?;
+    let mut buf = [0u8; 128];
+    let n = read_some(stream, &mut buf).await?;
+    let reply = String::from_utf8_lossy(&buf[..n]);
+    //NOAUTH znamena, ze Redis odpoveda, iba chce heslo
+    if reply.starts_with("+PONG") || reply.starts_with("-NOAUTH") {
+        Ok(())
+    } else {
+        Err(format!("Redis: {}", reply.lines().next().unwrap_or("").trim()))
+    }
+}
+
+async fn postgres_startup<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<(), String> {
+    let mut params = Vec::new();
+    params.extend_from_slice(&196608i32.to_be_bytes());
+    params.extend_from_slice(b"user\0monitor\0database\0postgres\0\0");
+    let mut msg = ((params.len() + 4) as i32).to_be_bytes().to_vec();
+    msg.extend_from_slice(&params);
+    stream.write_all(&msg).await.map_err(|e| e.to_string())?;
+
+    let mut buf = [0u8; 1];
+    read_some(stream, &mut buf).await?;
+    //R = poziadavka na autentifikaciu, E = chyba (napr. neznamy pouzivatel), oboje je PostgreSQL
+    match buf[0] {
+        b'R' | b'E' => Ok(()),
+        other => Err(format!("nečakaná odpoveď 0x{:02x}", other)),
+    }
+}
+
+async fn banner<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut S) -> Result<(), String> {
+    let mut buf = [0u8; 256];
+    match timeout(Duration::from_secs(1), stream.read(&mut buf)).await {
+        Ok(Ok(n)) if n > 0 => Ok(()),
+        Ok(Ok(_)) => Err("spojenie zatvorené bez banneru".into()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err("bez banneru".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    //falosna sluzba: (volitelne) pocka na poziadavku, posle odpoved a necha spojenie otvorene
+    async fn fake(reply: Vec<u8>, wait_for_request: bool) -> i32 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port() as i32;
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            if wait_for_request {
+                let mut buf = [0u8; 512];
+                let _ = sock.read(&mut buf).await;
+            }
+            if !reply.is_empty() {
+                sock.write_all(&reply).await.unwrap();
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+        port
+    }
+
+    async fn run(kind: ProbeKind, reply: &[u8], wait_for_request: bool) -> ProbeStatus {
+        let port = fake(reply.to_vec(), wait_for_request).await;
+        probe("127.0.0.1", port, kind, Duration::from_secs(3)).await.status
+    }
+
+    fn mongo_reply(ok: f64, op: i32) -> Vec<u8> {
+        let mut doc = vec![0x01];
+        doc.extend_from_slice(b"ok\0");
+        doc.extend_from_slice(&ok.to_le_bytes());
+        doc.push(0x00);
+        let mut bson = ((doc.len() + 4) as i32).to_le_bytes().to_vec();
+        bson.extend_from_slice(&doc);
+        let mut body = 0u32.to_le_bytes().to_vec();
+        body.push(0x00);
+        body.extend_from_slice(&bson);
+        let mut msg = ((body.len() + 16) as i32).to_le_bytes().to_vec();
+        msg.extend_from_slice(&7i32.to_le_bytes());
+        msg.extend_from_slice(&1i32.to_le_bytes());
+        msg.extend_from_slice(&op.to_le_bytes());
+        msg.extend_from_slice(&body);
+        msg
+    }
+
+    fn open_reason(status: ProbeStatus) -> String {
+        match status {
+            ProbeStatus::PortOpen(why) => why,
+            other => panic!("čakal sa PortOpen, prišlo {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parse_kind() {
+        assert_eq!(ProbeKind::parse("auto", 6379), Some(ProbeKind::Redis));
+        assert_eq!(ProbeKind::parse("", 8080), Some(ProbeKind::Http));
+        assert_eq!(ProbeKind::parse("PostgreSQL", 1), Some(ProbeKind::Postgres));
+        assert_eq!(ProbeKind::parse("auto", 2222), Some(ProbeKind::Banner));
+        assert_eq!(ProbeKind::parse("ftp", 21), None);
+    }
+
+    #[test]
+    fn bson_ok_types() {
+        //hlavicka 16 B + flagBits 4 B + typ sekcie 1 B
+        assert_eq!(bson_ok(&mongo_reply(1.0, 2013)[21..]), Some(1.0));
+        //{ msg: "x", ok: 1 (int32) }
+        let mut doc = vec![0x02];
+        doc.extend_from_slice(b"msg\0");
+        doc.extend_from_slice(&2i32.to_le_bytes());
+        doc.extend_from_slice(b"x\0");
+        doc.push(0x10);
+        doc.extend_from_slice(b"ok\0");
+        doc.extend_from_slice(&1i32.to_le_bytes());
+        doc.push(0x00);
+        let mut bson = ((doc.len() + 4) as i32).to_le_bytes().to_vec();
+        bson.extend_from_slice(&doc);
+        assert_eq!(bson_ok(&bson), Some(1.0));
+        assert_eq!(bson_ok(&[5, 0, 0, 0, 0]), None);
+        assert_eq!(bson_ok(&[9, 0, 0, 0, 0x42, b'o', b'k', 0, 0]), None);
+    }
+
+    #[tokio::test]
+    async fn closed_port_is_down() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port() as i32;
+        drop(listener);
+        let result = probe("127.0.0.1", port, ProbeKind::Http, Duration::from_secs(1)).await;
+        assert!(matches!(result.status, ProbeStatus::Down(_)));
+        assert_eq!(result.latency_ms, -1);
+        assert_eq!(result.connect_ms, -1);
+    }
+
+    #[tokio::test]
+    async fn connect_time_excludes_slow_protocol() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port() as i32;
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 512];
+            let _ = sock.read(&mut buf).await;
+            tokio::time::sleep(Duration::from_millis(300)).await;
+            sock.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").await.unwrap();
+        });
+        let result = probe("127.0.0.1", port, ProbeKind::Http, Duration::from_secs(3)).await;
+        assert_eq!(result.status, ProbeStatus::Healthy);
+        assert!(result.latency_ms >= 300, "{}", result.latency_ms);
+        assert!(result.connect_ms >= 1 && result.connect_ms < 300, "{}", result.connect_ms);
+    }
+
+    #[tokio::test]
+    async fn tcp_only_connects() {
+        assert_eq!(run(ProbeKind::Tcp, b"", false).await, ProbeStatus::Healthy);
+    }
+
+    #[tokio::test]
+    async fn http_head() {
+        assert_eq!(run(ProbeKind::Http, b"HTTP/1.1 404 Not Found\r\n\r\n", true).await, ProbeStatus::Healthy);
+        assert_eq!(open_reason(run(ProbeKind::Http, b"HTTP/1.1 503 Service Unavailable\r\n\r\n", true).await), "HTTP 503");
+        assert_eq!(open_reason(run(ProbeKind::Http, b"SSH-2.0-OpenSSH_9.6\r\n", true).await), "nie je HTTP odpoveď");
+    }
+
+    #[tokio::test]
+    async fn mongo_hello() {
+        assert_eq!(run(ProbeKind::Mongo, &mongo_reply(1.0, 2013), true).await, ProbeStatus::Healthy);
+        assert_eq!(open_reason(run(ProbeKind::Mongo, &mongo_reply(0.0, 2013), true).await), "hello: ok != 1");
+        assert_eq!(open_reason(run(ProbeKind::Mongo, &mongo_reply(1.0, 1), true).await), "nie je MongoDB odpoveď");
+    }
+
+    #[test]
+    fn mongo_request_is_op_msg() {
+        let msg = mongo_hello_message();
+        assert_eq!(i32::from_le_bytes(msg[0..4].try_into().unwrap()) as usize, msg.len());
+        assert_eq!(i32::from_le_bytes(msg[12..16].try_into().unwrap()), 2013);
+        assert!(msg.windows(6).any(|w| w == b"hello\0"));
+    }
+
+    #[tokio::test]
+    async fn redis_ping() {
+        assert_eq!(run(ProbeKind::Redis, b"+PONG\r\n", true).await, ProbeStatus::Healthy);
+        assert_eq!(run(ProbeKind::Redis, b"-NOAUTH Authentication required.\r\n", true).await, ProbeStatus::Healthy);
+        assert_eq!(open_reason(run(ProbeKind::Redis, b"-ERR unknown command\r\n", true).await), "Redis: -ERR unknown command");
+    }
+
+    #[tokio::test]
+    async fn postgres_startup() {
+        assert_eq!(run(ProbeKind::Postgres, b"R\0\0\0\x08\0\0\0\x03", true).await, ProbeStatus::Healthy);
+        assert_eq!(run(ProbeKind::Postgres, b"E\0\0\0\x10SFATAL\0\0", true).await, ProbeStatus::Healthy);
+        assert_eq!(open_reason(run(ProbeKind::Postgres, b"HTTP/1.1 400 Bad Request\r\n\r\n", true).await), "nečakaná odpoveď 0x48");
+    }
+
+    #[tokio::test]
+    async fn banner_grab() {
+        assert_eq!(run(ProbeKind::Banner, b"220 mail ready\r\n", false).await, ProbeStatus::Healthy);
+        assert_eq!(open_reason(run(ProbeKind::Banner, b"", false).await), "bez banneru");
+    }
+
+    #[tokio::test]
+    async fn closed_without_reply() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port() as i32;
+        tokio::spawn(async move {
+            let (sock, _) = listener.accept().await.unwrap();
+            drop(sock);
+        });
+        let status = probe("127.0.0.1", port, ProbeKind::Redis, Duration::from_secs(2)).await.status;
+        assert!(matches!(status, ProbeStatus::PortOpen(_)), "{:?}", status);
+    }
+}
@@ -0,0 +1,264 @@
+//skutocne metriky lokalneho stroja z /proc
+
+use crate::models::Metrics;
+use std::collections::HashMap;
+use std::fs;
+use std::net::{SocketAddr, TcpStream};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+//pocitadla z predosleho merania, rychlosti sa pocitaju z rozdielu
+//kazdy server ma vlastne, inak by servery merane hned po sebe videli takmer nulovy rozdiel
+#[derive(Default)]
+pub struct LocalCollector {
+    last: HashMap<i32, (Instant, Counters)>,
+}
+
+#[derive(Default, Clone, Copy)]
+struct Counters {
+    cpu_busy: u64,
+    cpu_total: u64,
+    disk_sectors: u64,
+    net_rx: u64,
+    net_tx: u64,
+}
+
+impl LocalCollector {
+    pub fn new() -> Self {
+        LocalCollector::default()
+    }
+
+    //metriky bez ohladu na stav servera (agent meria iba jeden stroj)
+    pub fn collect(&mut self) -> Metrics {
+        self.collect_server(0)
+    }
+
+    //citanie /proc blokuje, planovac to vola cez spawn_blocking
+    pub fn collect_server(&mut self, server_id: i32) -> Metrics {
+        let now = Instant::now();
+        let cur = read_counters();
+
+        let mut m = Metrics {
+            ram_usage: read_ram_used_gb().unwrap_or(0.0),
+            disk_usage: root_disk_usage().unwrap_or(0.0),
+            load_avg: read_load_avg().unwrap_or(0.0),
+            connections: count_established(),
+            ..Metrics::default()
+        };
+
+        if let Some((then, prev)) = self.last.insert(server_id, (now, cur)) {
+            apply_delta(&mut m, prev, cur, now.duration_since(then).as_secs_f32());
+        }
+        m
+    }
+}
+
+//rychlosti z rozdielu dvoch merani vzdialenych `secs` sekund
+fn apply_delta(m: &mut Metrics, prev: Counters, cur: Counters, secs: f32) {
+    let secs = secs.max(0.001);
+    let total = cur.cpu_total.saturating_sub(prev.cpu_total);
+    if total > 0 {
+        m.cpu_usage = cur.cpu_busy.saturating_sub(prev.cpu_busy) as f32 / total as f32 * 100.0;
+    }
+    //sektor = 512 B, disk_io v KB/s
+    m.disk_io = cur.disk_sectors.saturating_sub(prev.disk_sectors) as f32 * 0.5 / secs;
+    m.net_rx = cur.net_rx.saturating_sub(prev.net_rx) as f32 / secs;
+    m.net_tx = cur.net_tx.saturating_sub(prev.net_tx) as f32 / secs;
+}
+
+pub fn local_connect_ms(port: i32) -> i32 {
+    if port <= 0 || port > u16::MAX as i32 {
+        return -1;
+    }
+    let addr = SocketAddr::from(([127, 0, 0, 1], port as u16));
+    let start = Instant::now();
+    match TcpStream::connect_timeout(&addr, Duration::from_millis(500)) {
+        Ok(_) => (start.elapsed().as_millis() as i32).max(1),
+        Err(_) => -1,
+    }
+}
+
+fn read_counters() -> Counters {
+    let (cpu_busy, cpu_total) = parse_stat(&fs::read_to_string("/proc/stat").unwrap_or_default());
+    let disk_sectors = parse_diskstats(&fs::read_to_string("/proc/diskstats").unwrap_or_default(), |dev| Path::new("/sys/block").join(dev).exists());
+    let (net_rx, net_tx) = parse_net_dev(&fs::read_to_string("/proc/net/dev").unwrap_or_default());
+    Counters { cpu_busy, cpu_total, disk_sectors, net_rx, net_tx }
+}
+
+//(busy, total) jiffies zo suhrnneho riadku "cpu"
+fn parse_stat(stat: &str) -> (u64, u64) {
+    let Some(line) = stat.lines().find(|l| l.starts_with("cpu ")) else { return (0, 0) };
+    let vals: Vec<u64> = line.split_whitespace().skip(1).filter_map(|v| v.parse().ok()).collect();
+    //user nice system idle iowait irq softirq steal
+    let idle = vals.get(3).copied().unwrap_or(0) + vals.get(4).copied().unwrap_or(0);
+    let total = vals.iter().take(8).sum::<u64>();
+    (total.saturating_sub(idle), total)
+}
+
+//precitane + zapisane sektory; is_disk rozhoduje, ci je zariadenie cely disk
+fn parse_diskstats(disks: &str, is_disk: impl Fn(&str) -> bool) -> u64 {
+    let mut sectors = 0;
+    for line in disks.lines() {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        //iba cele disky, nie partitions ani loop zariadenia
+        if f.len() < 10 || f[2].starts_with("loop") || f[2].starts_with("ram") || !is_disk(f[2]) {
+            continue;
+        }
+        sectors += f[5].parse::<u64>().unwrap_or(0) + f[9].parse::<u64>().unwrap_or(0);
+    }
+    sectors
+}
+
+//(rx, tx) bajty vsetkych rozhrani okrem loopbacku
+fn parse_net_dev(net: &str) -> (u64, u64) {
+    let (mut rx, mut tx) = (0, 0);
+    for line in net.lines().skip(2) {
+        let Some((iface, rest)) = line.split_once(':') else { continue };
+        if iface.trim() == "lo" {
+            continue;
+        }
+        let f: Vec<u64> = rest.split_whitespace().filter_map(|v| v.parse().ok()).collect();
+        rx += f.first().copied().unwrap_or(0);
+        tx += f.get(8).copied().unwrap_or(0);
+    }
+    (rx, tx)
+}
+
+fn read_ram_used_gb() -> Option<f32> {
+    parse_meminfo(&fs::read_to_string("/proc/meminfo").ok()?)
+}
+
+//pouzita RAM v GB = MemTotal - MemAvailable
+fn parse_meminfo(info: &str) -> Option<f32> {
+    let field = |key: &str| -> Option<f32> {
+        info.lines().find(|l| l.starts_with(key))?.split_whitespace().nth(1)?.parse().ok()
+    };
+    let used_kb = field("MemTotal:")? - field("MemAvailable:")?;
+    Some(used_kb / 1024.0 / 1024.0)
+}
+
+fn read_load_avg() -> Option<f32> {
+    fs::read_to_string("/proc/loadavg").ok()?.split_whitespace().next()?.parse().ok()
+}
+
+fn count_established() -> i32 {
+    ["/proc/net/tcp", "/proc/net/tcp6"].iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .map(|t| parse_tcp(&t))
+        .sum()
+}
+
+//pocet spojeni v stave ESTABLISHED (st = 01)
+fn parse_tcp(table: &str) -> i32 {
+    table.lines().skip(1).filter(|l| l.split_whitespace().nth(3) == Some("01")).count() as i32
+}
+
+fn root_disk_usage() -> Option<f32> {
+    let mut st: libc::statvfs = unsafe { std::mem::zeroed() };
+    let rc = unsafe { libc::statvfs(c"/".as_ptr(), &mut st) };
+    if rc != 0 || st.f_blocks == 0 {
+        return None;
+    }
+    let used = st.f_blocks.saturating_sub(st.f_bfree) as f64;
+    let usable = used + st.f_bavail as f64;
+    Some((used / usable * 100.0) as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "\
+cpu  100 20 30 800 50 0 0 0 0 0
+cpu0 50 10 15 400 25 0 0 0 0 0
+intr 12345
+";
+
+    const DISKSTATS: &str = "\
+   7       0 loop0 10 0 2000 5 0 0 0 0 0 0 0
+   8       0 sda 100 0 4000 50 20 0 6000 30 0 70 80
+   8       1 sda1 90 0 3000 40 10 0 5000 20 0 50 60
+ 259       0 nvme0n1 5 0 1000 1 5 0 500 1 0 2 2
+   1       0 ram0 0 0 9999 0 0 0 9999 0 0 0 0
+";
+
+    const NET_DEV: &str = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo: 5000000     100    0    0    0     0          0         0  5000000     100    0    0    0     0       0          0
+  eth0:    1500      10    0    0    0     0          0         0     700       5    0    0    0     0       0          0
+ wlan0:     500       3    0    0    0     0          0         0     300       2    0    0    0     0       0          0
+";
+
+    const MEMINFO: &str = "\
+MemTotal:       16777216 kB
+MemFree:         1048576 kB
+MemAvailable:    4194304 kB
+Buffers:          123456 kB
+";
+
+    const TCP: &str = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1 1 0000000000000000 100 0 0 10 0
+   1: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000        0 2 1 0000000000000000 20 4 30 10 -1
+   2: 0100007F:C350 0100007F:1F90 01 00000000:00000000 00:00000000 00000000  1000        0 3 1 0000000000000000 20 4 30 10 -1
+   3: 0100007F:C351 0100007F:1F90 06 00000000:00000000 03:00000000 00000000     0        0 0 3 0000000000000000
+";
+
+    #[test]
+    fn stat_busy_and_total() {
+        //idle = idle + iowait = 850, total = 1000
+        assert_eq!(parse_stat(STAT), (150, 1000));
+        assert_eq!(parse_stat("cpu0 1 2 3 4\n"), (0, 0));
+        assert_eq!(parse_stat(""), (0, 0));
+    }
+
+    #[test]
+    fn diskstats_whole_disks_only() {
+        let whole = |dev: &str| dev == "sda" || dev == "nvme0n1";
+        //sda 4000 + 6000, nvme0n1 1000 + 500; sda1, loop0 a ram0 sa vynechaju
+        assert_eq!(parse_diskstats(DISKSTATS, whole), 11500);
+        assert_eq!(parse_diskstats(DISKSTATS, |_| true), 11500 + 8000);
+        assert_eq!(parse_diskstats("8 0 sda 1 2\n", |_| true), 0);
+    }
+
+    #[test]
+    fn net_dev_skips_loopback() {
+        assert_eq!(parse_net_dev(NET_DEV), (2000, 1000));
+        assert_eq!(parse_net_dev(""), (0, 0));
+    }
+
+    #[test]
+    fn meminfo_used_gb() {
+        assert_eq!(parse_meminfo(MEMINFO), Some(12.0));
+        assert_eq!(parse_meminfo("MemTotal: 1024 kB\n"), None);
+    }
+
+    #[test]
+    fn tcp_counts_established() {
+        assert_eq!(parse_tcp(TCP), 2);
+        assert_eq!(parse_tcp(TCP.lines().next().unwrap()), 0);
+    }
+
+    #[test]
+    fn delta_rates() {
+        let prev = Counters { cpu_busy: 150, cpu_total: 1000, disk_sectors: 10_000, net_rx: 2000, net_tx: 1000 };
+        let cur = Counters { cpu_busy: 200, cpu_total: 1200, disk_sectors: 14_000, net_rx: 6000, net_tx: 1200 };
+        let mut m = Metrics::default();
+        apply_delta(&mut m, prev, cur, 2.0);
+        assert_eq!(m.cpu_usage, 25.0);
+        //4000 sektorov * 0.5 KB za 2 s
+        assert_eq!(m.disk_io, 1000.0);
+        assert_eq!((m.net_rx, m.net_tx), (2000.0, 100.0));
+
+        //pretecenie/restart pocitadiel nesmie dat zaporne hodnoty
+        let mut m = Metrics::default();
+        apply_delta(&mut m, cur, prev, 1.0);
+        assert_eq!((m.cpu_usage, m.disk_io, m.net_rx, m.net_tx), (0.0, 0.0, 0.0, 0.0));
+
+        //bez pribudnutych jiffies ostava CPU nulove a nulovy cas sa nedeli
+        let mut m = Metrics::default();
+        apply_delta(&mut m, prev, prev, 0.0);
+        assert_eq!(m.cpu_usage, 0.0);
+    }
+}
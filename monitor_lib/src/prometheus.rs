@@ -0,0 +1,218 @@
+//zdroj metrik z Prometheus /metrics endpointu (textovy format)
+
+use crate::http;
+use crate::models::{Metrics, PrometheusSource};
+use std::time::Instant;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromSample {
+    pub name: String,
+    pub labels: Vec<(String, String)>,
+    pub value: f64,
+}
+
+pub fn parse_text(text: &str) -> Vec<PromSample> {
+    text.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<PromSample> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (name, labels, rest) = match line.find('{') {
+        Some(open) => {
+            let close = line.rfind('}')?;
+            (&line[..open], parse_labels(&line[open + 1..close]), &line[close + 1..])
+        }
+        None => {
+            let end = line.find(char::is_whitespace)?;
+            (&line[..end], Vec::new(), &line[end..])
+        }
+    };
+
+    //za hodnotou moze byt este timestamp
+    let value = match rest.split_whitespace().next()? {
+        "+Inf" => f64::INFINITY,
+        "-Inf" => f64::NEG_INFINITY,
+        v => v.parse().ok()?,
+    };
+    Some(PromSample { name: name.trim().to_string(), labels, value })
+}
+
+fn parse_labels(raw: &str) -> Vec<(String, String)> {
+    let mut labels = Vec::new();
+    let mut rest = raw;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim().trim_start_matches(',').trim().to_string();
+        let after = rest[eq + 1..].trim_start();
+        let Some(after) = after.strip_prefix('"') else { break };
+
+        let mut value = String::new();
+        let mut chars = after.char_indices();
+        let mut end = after.len();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => value.push('\n'),
+                    Some((_, other)) => value.push(other),
+                    None => {}
+                },
+                '"' => { end = i; break; }
+                _ => value.push(c),
+            }
+        }
+        labels.push((key, value));
+        rest = after.get(end + 1..).unwrap_or("");
+    }
+    labels
+}
+
+//selektor "nazov" alebo "nazov{label=\"x\"}", hodnoty zhodnych serii sa scitaju
+pub fn select(samples: &[PromSample], selector: &str) -> Option<f64> {
+    let (name, wanted) = match selector.find('{') {
+        Some(open) => (&selector[..open], parse_labels(selector[open + 1..].trim_end_matches('}'))),
+        None => (selector, Vec::new()),
+    };
+    let mut matched = samples.iter()
+        .filter(|s| s.name == name.trim())
+        .filter(|s| wanted.iter().all(|w| s.labels.contains(w)))
+        .peekable();
+    matched.peek()?;
+    Some(matched.map(|s| s.value).sum())
+}
+
+pub async fn scrape(url: &str) -> Result<Vec<PromSample>, String> {
+    let resp = http::get(url).await.map_err(|e| e.to_string())?;
+    if resp.status != 200 {
+        return Err(format!("HTTP {}", resp.status));
+    }
+    Ok(parse_text(&resp.body))
+}
+
+//countery (*_total) sa prepocitaju na % z rozdielu medzi scrapmi
+pub async fn sample(source: &PrometheusSource, prev_cpu: &mut Option<(Instant, f64)>) -> Metrics {
+    let start = Instant::now();
+    let samples = match scrape(&source.url).await {
+        Ok(s) => s,
+        Err(_) => return Metrics { response_ms: -1, ..Metrics::default() },
+    };
+    let mut m = Metrics { response_ms: (start.elapsed().as_millis() as i32).max(1), ..Metrics::default() };
+
+    if let Some(cpu) = select(&samples, &source.cpu_series) {
+        if source.cpu_series.split('{').next().unwrap_or("").ends_with("_total") {
+            if let Some((then, last)) = *prev_cpu {
+                let secs = start.duration_since(then).as_secs_f64();
+                if secs > 0.0 && cpu >= last {
+                    m.cpu_usage = ((cpu - last) / secs * 100.0) as f32;
+                }
+            }
+            *prev_cpu = Some((start, cpu));
+        } else {
+            m.cpu_usage = cpu as f32;
+        }
+    }
+
+    if let Some(ram) = select(&samples, &source.ram_series) {
+        m.ram_usage = if source.ram_series.split('{').next().unwrap_or("").ends_with("_bytes") {
+            (ram / 1024.0 / 1024.0 / 1024.0) as f32
+        } else {
+            ram as f32
+        };
+    }
+    m
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::tests::serve;
+
+    const BODY: &str = "# HELP process_cpu_seconds_total CPU\n\
+# TYPE process_cpu_seconds_total counter\n\
+process_cpu_seconds_total 12.5\n\
+process_resident_memory_bytes 2147483648 1700000000000\n\
+node_load1{cpu=\"0\",mode=\"idle\"} 0.5\n\
+node_load1{cpu=\"1\",mode=\"idle\"} 1.5\n\
+node_load1{cpu=\"1\",mode=\"user\"} 4\n\
+escaped{path=\"a\\\"b\\\\c\\n\"} +Inf\n";
+
+    fn source(url: &str, cpu: &str, ram: &str) -> PrometheusSource {
+        PrometheusSource { server_id: 1, url: url.to_string(), cpu_series: cpu.to_string(), ram_series: ram.to_string() }
+    }
+
+    fn ok(body: &str) -> String {
+        format!("HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}", body.len(), body)
+    }
+
+    #[test]
+    fn parses_text_format() {
+        let samples = parse_text(BODY);
+        assert_eq!(samples.len(), 6);
+        assert_eq!(samples[0], PromSample { name: "process_cpu_seconds_total".into(), labels: vec![], value: 12.5 });
+        assert_eq!(samples[1].value, 2147483648.0);
+        assert_eq!(samples[2].labels, vec![("cpu".to_string(), "0".to_string()), ("mode".to_string(), "idle".to_string())]);
+        assert_eq!(samples[5].labels, vec![("path".to_string(), "a\"b\\c\n".to_string())]);
+        assert_eq!(samples[5].value, f64::INFINITY);
+    }
+
+    #[test]
+    fn skips_malformed_lines() {
+        assert!(parse_text("no_value\nbroken{a=\"b\" 1\nname notanumber\n").is_empty());
+    }
+
+    #[test]
+    fn select_sums_matching_series() {
+        let samples = parse_text(BODY);
+        assert_eq!(select(&samples, "node_load1"), Some(6.0));
+        assert_eq!(select(&samples, "node_load1{mode=\"idle\"}"), Some(2.0));
+        assert_eq!(select(&samples, "node_load1{cpu=\"1\",mode=\"user\"}"), Some(4.0));
+        assert_eq!(select(&samples, "node_load1{mode=\"system\"}"), None);
+        assert_eq!(select(&samples, "missing"), None);
+    }
+
+    #[tokio::test]
+    async fn scrape_local_endpoint() {
+        let (url, server) = serve(vec![ok(BODY), "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 0\r\n\r\n".into()]).await;
+        let samples = scrape(&format!("{}/metrics", url)).await.unwrap();
+        assert_eq!(samples.len(), 6);
+        assert_eq!(scrape(&format!("{}/metrics", url)).await.unwrap_err(), "HTTP 500");
+        assert!(server.await.unwrap()[0].starts_with("GET /metrics "));
+    }
+
+    #[tokio::test]
+    async fn sample_maps_gauges_and_bytes() {
+        let (url, _server) = serve(vec![ok("cpu_percent 42\nprocess_resident_memory_bytes 3221225472\n")]).await;
+        let mut prev = None;
+        let m = sample(&source(&url, "cpu_percent", "process_resident_memory_bytes"), &mut prev).await;
+        assert_eq!(m.cpu_usage, 42.0);
+        assert_eq!(m.ram_usage, 3.0);
+        assert!(m.response_ms >= 1);
+        assert!(prev.is_none());
+    }
+
+    #[tokio::test]
+    async fn sample_counter_uses_delta() {
+        let (url, _server) = serve(vec![ok("process_cpu_seconds_total 10\n"), ok("process_cpu_seconds_total 10.5\n")]).await;
+        let src = source(&url, "process_cpu_seconds_total", "missing_bytes");
+        let mut prev = None;
+        let first = sample(&src, &mut prev).await;
+        assert_eq!(first.cpu_usage, 0.0);
+        assert_eq!(prev.map(|p| p.1), Some(10.0));
+        //o sekundu skor, aby rozdiel 0.5 s CPU za 1 s vysiel okolo 50 %
+        prev = prev.map(|(t, v)| (t - std::time::Duration::from_secs(1), v));
+        let second = sample(&src, &mut prev).await;
+        assert!((45.0..=50.0).contains(&second.cpu_usage), "{}", second.cpu_usage);
+        assert_eq!(second.ram_usage, 0.0);
+    }
+
+    #[tokio::test]
+    async fn sample_unreachable_is_down() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let m = sample(&source(&url, "a", "b"), &mut None).await;
+        assert_eq!(m.response_ms, -1);
+    }
+}
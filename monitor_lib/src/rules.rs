@@ -0,0 +1,321 @@
+//vyrazy pravidiel, napr. "cpu_usage > 90 for 5m" alebo "ram_usage / max_ram > 0.85"
+
+use crate::models::{Metrics, Server};
+use chrono::Duration;
+
+const VARIABLES: &[&str] = &[
+    "response_ms", "cpu_usage", "ram_usage", "disk_usage", "disk_io", "net_rx", "net_tx",
+    "load_avg", "connections", "rtt_min", "rtt_max", "rtt_p95", "loss_pct", "max_ram",
+];
+
+fn variable(name: &str, server: &Server, m: &Metrics) -> f64 {
+    match name {
+        "response_ms" => m.response_ms as f64,
+        "cpu_usage" => m.cpu_usage as f64,
+        "ram_usage" => m.ram_usage as f64,
+        "disk_usage" => m.disk_usage as f64,
+        "disk_io" => m.disk_io as f64,
+        "net_rx" => m.net_rx as f64,
+        "net_tx" => m.net_tx as f64,
+        "load_avg" => m.load_avg as f64,
+        "connections" => m.connections as f64,
+        "rtt_min" => m.rtt_min as f64,
+        "rtt_max" => m.rtt_max as f64,
+        "rtt_p95" => m.rtt_p95 as f64,
+        "loss_pct" => m.loss_pct as f64,
+        "max_ram" => server.max_ram as f64,
+        _ => f64::NAN,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Num(f64),
+    Ident(String),
+    Op(String),
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            tokens.push(Token::Num(text.parse().map_err(|_| format!("neplatné číslo '{}'", text))?));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            tokens.push(Token::Ident(chars[start..i].iter().collect::<String>().to_lowercase()));
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            if matches!(two.as_str(), ">=" | "<=" | "==" | "!=") {
+                tokens.push(Token::Op(two));
+                i += 2;
+            } else if "<>+-*/()".contains(c) {
+                tokens.push(Token::Op(c.to_string()));
+                i += 1;
+            } else {
+                return Err(format!("neočakávaný znak '{}'", c));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(f64),
+    Var(String),
+    Bin(Box<Expr>, char, Box<Expr>),
+}
+
+impl Expr {
+    //NaN (napr. delenie nulou) nesplni ziadne porovnanie
+    pub fn eval(&self, server: &Server, m: &Metrics) -> f64 {
+        match self {
+            Expr::Num(n) => *n,
+            Expr::Var(name) => variable(name, server, m),
+            Expr::Bin(a, op, b) => {
+                let (a, b) = (a.eval(server, m), b.eval(server, m));
+                match op {
+                    '+' => a + b,
+                    '-' => a - b,
+                    '*' => a * b,
+                    _ if b == 0.0 => f64::NAN,
+                    _ => a / b,
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+impl Comparison {
+    fn test(&self, a: f64, b: f64) -> bool {
+        match self {
+            Comparison::Gt => a > b,
+            Comparison::Ge => a >= b,
+            Comparison::Lt => a < b,
+            Comparison::Le => a <= b,
+            Comparison::Eq => a == b,
+            Comparison::Ne => a != b,
+        }
+    }
+}
+
+//ako dlho musi podmienka trvat, kym sa z pending stane firing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Hold {
+    Immediately,
+    For(Duration),
+    Samples(u32),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub lhs: Expr,
+    pub cmp: Comparison,
+    pub rhs: Expr,
+    pub hold: Hold,
+}
+
+impl Condition {
+    pub fn parse(src: &str) -> Result<Condition, String> {
+        let mut p = Parser { tokens: tokenize(src)?, pos: 0 };
+        let lhs = p.expr()?;
+        let cmp = match p.next() {
+            Some(Token::Op(op)) => match op.as_str() {
+                ">" => Comparison::Gt,
+                ">=" => Comparison::Ge,
+                "<" => Comparison::Lt,
+                "<=" => Comparison::Le,
+                "==" => Comparison::Eq,
+                "!=" => Comparison::Ne,
+                other => return Err(format!("očakávané porovnanie, nie '{}'", other)),
+            },
+            _ => return Err("chýba porovnanie (>, <, >=, ...)".into()),
+        };
+        let rhs = p.expr()?;
+        let hold = p.hold()?;
+        if p.pos < p.tokens.len() {
+            return Err(format!("nadbytočný text za pravidlom: {:?}", p.tokens[p.pos]));
+        }
+        Ok(Condition { lhs, cmp, rhs, hold })
+    }
+
+    //hodnota lavej strany a ci je podmienka splnena
+    pub fn check(&self, server: &Server, m: &Metrics) -> (f64, bool) {
+        let value = self.lhs.eval(server, m);
+        (value, self.cmp.test(value, self.rhs.eval(server, m)))
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let t = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        t
+    }
+
+    fn peek_op(&self, ops: &str) -> Option<char> {
+        match self.peek() {
+            Some(Token::Op(op)) if op.len() == 1 && ops.contains(op.as_str()) => op.chars().next(),
+            _ => None,
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut e = self.term()?;
+        while let Some(op) = self.peek_op("+-") {
+            self.pos += 1;
+            e = Expr::Bin(Box::new(e), op, Box::new(self.term()?));
+        }
+        Ok(e)
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut e = self.factor()?;
+        while let Some(op) = self.peek_op("*/") {
+            self.pos += 1;
+            e = Expr::Bin(Box::new(e), op, Box::new(self.factor()?));
+        }
+        Ok(e)
+    }
+
+    fn factor(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Num(n)) => Ok(Expr::Num(n)),
+            Some(Token::Ident(name)) if VARIABLES.contains(&name.as_str()) => Ok(Expr::Var(name)),
+            Some(Token::Ident(name)) => Err(format!("neznáma metrika '{}' (povolené: {})", name, VARIABLES.join(", "))),
+            Some(Token::Op(op)) if op == "(" => {
+                let e = self.expr()?;
+                match self.next() {
+                    Some(Token::Op(op)) if op == ")" => Ok(e),
+                    _ => Err("chýba ')'".into()),
+                }
+            }
+            Some(Token::Op(op)) if op == "-" => Ok(Expr::Bin(Box::new(Expr::Num(0.0)), '-', Box::new(self.factor()?))),
+            Some(t) => Err(format!("neočakávané {:?}", t)),
+            None => Err("neúplný výraz".into()),
+        }
+    }
+
+    //"for 5m", "for 30s", "for 3 samples"
+    fn hold(&mut self) -> Result<Hold, String> {
+        match self.peek() {
+            Some(Token::Ident(w)) if w == "for" => self.pos += 1,
+            _ => return Ok(Hold::Immediately),
+        }
+        let Some(Token::Num(n)) = self.next() else {
+            return Err("za 'for' chýba číslo".into());
+        };
+        match self.next() {
+            Some(Token::Ident(unit)) => match unit.as_str() {
+                "s" | "sec" => Ok(Hold::For(Duration::milliseconds((n * 1000.0) as i64))),
+                "m" | "min" => Ok(Hold::For(Duration::milliseconds((n * 60_000.0) as i64))),
+                "h" => Ok(Hold::For(Duration::milliseconds((n * 3_600_000.0) as i64))),
+                "sample" | "samples" => Ok(Hold::Samples(n.max(1.0) as u32)),
+                other => Err(format!("neznáma jednotka '{}' (s, m, h, samples)", other)),
+            },
+            _ => Err("za 'for' chýba jednotka (s, m, h, samples)".into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server() -> Server {
+        Server { id: 1, name: "web".into(), status: "ON".into(), port: 80, cpu_model: "intel-i5".into(), max_ram: 8.0, source: "sim".into(), interval_ms: 1000, timeout_ms: 2000, host: "127.0.0.1".into(), probe: "auto".into() }
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(
+            tokenize("CPU_usage>=90.5 (").unwrap(),
+            vec![Token::Ident("cpu_usage".into()), Token::Op(">=".into()), Token::Num(90.5), Token::Op("(".into())]
+        );
+        assert_eq!(tokenize("5m").unwrap(), vec![Token::Num(5.0), Token::Ident("m".into())]);
+        assert!(tokenize("cpu_usage > 9 & 1").unwrap_err().contains('&'));
+        assert!(tokenize("cpu_usage > 1.2.3").unwrap_err().contains("1.2.3"));
+    }
+
+    #[test]
+    fn precedence_and_unary_minus() {
+        let c = Condition::parse("ram_usage / max_ram * 100 > 50 - -10").unwrap();
+        let m = Metrics { ram_usage: 6.0, ..Metrics::default() };
+        //6 / 8 * 100 = 75 > 60
+        assert_eq!(c.check(&server(), &m), (75.0, true));
+        let c = Condition::parse("(cpu_usage + 10) * 2 == 100").unwrap();
+        assert!(c.check(&server(), &Metrics { cpu_usage: 40.0, ..Metrics::default() }).1);
+    }
+
+    #[test]
+    fn hold_units() {
+        assert_eq!(Condition::parse("cpu_usage > 90").unwrap().hold, Hold::Immediately);
+        assert_eq!(Condition::parse("cpu_usage > 90 for 5m").unwrap().hold, Hold::For(Duration::minutes(5)));
+        assert_eq!(Condition::parse("cpu_usage > 90 for 30 s").unwrap().hold, Hold::For(Duration::seconds(30)));
+        assert_eq!(Condition::parse("cpu_usage > 90 for 1.5h").unwrap().hold, Hold::For(Duration::minutes(90)));
+        assert_eq!(Condition::parse("cpu_usage > 90 for 3 samples").unwrap().hold, Hold::Samples(3));
+    }
+
+    #[test]
+    fn division_by_zero_never_matches() {
+        let c = Condition::parse("cpu_usage / connections > 1").unwrap();
+        let (value, hit) = c.check(&server(), &Metrics { cpu_usage: 50.0, ..Metrics::default() });
+        assert!(value.is_nan());
+        assert!(!hit);
+        let c = Condition::parse("cpu_usage / connections != 1").unwrap();
+        assert!(c.check(&server(), &Metrics { cpu_usage: 50.0, ..Metrics::default() }).1);
+    }
+
+    #[test]
+    fn malformed() {
+        for (src, needle) in [
+            ("", "neúplný"),
+            ("cpu_usage", "chýba porovnanie"),
+            ("cpu_usage >", "neúplný"),
+            ("cpu_usage + > 5", "neočakávané"),
+            ("(cpu_usage > 5", "chýba ')'"),
+            ("cpu_usage > 5)", "nadbytočný"),
+            ("cpu > 5", "neznáma metrika"),
+            ("cpu_usage > 5 for", "chýba číslo"),
+            ("cpu_usage > 5 for 5", "chýba jednotka"),
+            ("cpu_usage > 5 for 5 days", "neznáma jednotka"),
+            ("cpu_usage > 5 > 3", "nadbytočný"),
+            ("cpu_usage = 5", "neočakávaný znak"),
+        ] {
+            let err = Condition::parse(src).unwrap_err();
+            assert!(err.contains(needle), "{:?}: {}", src, err);
+        }
+    }
+}
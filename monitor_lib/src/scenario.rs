@@ -0,0 +1,212 @@
+//skriptovane incidenty pre simulator (TOML alebo JSON)
+
+use serde::Deserialize;
+use std::path::Path;
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct Scenario {
+    //po uplynuti sa scenar prehra znova od zaciatku
+    #[serde(default)]
+    pub loop_secs: Option<f64>,
+    #[serde(default, rename = "incident")]
+    pub incidents: Vec<Incident>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Incident {
+    pub server: String,
+    pub start_secs: f64,
+    pub duration_secs: f64,
+    #[serde(flatten)]
+    pub kind: IncidentKind,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IncidentKind {
+    CpuSpike { cpu: f32 },
+    MemoryLeak { target: f32 },
+    Latency { extra_ms: i32 },
+    Outage,
+    Flapping { period_secs: f64 },
+}
+
+//vysledok prehrania scenara pre jeden server v danom case
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Effect {
+    pub down: bool,
+    pub cpu: Option<f32>,
+    //(cielovy podiel max_ram, postup 0..1)
+    pub ram_leak: Option<(f32, f32)>,
+    pub extra_ms: i32,
+}
+
+impl Scenario {
+    pub fn load(path: &Path) -> Result<Scenario, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("json")) {
+            serde_json::from_str(&text).map_err(|e| e.to_string())
+        } else {
+            toml::from_str(&text).map_err(|e| e.to_string())
+        }
+    }
+
+    pub fn effect(&self, server_name: &str, elapsed_secs: f64) -> Effect {
+        let t = match self.loop_secs {
+            Some(l) if l > 0.0 => elapsed_secs % l,
+            _ => elapsed_secs,
+        };
+
+        let mut effect = Effect::default();
+        for inc in self.incidents.iter().filter(|i| i.server == server_name) {
+            if t < inc.start_secs || t >= inc.start_secs + inc.duration_secs {
+                continue;
+            }
+            let progress = if inc.duration_secs > 0.0 { ((t - inc.start_secs) / inc.duration_secs) as f32 } else { 1.0 };
+
+            match inc.kind {
+                IncidentKind::CpuSpike { cpu } => effect.cpu = Some(cpu.clamp(0.0, 100.0)),
+                IncidentKind::MemoryLeak { target } => effect.ram_leak = Some((target.clamp(0.0, 1.0), progress)),
+                IncidentKind::Latency { extra_ms } => effect.extra_ms += (extra_ms as f32 * progress) as i32,
+                IncidentKind::Outage => effect.down = true,
+                IncidentKind::Flapping { period_secs } => {
+                    if period_secs > 0.0 && ((t - inc.start_secs) / period_secs) as i64 % 2 == 0 {
+                        effect.down = true;
+                    }
+                }
+            }
+        }
+        effect
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const TOML: &str = r#"
+loop_secs = 100
+
+[[incident]]
+server = "web"
+kind = "cpu_spike"
+cpu = 150.0
+start_secs = 10
+duration_secs = 20
+
+[[incident]]
+server = "web"
+kind = "memory_leak"
+target = 0.9
+start_secs = 40
+duration_secs = 40
+
+[[incident]]
+server = "db"
+kind = "latency"
+extra_ms = 400
+start_secs = 0
+duration_secs = 50
+"#;
+
+    const JSON: &str = r#"{
+  "incident": [
+    { "server": "db", "kind": "outage", "start_secs": 10, "duration_secs": 5 },
+    { "server": "cache", "kind": "flapping", "period_secs": 4, "start_secs": 20, "duration_secs": 16 }
+  ]
+}"#;
+
+    fn write(name: &str, text: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("monitor-test-{}-{}", std::process::id(), name));
+        std::fs::write(&path, text).unwrap();
+        path
+    }
+
+    fn load(name: &str, text: &str) -> Scenario {
+        let path = write(name, text);
+        let scenario = Scenario::load(&path).unwrap();
+        std::fs::remove_file(path).ok();
+        scenario
+    }
+
+    #[test]
+    fn toml_incidents() {
+        let sc = load("scenario.toml", TOML);
+        assert_eq!(sc.loop_secs, Some(100.0));
+        assert_eq!(sc.incidents.len(), 3);
+
+        //cpu sa orezava na 100, koniec intervalu uz neplati
+        assert_eq!(sc.effect("web", 9.9), Effect::default());
+        assert_eq!(sc.effect("web", 10.0).cpu, Some(100.0));
+        assert_eq!(sc.effect("web", 30.0).cpu, None);
+
+        //unik pamate rastie od 0 po 1 k cielu
+        assert_eq!(sc.effect("web", 40.0).ram_leak, Some((0.9, 0.0)));
+        assert_eq!(sc.effect("web", 60.0).ram_leak, Some((0.9, 0.5)));
+        assert_eq!(sc.effect("web", 79.0).ram_leak, Some((0.9, 0.975)));
+        assert_eq!(sc.effect("web", 80.0).ram_leak, None);
+
+        //latencia narasta postupne
+        assert_eq!(sc.effect("db", 0.0).extra_ms, 0);
+        assert_eq!(sc.effect("db", 25.0).extra_ms, 200);
+        assert_eq!(sc.effect("db", 50.0).extra_ms, 0);
+    }
+
+    #[test]
+    fn looping_repeats_from_start() {
+        let sc = load("loop.toml", TOML);
+        assert_eq!(sc.effect("web", 115.0), sc.effect("web", 15.0));
+        assert_eq!(sc.effect("db", 325.0).extra_ms, 200);
+        //bez loop_secs scenar po skonceni uz nic nerobi
+        let once = Scenario { loop_secs: None, ..sc };
+        assert_eq!(once.effect("web", 115.0), Effect::default());
+    }
+
+    #[test]
+    fn json_outage_and_flapping() {
+        let sc = load("scenario.json", JSON);
+        assert_eq!(sc.loop_secs, None);
+        assert!(!sc.effect("db", 9.0).down);
+        assert!(sc.effect("db", 12.0).down);
+        assert!(!sc.effect("db", 15.0).down);
+
+        //prvu periodu dole, druhu hore
+        let flaps: Vec<bool> = [20.0, 23.9, 24.0, 27.9, 28.0, 36.0].iter().map(|t| sc.effect("cache", *t).down).collect();
+        assert_eq!(flaps, [true, true, false, false, true, false]);
+    }
+
+    #[test]
+    fn other_servers_unaffected() {
+        let sc = load("other.toml", TOML);
+        for t in [0.0, 15.0, 45.0, 99.0] {
+            assert_eq!(sc.effect("mail", t), Effect::default());
+            //nazov musi sediet presne
+            assert_eq!(sc.effect("Web", t), Effect::default());
+        }
+    }
+
+    #[test]
+    fn bad_files_report_errors() {
+        let path = write("bad.toml", "[[incident]]\nserver = \"web\"\nkind = \"meteor\"\nstart_secs = 1\nduration_secs = 1\n");
+        assert!(Scenario::load(&path).is_err());
+        std::fs::remove_file(path).ok();
+        assert!(Scenario::load(Path::new("/nonexistent/scenario.toml")).unwrap_err().contains("/nonexistent/scenario.toml"));
+        //dodavany priklad musi byt platny
+        assert_eq!(Scenario::load(&Path::new(env!("CARGO_MANIFEST_DIR")).join("../scenarios/demo.toml")).unwrap().incidents.len(), 5);
+    }
+
+    #[test]
+    fn memory_leak_ramps_toward_target() {
+        use crate::engine::Simulator;
+        use crate::models::Server;
+        let server = Server { id: 1, name: "web".into(), status: "ON".into(), port: 80, cpu_model: "intel-i5".into(), max_ram: 16.0, source: "sim".into(), interval_ms: 1000, timeout_ms: 2000, host: "127.0.0.1".into(), probe: String::new() };
+        let mut sim = Simulator::seeded(5);
+        sim.set_scenario(Some(load("leak.toml", TOML)));
+        let ram: Vec<f32> = (0..80).map(|_| sim.sample(&server).ram_usage).collect();
+        //na konci uniku je server takmer na 90% max_ram
+        assert!(ram[79] > 16.0 * 0.85, "{}", ram[79]);
+        assert!(ram[79] <= 16.0 * 0.9);
+        assert!(ram[45] < ram[60] && ram[60] < ram[79]);
+    }
+}
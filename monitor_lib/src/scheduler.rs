@@ -0,0 +1,466 @@
+//paralelne vzorkovanie serverov, kazdy s vlastnym intervalom a timeoutom
+
+use crate::db::{get_all_servers, get_cpu_profiles, get_prometheus_sources, open_connection, setting_or};
+use crate::engine::Simulator;
+use crate::models::{Metrics, PrometheusSource, Server};
+use crate::prometheus;
+use crate::agent::AgentCache;
+use crate::latency::{self, LatencyStats};
+use crate::lifecycle;
+use crate::probe::{self, ProbeKind, ProbeStatus};
+use crate::tls::{self, TlsInfo};
+use crate::procfs::LocalCollector;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use tokio::sync::{broadcast, Semaphore};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+#[derive(Debug, Clone)]
+pub struct Sample {
+    pub server: Server,
+    pub metrics: Metrics,
+    pub health: Option<ProbeStatus>,
+    pub tls: Option<TlsInfo>,
+    pub taken_at: DateTime<Utc>,
+    pub timed_out: bool,
+}
+
+//posledna vzorka kazdeho servera, UI z nej iba cita
+pub type SampleCache = Arc<RwLock<HashMap<i32, Sample>>>;
+
+//vysledok jedneho merania zo zdroja
+#[derive(Debug, Clone, Default)]
+pub struct Reading {
+    pub metrics: Metrics,
+    pub health: Option<ProbeStatus>,
+    pub tls: Option<TlsInfo>,
+}
+
+impl From<Metrics> for Reading {
+    fn from(metrics: Metrics) -> Self {
+        Reading { metrics, health: None, tls: None }
+    }
+}
+
+//vyber zdroja metrik podla servers.source
+pub struct Sampler {
+    sim: Mutex<Simulator>,
+    local: Arc<Mutex<LocalCollector>>,
+    prom_sources: RwLock<HashMap<i32, PrometheusSource>>,
+    prom_cpu: Mutex<HashMap<i32, (std::time::Instant, f64)>>,
+    agents: RwLock<Option<AgentCache>>,
+    latency_samples: AtomicUsize,
+    tls_checks: Mutex<HashMap<i32, (std::time::Instant, Option<TlsInfo>)>>,
+}
+
+//certifikat sa meni zriedka, zdroje bez sondy ho kontroluju iba obcas
+const TLS_RECHECK: Duration = Duration::from_secs(600);
+
+impl Sampler {
+    pub fn new(sim: Simulator) -> Self {
+        Sampler {
+            sim: Mutex::new(sim),
+            local: Arc::new(Mutex::new(LocalCollector::new())),
+            prom_sources: RwLock::new(HashMap::new()),
+            prom_cpu: Mutex::new(HashMap::new()),
+            agents: RwLock::new(None),
+            latency_samples: AtomicUsize::new(latency::DEFAULT_SAMPLES),
+            tls_checks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_agent_cache(&self, cache: AgentCache) {
+        *self.agents.write().unwrap() = Some(cache);
+    }
+
+    pub fn set_prometheus_sources(&self, sources: Vec<PrometheusSource>) {
+        *self.prom_sources.write().unwrap() = sources.into_iter().map(|p| (p.server_id, p)).collect();
+    }
+
+    //kolko merani odozvy sa urobi v jednom cykle
+    pub fn set_latency_samples(&self, n: usize) {
+        self.latency_samples.store(n.max(1), Ordering::Relaxed);
+        self.sim.lock().unwrap().set_latency_samples(n);
+    }
+
+    pub fn simulator(&self) -> &Mutex<Simulator> {
+        &self.sim
+    }
+
+    pub async fn sample(&self, server: &Server) -> Reading {
+        if server.source != "sim" && lifecycle::is_inactive(&server.status) {
+            return Reading::default();
+        }
+        let mut reading = self.read_source(server).await;
+        //sonda cita certifikat sama, simulovane servery nemaju skutocny TLS
+        if server.port == 443 && !matches!(server.source.as_str(), "probe" | "sim") {
+            reading.tls = self.tls_info(server).await;
+        }
+        reading
+    }
+
+    async fn tls_info(&self, server: &Server) -> Option<TlsInfo> {
+        if let Some((at, info)) = self.tls_checks.lock().unwrap().get(&server.id)
+            && at.elapsed() < TLS_RECHECK {
+            return info.clone();
+        }
+        let limit = Duration::from_millis(server.timeout_ms.max(2) as u64 / 2);
+        let info = tls::check(&server.host, server.port, limit).await.ok();
+        self.tls_checks.lock().unwrap().insert(server.id, (std::time::Instant::now(), info.clone()));
+        info
+    }
+
+    async fn read_source(&self, server: &Server) -> Reading {
+        let pings = self.latency_samples.load(Ordering::Relaxed);
+        match server.source.as_str() {
+            "local" => {
+                //citanie /proc mimo async vlakien, odozva cez neblokujuce spojenia, aby timeout planovaca platil
+                let (local, id) = (self.local.clone(), server.id);
+                let mut metrics = tokio::task::spawn_blocking(move || local.lock().unwrap().collect_server(id)).await.unwrap_or_default();
+                let limit = Duration::from_millis(server.timeout_ms.max(2) as u64 * 9 / 10);
+                LatencyStats::from_samples(&latency::tcp_samples("127.0.0.1", server.port, pings, limit).await).apply(&mut metrics);
+                metrics.into()
+            }
+            "agent" => {
+                //report starsi ako 3 intervaly = agent je nedostupny
+                let max_age = chrono::Duration::milliseconds((server.interval_ms as i64 * 3).max(5000));
+                let agents = self.agents.read().unwrap();
+                let latest = agents.as_ref().and_then(|c| c.read().unwrap().get(&server.id).copied());
+                match latest {
+                    Some((m, at)) if Utc::now() - at <= max_age => m.into(),
+                    _ => Metrics { response_ms: -1, ..Metrics::default() }.into(),
+                }
+            }
+            "prometheus" => {
+                let Some(source) = self.prom_sources.read().unwrap().get(&server.id).cloned() else {
+                    return Metrics { response_ms: -1, ..Metrics::default() }.into();
+                };
+                let mut prev = self.prom_cpu.lock().unwrap().get(&server.id).copied();
+                let m = prometheus::sample(&source, &mut prev).await;
+                if let Some(p) = prev {
+                    self.prom_cpu.lock().unwrap().insert(server.id, p);
+                }
+                m.into()
+            }
+            "probe" => {
+                let kind = ProbeKind::parse(&server.probe, server.port).unwrap_or(ProbeKind::Tcp);
+                //o nieco kratsie nez timeout planovaca, aby sa stihol vratit dovod
+                //polovica casu na protokol, zvysok na opakovane merania spojenia
+                let limit = Duration::from_millis(server.timeout_ms.max(2) as u64 * 9 / 10);
+                let result = probe::probe(&server.host, server.port, kind, limit / 2).await;
+                let mut metrics = Metrics::default();
+                if matches!(result.status, ProbeStatus::Down(_)) {
+                    metrics.response_ms = -1;
+                } else {
+                    let mut samples = latency::tcp_samples(&server.host, server.port, pings.saturating_sub(1), limit / 2).await;
+                    //do odozvy ide iba cas spojenia, handshake protokolu by merania skreslil
+                    samples.push(result.connect_ms);
+                    LatencyStats::from_samples(&samples).apply(&mut metrics);
+                }
+                Reading { metrics, health: Some(result.status), tls: result.tls }
+            }
+            _ => self.sim.lock().unwrap().sample(server).into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SchedulerConfig {
+    pub max_concurrency: usize,
+    //ako casto sa kontroluje, komu uz vyprsal interval
+    pub tick: Duration,
+    //ako casto sa znova nacitaju servery a nastavenia z DB
+    pub reload: Duration,
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig { max_concurrency: 8, tick: Duration::from_millis(100), reload: Duration::from_secs(1) }
+    }
+}
+
+//uvolni server na dalsie meranie, aj ked uloha spadne
+struct InFlight {
+    set: Arc<Mutex<HashSet<i32>>>,
+    id: i32,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Ok(mut set) = self.set.lock() {
+            set.remove(&self.id);
+        }
+    }
+}
+
+pub struct Scheduler {
+    cache: SampleCache,
+    tx: broadcast::Sender<Sample>,
+    sampler: Arc<Sampler>,
+    handle: JoinHandle<()>,
+}
+
+impl Scheduler {
+    pub fn spawn(config: SchedulerConfig, sampler: Arc<Sampler>) -> Scheduler {
+        let cache: SampleCache = Arc::new(RwLock::new(HashMap::new()));
+        let (tx, _) = broadcast::channel(256);
+        let handle = tokio::spawn(run(config, sampler.clone(), cache.clone(), tx.clone()));
+        Scheduler { cache, tx, sampler, handle }
+    }
+
+    pub fn latest(&self, server_id: i32) -> Option<Sample> {
+        self.cache.read().unwrap().get(&server_id).cloned()
+    }
+
+    pub fn cache(&self) -> SampleCache {
+        self.cache.clone()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Sample> {
+        self.tx.subscribe()
+    }
+
+    pub fn sampler(&self) -> Arc<Sampler> {
+        self.sampler.clone()
+    }
+
+    pub fn stop(&self) {
+        self.handle.abort();
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn run(config: SchedulerConfig, sampler: Arc<Sampler>, cache: SampleCache, tx: broadcast::Sender<Sample>) {
+    //schemu uz vytvoril ten, kto planovac spustil; zamknuta databaza sa skusa znova
+    let mut conn = loop {
+        match open_connection() {
+            Ok(conn) => break conn,
+            Err(_) => tokio::time::sleep(config.reload).await,
+        }
+    };
+    let permits = Arc::new(Semaphore::new(config.max_concurrency.max(1)));
+    let in_flight: Arc<Mutex<HashSet<i32>>> = Arc::new(Mutex::new(HashSet::new()));
+    let mut next_due: HashMap<i32, Instant> = HashMap::new();
+    let mut servers: Vec<Server> = Vec::new();
+    let mut loaded_at: Option<Instant> = None;
+
+    loop {
+        if loaded_at.is_none_or(|t| t.elapsed() >= config.reload) {
+            servers = get_all_servers(&mut conn).unwrap_or_default();
+            if let Ok(profiles) = get_cpu_profiles(&mut conn) {
+                sampler.sim.lock().unwrap().set_profiles(profiles);
+            }
+            if let Ok(sources) = get_prometheus_sources(&mut conn) {
+                sampler.set_prometheus_sources(sources);
+            }
+            sampler.set_latency_samples(setting_or(&mut conn, "latency_samples", latency::DEFAULT_SAMPLES));
+            loaded_at = Some(Instant::now());
+        }
+
+        let now = Instant::now();
+        for s in &servers {
+            if next_due.get(&s.id).is_some_and(|due| *due > now) || !in_flight.lock().unwrap().insert(s.id) {
+                continue;
+            }
+            next_due.insert(s.id, now + Duration::from_millis(s.interval_ms.max(50) as u64));
+
+            let server = s.clone();
+            let guard = InFlight { set: in_flight.clone(), id: s.id };
+            let (sampler, cache, tx, permits) = (sampler.clone(), cache.clone(), tx.clone(), permits.clone());
+            tokio::spawn(async move {
+                let _guard = guard;
+                let Ok(_permit) = permits.acquire_owned().await else { return };
+                let limit = Duration::from_millis(server.timeout_ms.max(1) as u64);
+                let (mut reading, timed_out) = match tokio::time::timeout(limit, sampler.sample(&server)).await {
+                    Ok(r) => (r, false),
+                    Err(_) => (Metrics { response_ms: -1, ..Metrics::default() }.into(), true),
+                };
+                LatencyStats::fill_single(&mut reading.metrics);
+
+                let id = server.id;
+                let sample = Sample { server, metrics: reading.metrics, health: reading.health, tls: reading.tls, taken_at: Utc::now(), timed_out };
+                cache.write().unwrap().insert(id, sample.clone());
+                let _ = tx.send(sample);
+            });
+        }
+
+        //zmazane servery zmiznu aj z cache
+        let alive: HashSet<i32> = servers.iter().map(|s| s.id).collect();
+        next_due.retain(|id, _| alive.contains(id));
+        cache.write().unwrap().retain(|id, _| alive.contains(id));
+
+        tokio::time::sleep(config.tick).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::{test_connection, test_server};
+    use crate::db::{save_prometheus_source, update_interval, update_source, update_status, SqliteConnection};
+    use tokio::io::AsyncReadExt;
+
+    //prvych n vzoriek kazdeho zo serverov
+    async fn collect(scheduler: &Scheduler, ids: &[i32], n: usize) -> HashMap<i32, Vec<String>> {
+        let mut rx = scheduler.subscribe();
+        let mut out: HashMap<i32, Vec<String>> = ids.iter().map(|id| (*id, Vec::new())).collect();
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while out.values().any(|v| v.len() < n) {
+                let Ok(sample) = rx.recv().await else { continue };
+                if let Some(v) = out.get_mut(&sample.server.id) && v.len() < n {
+                    v.push(format!("{:?}", sample.metrics));
+                }
+            }
+        }).await.expect("vzorky neprisli");
+        out
+    }
+
+    fn quick() -> SchedulerConfig {
+        SchedulerConfig { max_concurrency: 4, tick: Duration::from_millis(10), reload: Duration::from_millis(50) }
+    }
+
+    //zapnuty server s danym intervalom a timeoutom
+    fn running(conn: &mut SqliteConnection, prefix: &str, interval: i32, timeout: i32) -> Server {
+        let s = test_server(conn, prefix, 80);
+        update_status(conn, s.id, "ON");
+        update_interval(conn, s.id, interval, timeout).unwrap();
+        s
+    }
+
+    //Prometheus endpoint, ktory spojenie prijme, ale nikdy neodpovie
+    fn stalled_endpoint(conn: &mut SqliteConnection, server_id: i32, port: u16) {
+        update_source(conn, server_id, "prometheus").unwrap();
+        let url = format!("http://127.0.0.1:{}/metrics", port);
+        save_prometheus_source(conn, &PrometheusSource { server_id, url, cpu_series: "cpu".into(), ram_series: "ram".into() }).unwrap();
+    }
+
+    async fn stalling_listener() -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut buf = [0u8; 1024];
+                    while matches!(stream.read(&mut buf).await, Ok(n) if n > 0) {}
+                });
+            }
+        });
+        port
+    }
+
+    //prve vzorky danych serverov, najviac do `limit`
+    async fn first_samples(scheduler: &Scheduler, ids: &[i32], limit: Duration) -> HashMap<i32, Sample> {
+        let mut rx = scheduler.subscribe();
+        let mut out = HashMap::new();
+        let _ = tokio::time::timeout(limit, async {
+            while out.len() < ids.len() {
+                if let Ok(sample) = rx.recv().await && ids.contains(&sample.server.id) {
+                    out.entry(sample.server.id).or_insert(sample);
+                }
+            }
+        }).await;
+        out
+    }
+
+    #[tokio::test]
+    async fn same_seed_same_streams() {
+        let mut conn = test_connection();
+        let ids: Vec<i32> = [80, 27017, 5432].into_iter().map(|port| {
+            let s = test_server(&mut conn, "seeded", port);
+            update_status(&mut conn, s.id, "ON");
+            update_interval(&mut conn, s.id, 50, 1000).unwrap();
+            s.id
+        }).collect();
+
+        let a = Scheduler::spawn(quick(), Arc::new(Sampler::new(Simulator::seeded(9))));
+        let b = Scheduler::spawn(quick(), Arc::new(Sampler::new(Simulator::seeded(9))));
+        let (sa, sb) = tokio::join!(collect(&a, &ids, 8), collect(&b, &ids, 8));
+        assert_eq!(sa, sb);
+        assert_ne!(sa[&ids[0]], sa[&ids[1]]);
+    }
+
+    #[tokio::test]
+    async fn in_flight_released_after_panic() {
+        let set = Arc::new(Mutex::new(HashSet::from([7])));
+        let guard = InFlight { set: set.clone(), id: 7 };
+        let task = tokio::spawn(async move {
+            let _guard = guard;
+            panic!("vzorka spadla");
+        });
+        assert!(task.await.is_err());
+        assert!(set.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn each_server_keeps_its_interval() {
+        let mut conn = test_connection();
+        let fast = running(&mut conn, "interval-fast", 50, 1000).id;
+        let slow = running(&mut conn, "interval-slow", 400, 1000).id;
+        let scheduler = Scheduler::spawn(SchedulerConfig { max_concurrency: 16, ..quick() }, Arc::new(Sampler::new(Simulator::seeded(1))));
+
+        let mut rx = scheduler.subscribe();
+        let mut counts: HashMap<i32, usize> = HashMap::new();
+        let end = Instant::now() + Duration::from_millis(1200);
+        while let Ok(received) = tokio::time::timeout_at(end, rx.recv()).await {
+            if let Ok(sample) = received {
+                *counts.entry(sample.server.id).or_default() += 1;
+            }
+        }
+        let (fast, slow) = (counts.get(&fast).copied().unwrap_or(0), counts.get(&slow).copied().unwrap_or(0));
+        //1.2 s: pri 400 ms tri az styri vzorky, pri 50 ms okolo dvadsat
+        assert!((2..=4).contains(&slow), "pomaly server: {}", slow);
+        assert!(fast >= 12 && fast >= slow * 4, "rychly {} / pomaly {}", fast, slow);
+    }
+
+    #[tokio::test]
+    async fn stalled_source_times_out() {
+        let mut conn = test_connection();
+        let port = stalling_listener().await;
+        let stalled = running(&mut conn, "timeout", 50, 150);
+        stalled_endpoint(&mut conn, stalled.id, port);
+        let healthy = running(&mut conn, "timeout-sim", 50, 1000).id;
+        let scheduler = Scheduler::spawn(quick(), Arc::new(Sampler::new(Simulator::seeded(2))));
+
+        let started = Instant::now();
+        let samples = first_samples(&scheduler, &[stalled.id, healthy], Duration::from_secs(5)).await;
+        let sample = &samples[&stalled.id];
+        assert!(started.elapsed() >= Duration::from_millis(150));
+        assert!(sample.timed_out);
+        let m = &sample.metrics;
+        assert_eq!((m.response_ms, m.rtt_max, m.rtt_p95, m.loss_pct), (-1, -1, -1, 100.0));
+        assert!(!samples[&healthy].timed_out);
+    }
+
+    #[tokio::test]
+    async fn concurrency_is_limited() {
+        let mut conn = test_connection();
+        let port = stalling_listener().await;
+        let mut ids = Vec::new();
+        for _ in 0..6 {
+            let s = running(&mut conn, "permits", 50, 300);
+            stalled_endpoint(&mut conn, s.id, port);
+            ids.push(s.id);
+        }
+        let scheduler = Scheduler::spawn(SchedulerConfig { max_concurrency: 2, ..quick() }, Arc::new(Sampler::new(Simulator::seeded(3))));
+        let samples = first_samples(&scheduler, &ids, Duration::from_secs(10)).await;
+        assert_eq!(samples.len(), ids.len());
+        assert!(samples.values().all(|s| s.timed_out));
+
+        //kazde meranie drzi povolenie 300 ms pred taken_at, s rezervou 50 ms sa prekryvaju najviac dve
+        let mut edges: Vec<(DateTime<Utc>, i32)> = samples.values()
+            .flat_map(|s| [(s.taken_at - chrono::Duration::milliseconds(250), 1), (s.taken_at, -1)])
+            .collect();
+        edges.sort();
+        let peak = edges.iter().scan(0, |open, (_, d)| { *open += d; Some(*open) }).max().unwrap();
+        assert!(peak <= 2, "naraz {} merani", peak);
+    }
+}
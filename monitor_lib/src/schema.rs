@@ -9,6 +9,11 @@ diesel::table! {
         port -> Integer,
         cpu_model -> Text,
         max_ram -> Float,
+        source -> Text,
+        interval_ms -> Integer,
+        timeout_ms -> Integer,
+        host -> Text,
+        probe -> Text,
     }
 }
 
@@ -21,5 +26,218 @@ diesel::table! {
         response_ms -> Integer,
         ram_usage -> Float,
         cpu_usage -> Float,
+        disk_usage -> Float,
+        disk_io -> Float,
+        net_rx -> Float,
+        net_tx -> Float,
+        load_avg -> Float,
+        connections -> Integer,
+        rtt_min -> Integer,
+        rtt_max -> Integer,
+        rtt_p95 -> Integer,
+        loss_pct -> Float,
     }
-}
\ No newline at end of file
+}
+
+//katalog CPU profilov pre simulator
+diesel::table! {
+    cpu_profiles (name) {
+        name -> Text,
+        cores -> Integer,
+        efficiency -> Float,
+        base_load -> Float,
+    }
+}
+
+//mapovanie Prometheus serii na metriky servera
+diesel::table! {
+    prometheus_sources (server_id) {
+        server_id -> Integer,
+        url -> Text,
+        cpu_series -> Text,
+        ram_series -> Text,
+    }
+}
+
+//tokeny agentov, ktori posielaju metriky sami
+diesel::table! {
+    agent_tokens (server_id) {
+        server_id -> Integer,
+        token -> Text,
+    }
+}
+
+diesel::joinable!(agent_tokens -> servers (server_id));
+diesel::allow_tables_to_appear_in_same_query!(servers, agent_tokens);
+
+//nastavenia typu kluc = hodnota
+diesel::table! {
+    settings (key) {
+        key -> Text,
+        value -> Text,
+    }
+}
+
+//udalosti serverov (anomalie, ...), kind napr. "anomaly.cpu"
+diesel::table! {
+    events (id) {
+        id -> Integer,
+        server_id -> Integer,
+        timestamp -> Timestamp,
+        kind -> Text,
+        message -> Text,
+    }
+}
+
+diesel::table! {
+    server_tags (server_id, tag) {
+        server_id -> Integer,
+        tag -> Text,
+    }
+}
+
+//scope = "server:<nazov>" / "tag:<tag>", objective v percentach
+diesel::table! {
+    slo_targets (id) {
+        id -> Integer,
+        scope -> Text,
+        objective -> Float,
+        window_days -> Integer,
+    }
+}
+
+diesel::table! {
+    alert_rules (id) {
+        id -> Integer,
+        name -> Text,
+        scope -> Text,
+        expr -> Text,
+        channels -> Text,
+        severity -> Text,
+    }
+}
+
+//state = pending / firing / resolved
+diesel::table! {
+    alerts (id) {
+        id -> Integer,
+        rule_id -> Integer,
+        server_id -> Integer,
+        state -> Text,
+        value -> Float,
+        started_at -> Timestamp,
+        fired_at -> Nullable<Timestamp>,
+        resolved_at -> Nullable<Timestamp>,
+        ack_by -> Nullable<Text>,
+        ack_at -> Nullable<Timestamp>,
+        ack_note -> Nullable<Text>,
+        esc_level -> Integer,
+        esc_at -> Nullable<Timestamp>,
+        caused_by -> Nullable<Text>,
+        muted -> Bool,
+    }
+}
+
+//kind = webhook / smtp / exec, config = JSON s doplnkovymi udajmi (napr. from/to)
+diesel::table! {
+    channels (id) {
+        id -> Integer,
+        name -> Text,
+        kind -> Text,
+        target -> Text,
+        config -> Text,
+    }
+}
+
+//kazdy pokus o dorucenie notifikacie
+diesel::table! {
+    deliveries (id) {
+        id -> Integer,
+        alert_id -> Integer,
+        channel_id -> Integer,
+        state -> Text,
+        attempt -> Integer,
+        ok -> Bool,
+        error -> Nullable<Text>,
+        timestamp -> Timestamp,
+    }
+}
+
+//recur = '' (jednorazove) / daily / weekly, cas v UTC
+diesel::table! {
+    maintenance_windows (id) {
+        id -> Integer,
+        scope -> Text,
+        starts_at -> Timestamp,
+        duration_mins -> Integer,
+        recur -> Text,
+        comment -> Text,
+    }
+}
+
+//matcher napr. "rule=cpu-high,server=web*"
+diesel::table! {
+    silences (id) {
+        id -> Integer,
+        matcher -> Text,
+        author -> Text,
+        comment -> Text,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+//tiers = skupiny kanalov oddelene ';', kanaly v skupine ','
+diesel::table! {
+    escalation_policies (id) {
+        id -> Integer,
+        name -> Text,
+        scope -> Text,
+        every_mins -> Integer,
+        tiers -> Text,
+        max_repeats -> Integer,
+    }
+}
+
+//posledny stav detekcie flappingu, since = kedy sa zmenil
+diesel::table! {
+    flap_state (server_id) {
+        server_id -> Integer,
+        flapping -> Bool,
+        change_pct -> Float,
+        since -> Timestamp,
+    }
+}
+
+//server_id potrebuje depends_on (napr. aplikacia -> databaza)
+diesel::table! {
+    server_dependencies (server_id, depends_on) {
+        server_id -> Integer,
+        depends_on -> Integer,
+    }
+}
+
+//unit = systemd sluzba (systemctl start/stop/restart), inak shell prikazy
+diesel::table! {
+    lifecycle_commands (server_id) {
+        server_id -> Integer,
+        unit -> Text,
+        start_cmd -> Text,
+        stop_cmd -> Text,
+        restart_cmd -> Text,
+        timeout_secs -> Integer,
+        wait_probe -> Bool,
+    }
+}
+
+//prebiehajuci alebo neuspesny prechod, reason je vyplneny iba pri FAILED
+diesel::table! {
+    server_transitions (server_id) {
+        server_id -> Integer,
+        action -> Text,
+        prev_status -> Text,
+        started_at -> Timestamp,
+        deadline -> Timestamp,
+        reason -> Nullable<Text>,
+    }
+}
@@ -0,0 +1,221 @@
+//dostupnost zo zmien stavu a neuspesnych merani, SLO a error budget
+
+use crate::db::{get_all_servers, get_failed_samples, get_server_events, get_slo_targets, get_tags, last_event_before, SqliteConnection};
+use crate::lifecycle;
+use crate::models::{Scope, Server, SloTarget};
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::QueryResult;
+use std::collections::HashMap;
+
+//Some(true) = ma bezat, Some(false) = vypadok, None = planovane mimo prevadzky
+fn status_class(status: &str) -> Option<bool> {
+    match status {
+        "ON" => Some(true),
+        //neuspesny prechod sa rata ako vypadok
+        lifecycle::FAILED => Some(false),
+        //OFF, novy server, prechody a neznamy stav sa do dostupnosti nerataju
+        _ => None,
+    }
+}
+
+fn secs(from: NaiveDateTime, to: NaiveDateTime) -> f64 {
+    ((to - from).num_milliseconds() as f64 / 1000.0).max(0.0)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Uptime {
+    //cas, ked mal server bezat
+    pub measured_secs: f64,
+    pub down_secs: f64,
+}
+
+impl Uptime {
+    //v percentach, None = ziadne data
+    pub fn availability(&self) -> Option<f64> {
+        (self.measured_secs > 0.0).then(|| (1.0 - self.down_secs / self.measured_secs) * 100.0)
+    }
+
+    pub fn add(&mut self, other: Uptime) {
+        self.measured_secs += other.measured_secs;
+        self.down_secs += other.down_secs;
+    }
+
+    //1.0 = budget sa minie presne za okno SLO
+    pub fn burn_rate(&self, objective: f64) -> Option<f64> {
+        let allowed = 1.0 - objective / 100.0;
+        (self.measured_secs > 0.0 && allowed > 0.0).then(|| self.down_secs / self.measured_secs / allowed)
+    }
+}
+
+pub fn server_uptime(conn: &mut SqliteConnection, server: &Server, since: NaiveDateTime, until: NaiveDateTime) -> QueryResult<Uptime> {
+    //stav na zaciatku okna; bez jedinej udalosti (stara databaza) plati aktualny stav
+    let events = get_server_events(conn, server.id, "status", since, until)?;
+    let mut status = last_event_before(conn, server.id, "status", since)?.map(|e| e.message);
+    if status.is_none() && events.is_empty() {
+        status = Some(server.status.clone());
+    }
+
+    let mut segments = Vec::new();
+    let mut start = since;
+    for e in events {
+        segments.push((start, e.timestamp, status.take()));
+        start = e.timestamp;
+        status = Some(e.message);
+    }
+    segments.push((start, until, status));
+
+    let mut up = Uptime::default();
+    let mut on = Vec::new();
+    for (from, to, st) in segments {
+        match st.as_deref().and_then(status_class) {
+            Some(true) => {
+                up.measured_secs += secs(from, to);
+                on.push((from, to));
+            }
+            Some(false) => {
+                up.measured_secs += secs(from, to);
+                up.down_secs += secs(from, to);
+            }
+            None => {}
+        }
+    }
+
+    //neuspesne meranie plati do dalsieho, najviac 3 intervaly
+    let cap = Duration::milliseconds((server.interval_ms as i64 * 3).max(5000));
+    for (failed_at, next) in get_failed_samples(conn, server.id, since, until)? {
+        let end = next.unwrap_or(until).min(failed_at + cap).min(until);
+        for (from, to) in &on {
+            let (a, b) = (failed_at.max(*from), end.min(*to));
+            if a < b {
+                up.down_secs += secs(a, b);
+            }
+        }
+    }
+    Ok(up)
+}
+
+#[derive(Debug, Clone)]
+pub struct SloReport {
+    pub target: SloTarget,
+    pub servers: Vec<String>,
+    pub window: Uptime,
+    //posledna hodina, rychle palenie budgetu
+    pub recent: Uptime,
+}
+
+impl SloReport {
+    pub fn availability(&self) -> Option<f64> {
+        self.window.availability()
+    }
+
+    //kolko % error budgetu este ostava (zaporne = prekroceny)
+    pub fn budget_remaining(&self) -> Option<f64> {
+        let allowed = (1.0 - self.target.objective as f64 / 100.0) * self.window.measured_secs;
+        (allowed > 0.0).then(|| (1.0 - self.window.down_secs / allowed) * 100.0)
+    }
+
+    pub fn burn_rate(&self) -> Option<f64> {
+        self.window.burn_rate(self.target.objective as f64)
+    }
+
+    pub fn recent_burn_rate(&self) -> Option<f64> {
+        self.recent.burn_rate(self.target.objective as f64)
+    }
+
+    pub fn breached(&self) -> bool {
+        self.availability().is_some_and(|a| a < self.target.objective as f64)
+    }
+}
+
+pub fn evaluate(conn: &mut SqliteConnection, target: &SloTarget, servers: &[Server], tags: &HashMap<i32, Vec<String>>) -> QueryResult<SloReport> {
+    let now = Utc::now().naive_utc();
+    let since = now - Duration::days(target.window_days.max(1) as i64);
+    let scope = Scope::parse(&target.scope);
+
+    let mut report = SloReport { target: target.clone(), servers: Vec::new(), window: Uptime::default(), recent: Uptime::default() };
+    for s in servers {
+        let server_tags = tags.get(&s.id).map(Vec::as_slice).unwrap_or_default();
+        if !scope.as_ref().is_some_and(|sc| sc.matches(s, server_tags)) {
+            continue;
+        }
+        report.servers.push(s.name.clone());
+        report.window.add(server_uptime(conn, s, since, now)?);
+        report.recent.add(server_uptime(conn, s, now - Duration::hours(1), now)?);
+    }
+    Ok(report)
+}
+
+pub fn evaluate_all(conn: &mut SqliteConnection) -> QueryResult<Vec<SloReport>> {
+    let servers = get_all_servers(conn)?;
+    let tags = get_tags(conn)?;
+    get_slo_targets(conn)?.iter().map(|t| evaluate(conn, t, &servers, &tags)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::tests::{test_connection, test_server};
+    use crate::schema::{events, history};
+    use diesel::prelude::*;
+
+    fn at(base: NaiveDateTime, secs: i64) -> NaiveDateTime {
+        base + Duration::seconds(secs)
+    }
+
+    fn status_at(conn: &mut SqliteConnection, server: &Server, ts: NaiveDateTime, status: &str) {
+        diesel::insert_into(events::table)
+            .values((events::server_id.eq(server.id), events::timestamp.eq(ts), events::kind.eq("status"), events::message.eq(status)))
+            .execute(conn)
+            .unwrap();
+    }
+
+    fn sample_at(conn: &mut SqliteConnection, server: &Server, ts: NaiveDateTime, response_ms: i32) {
+        diesel::insert_into(history::table)
+            .values((history::server_id.eq(server.id), history::timestamp.eq(ts), history::response_ms.eq(response_ms), history::ram_usage.eq(1.0), history::cpu_usage.eq(1.0)))
+            .execute(conn)
+            .unwrap();
+    }
+
+    #[test]
+    fn statuses_are_explicit() {
+        assert_eq!(status_class("ON"), Some(true));
+        assert_eq!(status_class("FAILED"), Some(false));
+        for s in ["OFF", "/", "Starting", "STOPPING", "Restarting", "niečo"] {
+            assert_eq!(status_class(s), None, "{}", s);
+        }
+    }
+
+    #[test]
+    fn failed_samples_count_until_next() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "slo", 80);
+        let base = Utc::now().naive_utc() - Duration::hours(2);
+        status_at(&mut conn, &server, base, "ON");
+        sample_at(&mut conn, &server, at(base, 100), 10);
+        //vypadok 2s do dalsieho uspesneho merania
+        sample_at(&mut conn, &server, at(base, 200), -1);
+        sample_at(&mut conn, &server, at(base, 202), 10);
+        //posledne neuspesne meranie plati najviac 3 intervaly (min. 5s)
+        sample_at(&mut conn, &server, at(base, 500), -1);
+
+        let up = server_uptime(&mut conn, &server, base, at(base, 1000)).unwrap();
+        assert_eq!(up.measured_secs, 1000.0);
+        assert_eq!(up.down_secs, 2.0 + 5.0);
+    }
+
+    #[test]
+    fn failed_status_is_downtime_unknown_is_not() {
+        let mut conn = test_connection();
+        let server = test_server(&mut conn, "slo", 80);
+        let base = Utc::now().naive_utc() - Duration::hours(2);
+        status_at(&mut conn, &server, base, "ON");
+        status_at(&mut conn, &server, at(base, 100), "FAILED");
+        status_at(&mut conn, &server, at(base, 150), "Starting");
+        status_at(&mut conn, &server, at(base, 160), "ON");
+        status_at(&mut conn, &server, at(base, 200), "niečo");
+
+        let up = server_uptime(&mut conn, &server, base, at(base, 300)).unwrap();
+        assert_eq!(up.measured_secs, 100.0 + 50.0 + 40.0);
+        assert_eq!(up.down_secs, 50.0);
+    }
+}
@@ -0,0 +1,242 @@
+//jednoduche sablony v style handlebars pre texty notifikacii
+//{{ cesta.k.hodnote }}, {{ cesta | upper }}, {{#each zoznam}}..{{/each}}, {{#if cesta}}..{{else}}..{{/if}}
+
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Upper,
+    Lower,
+    Round,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Text(String),
+    Var(String, Vec<Filter>),
+    Each(String, Vec<Node>),
+    If(String, Vec<Node>, Vec<Node>),
+}
+
+#[derive(Debug, Clone)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+//text alebo obsah {{ ... }} bez zatvoriek
+enum Token<'a> {
+    Text(&'a str),
+    Tag(&'a str),
+}
+
+fn tokenize(src: &str) -> Result<Vec<Token<'_>>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = src;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(&rest[..start]));
+        }
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or("neuzavretá značka {{")?;
+        tokens.push(Token::Tag(after[..end].trim()));
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest));
+    }
+    Ok(tokens)
+}
+
+fn parse_var(tag: &str) -> Result<Node, String> {
+    let mut parts = tag.split('|').map(str::trim);
+    let path = parts.next().unwrap_or_default();
+    if path.is_empty() {
+        return Err("prázdna značka {{}}".into());
+    }
+    let filters = parts
+        .map(|f| match f {
+            "upper" => Ok(Filter::Upper),
+            "lower" => Ok(Filter::Lower),
+            "round" => Ok(Filter::Round),
+            other => Err(format!("neznámy filter '{}' (upper, lower, round)", other)),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Node::Var(path.to_string(), filters))
+}
+
+//vrati uzly po ukoncovaciu znacku a jej nazov
+fn parse_block<'a>(tokens: &mut std::slice::Iter<'_, Token<'a>>, stop: &[&str]) -> Result<(Vec<Node>, Option<&'a str>), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(t) => {
+                nodes.push(Node::Text(t.to_string()));
+                continue;
+            }
+            Token::Tag(tag) => *tag,
+        };
+        if stop.contains(&tag) {
+            return Ok((nodes, Some(tag)));
+        }
+        if let Some(path) = tag.strip_prefix("#each ") {
+            let (body, end) = parse_block(tokens, &["/each"])?;
+            end.ok_or("chýba {{/each}}")?;
+            nodes.push(Node::Each(path.trim().to_string(), body));
+        } else if let Some(path) = tag.strip_prefix("#if ") {
+            let (then, end) = parse_block(tokens, &["else", "/if"])?;
+            let otherwise = match end {
+                Some("else") => {
+                    let (otherwise, end) = parse_block(tokens, &["/if"])?;
+                    end.ok_or("chýba {{/if}}")?;
+                    otherwise
+                }
+                Some(_) => Vec::new(),
+                None => return Err("chýba {{/if}}".into()),
+            };
+            nodes.push(Node::If(path.trim().to_string(), then, otherwise));
+        } else if tag.starts_with('#') || tag.starts_with('/') || tag == "else" {
+            return Err(format!("neočakávaná značka {{{{{}}}}}", tag));
+        } else {
+            nodes.push(parse_var(tag)?);
+        }
+    }
+    Ok((nodes, None))
+}
+
+//hodnota podla cesty, hlada sa od najvnutornejsieho each smerom von
+fn lookup<'a>(scopes: &[&'a Value], path: &str) -> Option<&'a Value> {
+    if path == "this" {
+        return scopes.last().copied();
+    }
+    scopes.iter().rev().find_map(|scope| {
+        path.split('.').try_fold(*scope, |v, key| v.get(key))
+    })
+}
+
+fn truthy(v: Option<&Value>) -> bool {
+    match v {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_f64().is_some_and(|f| f != 0.0),
+        Some(Value::String(s)) => !s.is_empty(),
+        Some(Value::Array(a)) => !a.is_empty(),
+        Some(Value::Object(_)) => true,
+    }
+}
+
+//desatinne cisla na 2 miesta, ako v povodnych textoch
+fn format_value(v: Option<&Value>, filters: &[Filter]) -> String {
+    let mut out = match v {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Number(n)) if filters.contains(&Filter::Round) => format!("{:.0}", n.as_f64().unwrap_or_default()),
+        Some(Value::Number(n)) if n.is_f64() => format!("{:.2}", n.as_f64().unwrap_or_default()),
+        Some(other) => other.to_string(),
+    };
+    for f in filters {
+        match f {
+            Filter::Upper => out = out.to_uppercase(),
+            Filter::Lower => out = out.to_lowercase(),
+            Filter::Round => {}
+        }
+    }
+    out
+}
+
+fn render_nodes(nodes: &[Node], scopes: &mut Vec<&Value>, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(t) => out.push_str(t),
+            Node::Var(path, filters) => out.push_str(&format_value(lookup(scopes, path), filters)),
+            Node::Each(path, body) => {
+                if let Some(Value::Array(items)) = lookup(scopes, path) {
+                    for item in items {
+                        scopes.push(item);
+                        render_nodes(body, scopes, out);
+                        scopes.pop();
+                    }
+                }
+            }
+            Node::If(path, then, otherwise) => {
+                let branch = if truthy(lookup(scopes, path)) { then } else { otherwise };
+                render_nodes(branch, scopes, out);
+            }
+        }
+    }
+}
+
+impl Template {
+    pub fn parse(src: &str) -> Result<Template, String> {
+        let tokens = tokenize(src)?;
+        let (nodes, end) = parse_block(&mut tokens.iter(), &[])?;
+        match end {
+            Some(tag) => Err(format!("neočakávaná značka {{{{{}}}}}", tag)),
+            None => Ok(Template { nodes }),
+        }
+    }
+
+    pub fn render(&self, ctx: &Value) -> String {
+        let mut out = String::new();
+        render_nodes(&self.nodes, &mut vec![ctx], &mut out);
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(src: &str, ctx: Value) -> String {
+        Template::parse(src).unwrap().render(&ctx)
+    }
+
+    #[test]
+    fn variables_and_filters() {
+        let ctx = json!({"server": {"name": "Web"}, "value": 91.256, "count": 3, "ok": true});
+        assert_eq!(render("{{ server.name }}: {{value}}", ctx.clone()), "Web: 91.26");
+        assert_eq!(render("{{server.name|upper}} {{ server.name | lower }}", ctx.clone()), "WEB web");
+        assert_eq!(render("{{value | round}}x{{count}} {{ok}}", ctx.clone()), "91x3 true");
+    }
+
+    #[test]
+    fn unknown_key_renders_empty() {
+        let ctx = json!({"server": {"name": "Web"}});
+        assert_eq!(render("[{{missing}}|{{server.port}}|{{server.name.x}}]", ctx), "[||]");
+    }
+
+    #[test]
+    fn each_and_if_sections() {
+        let ctx = json!({"key": "db", "alerts": [{"rule": "cpu", "n": 2}, {"rule": "ram", "n": 1}], "empty": [], "zero": 0});
+        assert_eq!(render("{{#each alerts}}{{rule}}@{{key}}{{#if n}}*{{/if}};{{/each}}", ctx.clone()), "cpu@db*;ram@db*;");
+        assert_eq!(render("{{#each alerts}}{{#each this}}x{{/each}}{{/each}}", ctx.clone()), "");
+        assert_eq!(render("{{#if empty}}a{{else}}b{{/if}}{{#if zero}}c{{else}}d{{/if}}", ctx.clone()), "bd");
+        assert_eq!(render("{{#if key}}{{key|upper}}{{else}}-{{/if}}", ctx), "DB");
+    }
+
+    #[test]
+    fn values_are_not_reparsed_or_escaped() {
+        //hodnota so znackami sa vypise doslovne, HTML ani uvodzovky sa nemenia
+        let ctx = json!({"name": "{{secret}} <b>\"a\" & b</b>", "secret": "X"});
+        assert_eq!(render("{{name}}", ctx.clone()), "{{secret}} <b>\"a\" & b</b>");
+        assert_eq!(render("a { b } c }} d", ctx), "a { b } c }} d");
+    }
+
+    #[test]
+    fn malformed_templates() {
+        for (src, err) in [
+            ("ahoj {{name", "neuzavretá"),
+            ("{{}}", "prázdna"),
+            ("{{x | bold}}", "neznámy filter"),
+            ("{{#each a}}x", "{{/each}}"),
+            ("{{#if a}}x{{else}}y", "{{/if}}"),
+            ("{{#if a}}x", "{{/if}}"),
+            ("x{{/if}}", "neočakávaná"),
+            ("{{else}}", "neočakávaná"),
+            ("{{#each a}}{{/if}}{{/each}}", "neočakávaná"),
+        ] {
+            let e = Template::parse(src).unwrap_err();
+            assert!(e.contains(err), "{}: {}", src, e);
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//kontrola TLS certifikatu: platnost, vydavatel a zhoda s hostname
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use tokio_rustls::rustls::crypto::{self, ring, CryptoProvider};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{ClientConfig, DigitallySignedStruct, Error, SignatureScheme};
+use tokio_rustls::TlsConnector;
+use x509_parser::extensions::GeneralName;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TlsInfo {
+    pub days_left: i64,
+    pub not_after: DateTime<Utc>,
+    pub issuer: String,
+    pub subject: String,
+    pub hostname_ok: bool,
+    pub chain_len: usize,
+    //najskor expirujuci medzicertifikat z tych, ktore server poslal
+    //koren server neposiela, jeho platnost sa nekontroluje
+    pub intermediate: Option<CertExpiry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CertExpiry {
+    pub subject: String,
+    pub not_after: DateTime<Utc>,
+    pub days_left: i64,
+}
+
+//uroven varovania podla prahov v dnoch
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsLevel {
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl TlsInfo {
+    //retazec prestane platit, ked vyprsi ktorykolvek certifikat v nom
+    pub fn min_days_left(&self) -> i64 {
+        self.intermediate.as_ref().map_or(self.days_left, |c| c.days_left.min(self.days_left))
+    }
+
+    pub fn level(&self, warn_days: i64, crit_days: i64) -> TlsLevel {
+        let days_left = self.min_days_left();
+        if days_left < crit_days || !self.hostname_ok {
+            TlsLevel::Critical
+        } else if days_left < warn_days {
+            TlsLevel::Warning
+        } else {
+            TlsLevel::Ok
+        }
+    }
+}
+
+//certifikat chceme precitat aj ked je neplatny, overenie robime sami
+#[derive(Debug)]
+struct InspectOnly(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for InspectOnly {
+    fn verify_server_cert(&self, _: &CertificateDer<'_>, _: &[CertificateDer<'_>], _: &ServerName<'_>, _: &[u8], _: UnixTime) -> Result<ServerCertVerified, Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, Error> {
+        crypto::verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(&self, message: &[u8], cert: &CertificateDer<'_>, dss: &DigitallySignedStruct) -> Result<HandshakeSignatureValid, Error> {
+        crypto::verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+fn connector() -> Result<TlsConnector, String> {
+    let provider = Arc::new(ring::default_provider());
+    let config = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(InspectOnly(provider)))
+        .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+pub async fn handshake(stream: TcpStream, host: &str) -> Result<(TlsStream<TcpStream>, TlsInfo), String> {
+    let name = ServerName::try_from(host.to_string()).map_err(|e| e.to_string())?;
+    let tls = connector()?.connect(name, stream).await.map_err(|e| format!("TLS: {}", e))?;
+
+    let chain = tls.get_ref().1.peer_certificates().ok_or("TLS: server neposlal certifikát")?;
+    let info = inspect(chain, host)?;
+    Ok((tls, info))
+}
+
+//samostatna kontrola certifikatu pre servery, ktore nemeria sonda
+pub async fn check(host: &str, port: i32, limit: Duration) -> Result<TlsInfo, String> {
+    let work = async {
+        let stream = TcpStream::connect(format!("{}:{}", host, port)).await.map_err(|e| e.to_string())?;
+        handshake(stream, host).await.map(|(_, info)| info)
+    };
+    tokio::time::timeout(limit, work).await.unwrap_or_else(|_| Err("TLS: bez odpovede".into()))
+}
+
+fn expiry(cert: &X509Certificate<'_>) -> CertExpiry {
+    let not_after = DateTime::from_timestamp(cert.validity().not_after.timestamp(), 0).unwrap_or_default();
+    CertExpiry { subject: cert.subject().to_string(), not_after, days_left: (not_after - Utc::now()).num_days() }
+}
+
+pub fn inspect(chain: &[CertificateDer<'_>], host: &str) -> Result<TlsInfo, String> {
+    let leaf = chain.first().ok_or("TLS: prázdny reťazec")?;
+    let (_, cert) = X509Certificate::from_der(leaf.as_ref()).map_err(|e| format!("TLS: {}", e))?;
+    let leaf_expiry = expiry(&cert);
+
+    let mut intermediate: Option<CertExpiry> = None;
+    for der in &chain[1..] {
+        let (_, c) = X509Certificate::from_der(der.as_ref()).map_err(|e| format!("TLS: {}", e))?;
+        let e = expiry(&c);
+        if intermediate.as_ref().is_none_or(|cur| e.not_after < cur.not_after) {
+            intermediate = Some(e);
+        }
+    }
+
+    Ok(TlsInfo {
+        days_left: leaf_expiry.days_left,
+        not_after: leaf_expiry.not_after,
+        issuer: cert.issuer().to_string(),
+        subject: leaf_expiry.subject,
+        hostname_ok: matches_host(&cert, host),
+        chain_len: chain.len(),
+        intermediate,
+    })
+}
+
+fn matches_host(cert: &X509Certificate<'_>, host: &str) -> bool {
+    let host = host.trim_end_matches('.').to_lowercase();
+    let ip: Option<IpAddr> = host.parse().ok();
+
+    if let Ok(Some(san)) = cert.subject_alternative_name() {
+        return san.value.general_names.iter().any(|n| match (n, ip) {
+            (GeneralName::DNSName(d), None) => dns_match(d, &host),
+            (GeneralName::IPAddress(raw), Some(ip)) => match ip {
+                IpAddr::V4(v4) => *raw == v4.octets().as_slice(),
+                IpAddr::V6(v6) => *raw == v6.octets().as_slice(),
+            },
+            _ => false,
+        });
+    }
+
+    //bez SAN sa porovna CN
+    cert.subject().iter_common_name()
+        .filter_map(|cn| cn.as_str().ok())
+        .any(|cn| dns_match(cn, &host))
+}
+
+fn dns_match(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim_end_matches('.').to_lowercase();
+    match pattern.strip_prefix("*.") {
+        //wildcard pokryva presne jednu uroven
+        Some(suffix) => host.split_once('.').is_some_and(|(label, rest)| !label.is_empty() && rest == suffix),
+        None => pattern == host,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Datelike;
+    use rcgen::{date_time_ymd, BasicConstraints, CertificateParams, DnType, IsCa, Issuer, KeyPair};
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+    use tokio_rustls::rustls::pki_types::{PrivateKeyDer, PrivatePkcs8KeyDer};
+    use tokio_rustls::rustls::ServerConfig;
+    use tokio_rustls::TlsAcceptor;
+
+    fn params(names: &[&str], cn: &str, days: i64) -> CertificateParams {
+        let mut p = CertificateParams::new(names.iter().map(|n| n.to_string()).collect::<Vec<_>>()).unwrap();
+        p.distinguished_name.push(DnType::CommonName, cn);
+        let end = Utc::now() + chrono::Duration::days(days);
+        p.not_after = date_time_ymd(end.year(), end.month() as u8, end.day() as u8);
+        p
+    }
+
+    //lokalny TLS server s danym retazcom, prijme jedno spojenie
+    async fn serve(chain: Vec<CertificateDer<'static>>, key: &KeyPair) -> u16 {
+        let key = PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der()));
+        let config = ServerConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(chain, key)
+            .unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (sock, _) = listener.accept().await.unwrap();
+            if let Ok(mut tls) = acceptor.accept(sock).await {
+                let _ = tls.shutdown().await;
+            }
+        });
+        port
+    }
+
+    async fn self_signed(names: &[&str], days: i64) -> u16 {
+        let key = KeyPair::generate().unwrap();
+        let cert = params(names, "monitor test", days).self_signed(&key).unwrap();
+        serve(vec![cert.der().clone()], &key).await
+    }
+
+    #[tokio::test]
+    async fn self_signed_expiry() {
+        let port = self_signed(&["localhost"], 20).await;
+        let info = check("localhost", port as i32, Duration::from_secs(5)).await.unwrap();
+        assert!((19..=20).contains(&info.days_left), "{}", info.days_left);
+        assert!(info.hostname_ok);
+        assert_eq!(info.chain_len, 1);
+        assert_eq!(info.intermediate, None);
+        assert_eq!(info.issuer, info.subject);
+        assert_eq!(info.level(30, 7), TlsLevel::Warning);
+        assert_eq!(info.level(10, 7), TlsLevel::Ok);
+    }
+
+    #[tokio::test]
+    async fn hostname_mismatch_is_critical() {
+        let port = self_signed(&["example.com"], 365).await;
+        let info = check("127.0.0.1", port as i32, Duration::from_secs(5)).await.unwrap();
+        assert!(!info.hostname_ok);
+        assert_eq!(info.level(30, 7), TlsLevel::Critical);
+    }
+
+    #[tokio::test]
+    async fn ip_san_matches() {
+        let port = self_signed(&["127.0.0.1"], 365).await;
+        let info = check("127.0.0.1", port as i32, Duration::from_secs(5)).await.unwrap();
+        assert!(info.hostname_ok);
+    }
+
+    #[tokio::test]
+    async fn intermediate_expiring_first() {
+        let ca_key = KeyPair::generate().unwrap();
+        let mut ca_params = params(&[], "monitor test CA", 5);
+        ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = ca_params.self_signed(&ca_key).unwrap();
+        let issuer = Issuer::new(ca_params, &ca_key);
+
+        let leaf_key = KeyPair::generate().unwrap();
+        let leaf = params(&["localhost"], "localhost", 90).signed_by(&leaf_key, &issuer).unwrap();
+        let port = serve(vec![leaf.der().clone(), ca.der().clone()], &leaf_key).await;
+
+        let info = check("localhost", port as i32, Duration::from_secs(5)).await.unwrap();
+        assert_eq!(info.chain_len, 2);
+        assert!(info.days_left >= 89);
+        assert!(info.issuer.contains("monitor test CA"));
+        let ca_expiry = info.intermediate.clone().expect("medzicertifikát");
+        assert!((4..=5).contains(&ca_expiry.days_left), "{}", ca_expiry.days_left);
+        assert!(ca_expiry.subject.contains("monitor test CA"));
+        assert_eq!(info.min_days_left(), ca_expiry.days_left);
+        assert_eq!(info.level(30, 7), TlsLevel::Critical);
+    }
+
+    #[tokio::test]
+    async fn plain_tcp_is_error() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut sock, _) = listener.accept().await.unwrap();
+            let _ = sock.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        });
+        assert!(check("127.0.0.1", port as i32, Duration::from_secs(5)).await.is_err());
+    }
+
+    #[test]
+    fn wildcard_covers_one_label() {
+        assert!(dns_match("*.example.com", "www.example.com"));
+        assert!(dns_match("Example.COM.", "example.com"));
+        assert!(!dns_match("*.example.com", "example.com"));
+        assert!(!dns_match("*.example.com", "a.b.example.com"));
+    }
+}
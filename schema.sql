@@ -4,7 +4,12 @@ CREATE TABLE IF NOT EXISTS servers (
     status TEXT NOT NULL DEFAULT '/', 
     port INTEGER NOT NULL,          
     cpu_model TEXT NOT NULL,         
-    max_ram REAL NOT NULL           
+    max_ram REAL NOT NULL,
+    source TEXT NOT NULL DEFAULT 'sim',
+    interval_ms INTEGER NOT NULL DEFAULT 1000,
+    timeout_ms INTEGER NOT NULL DEFAULT 2000,
+    host TEXT NOT NULL DEFAULT '127.0.0.1',
+    probe TEXT NOT NULL DEFAULT 'auto'
 );
 
 CREATE TABLE IF NOT EXISTS history (
@@ -14,5 +19,182 @@ CREATE TABLE IF NOT EXISTS history (
     response_ms INTEGER NOT NULL,    
     ram_usage REAL NOT NULL,         
     cpu_usage REAL NOT NULL,         
+    disk_usage REAL NOT NULL DEFAULT 0,
+    disk_io REAL NOT NULL DEFAULT 0,
+    net_rx REAL NOT NULL DEFAULT 0,
+    net_tx REAL NOT NULL DEFAULT 0,
+    load_avg REAL NOT NULL DEFAULT 0,
+    connections INTEGER NOT NULL DEFAULT 0,
+    rtt_min INTEGER NOT NULL DEFAULT 0,
+    rtt_max INTEGER NOT NULL DEFAULT 0,
+    rtt_p95 INTEGER NOT NULL DEFAULT 0,
+    loss_pct REAL NOT NULL DEFAULT 0,
     FOREIGN KEY(server_id) REFERENCES servers(id)
-);
\ No newline at end of file
+);
+
+CREATE INDEX IF NOT EXISTS history_server_time ON history(server_id, timestamp);
+
+CREATE TABLE IF NOT EXISTS cpu_profiles (
+    name TEXT PRIMARY KEY,
+    cores INTEGER NOT NULL,
+    efficiency REAL NOT NULL,
+    base_load REAL NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS prometheus_sources (
+    server_id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL,
+    cpu_series TEXT NOT NULL DEFAULT 'process_cpu_seconds_total',
+    ram_series TEXT NOT NULL DEFAULT 'process_resident_memory_bytes',
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);
+
+CREATE TABLE IF NOT EXISTS agent_tokens (
+    server_id INTEGER PRIMARY KEY,
+    token TEXT NOT NULL,
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);
+
+CREATE TABLE IF NOT EXISTS settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    server_id INTEGER NOT NULL,
+    timestamp DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    kind TEXT NOT NULL,
+    message TEXT NOT NULL,
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);
+
+CREATE INDEX IF NOT EXISTS events_server_kind_time ON events(server_id, kind, timestamp);
+
+CREATE TABLE IF NOT EXISTS server_tags (
+    server_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY(server_id, tag),
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);
+
+CREATE TABLE IF NOT EXISTS slo_targets (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    scope TEXT NOT NULL,
+    objective REAL NOT NULL,
+    window_days INTEGER NOT NULL DEFAULT 30
+);
+
+CREATE TABLE IF NOT EXISTS alert_rules (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    scope TEXT NOT NULL DEFAULT 'all',
+    expr TEXT NOT NULL,
+    channels TEXT NOT NULL DEFAULT '',
+    severity TEXT NOT NULL DEFAULT 'warning'
+);
+
+CREATE TABLE IF NOT EXISTS alerts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    rule_id INTEGER NOT NULL,
+    server_id INTEGER NOT NULL,
+    state TEXT NOT NULL DEFAULT 'pending',
+    value REAL NOT NULL,
+    started_at DATETIME NOT NULL,
+    fired_at DATETIME,
+    resolved_at DATETIME,
+    ack_by TEXT,
+    ack_at DATETIME,
+    ack_note TEXT,
+    esc_level INTEGER NOT NULL DEFAULT 0,
+    esc_at DATETIME,
+    caused_by TEXT,
+    muted BOOLEAN NOT NULL DEFAULT 0,
+    FOREIGN KEY(rule_id) REFERENCES alert_rules(id),
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);
+
+CREATE TABLE IF NOT EXISTS channels (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,
+    target TEXT NOT NULL,
+    config TEXT NOT NULL DEFAULT '{}'
+);
+
+CREATE TABLE IF NOT EXISTS deliveries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    alert_id INTEGER NOT NULL,
+    channel_id INTEGER NOT NULL,
+    state TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    ok BOOLEAN NOT NULL,
+    error TEXT,
+    timestamp DATETIME NOT NULL,
+    FOREIGN KEY(alert_id) REFERENCES alerts(id),
+    FOREIGN KEY(channel_id) REFERENCES channels(id)
+);
+
+CREATE TABLE IF NOT EXISTS maintenance_windows (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    scope TEXT NOT NULL,
+    starts_at DATETIME NOT NULL,
+    duration_mins INTEGER NOT NULL,
+    recur TEXT NOT NULL DEFAULT '',
+    comment TEXT NOT NULL DEFAULT ''
+);
+
+CREATE TABLE IF NOT EXISTS silences (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    matcher TEXT NOT NULL,
+    author TEXT NOT NULL,
+    comment TEXT NOT NULL DEFAULT '',
+    created_at DATETIME NOT NULL,
+    expires_at DATETIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS escalation_policies (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    scope TEXT NOT NULL DEFAULT 'all',
+    every_mins INTEGER NOT NULL,
+    tiers TEXT NOT NULL,
+    max_repeats INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE TABLE IF NOT EXISTS flap_state (
+    server_id INTEGER PRIMARY KEY,
+    flapping BOOLEAN NOT NULL,
+    change_pct REAL NOT NULL,
+    since DATETIME NOT NULL,
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);
+
+CREATE TABLE IF NOT EXISTS server_dependencies (
+    server_id INTEGER NOT NULL,
+    depends_on INTEGER NOT NULL,
+    PRIMARY KEY(server_id, depends_on),
+    FOREIGN KEY(server_id) REFERENCES servers(id),
+    FOREIGN KEY(depends_on) REFERENCES servers(id)
+);
+
+CREATE TABLE IF NOT EXISTS lifecycle_commands (
+    server_id INTEGER PRIMARY KEY,
+    unit TEXT NOT NULL DEFAULT '',
+    start_cmd TEXT NOT NULL DEFAULT '',
+    stop_cmd TEXT NOT NULL DEFAULT '',
+    restart_cmd TEXT NOT NULL DEFAULT '',
+    timeout_secs INTEGER NOT NULL DEFAULT 60,
+    wait_probe BOOLEAN NOT NULL DEFAULT 1,
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);
+
+CREATE TABLE IF NOT EXISTS server_transitions (
+    server_id INTEGER PRIMARY KEY,
+    action TEXT NOT NULL,
+    prev_status TEXT NOT NULL,
+    started_at DATETIME NOT NULL,
+    deadline DATETIME NOT NULL,
+    reason TEXT,
+    FOREIGN KEY(server_id) REFERENCES servers(id)
+);